@@ -0,0 +1,57 @@
+//! The worst-case latency of `update` when every call reports to a slow
+//! sink, with the reports written on the calling thread and with
+//! `with_async_reports`. Memory reporting is off: on a machine with a
+//! single CPU, refreshing the memory usage on the background thread
+//! preempts the measured one.
+//!
+//!     cargo bench --bench latency
+
+use progress_logger::{ProgressLogger, SharedSink};
+use std::time::{Duration, Instant};
+
+/// A sink taking 200 µs per write, like a slow terminal or network mount
+struct Slow;
+
+impl std::io::Write for Slow {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::thread::sleep(Duration::from_micros(200));
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The median and maximum latency of 10 000 updates, each due to report
+fn latencies(async_reports: bool) -> (Duration, Duration) {
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(0))
+        .with_min_report_spacing(Duration::from_millis(0))
+        .with_async_reports(async_reports)
+        .with_memory_reporting(false)
+        .with_sink(SharedSink::new(Slow))
+        .start();
+    let mut latencies: Vec<Duration> = (0..10_000)
+        .map(|_| {
+            let start = Instant::now();
+            pl.update(1u32);
+            start.elapsed()
+        })
+        .collect();
+    pl.stop();
+    latencies.sort();
+    (
+        latencies[latencies.len() / 2],
+        latencies[latencies.len() - 1],
+    )
+}
+
+fn main() {
+    for &async_reports in &[false, true] {
+        let (median, max) = latencies(async_reports);
+        println!(
+            "async reports {:5}: median {:>10.2?}, max {:>10.2?}",
+            async_reports, median, max
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! The cost of `update`, in nanoseconds per call, in hot loops and in slow
+//! ones, against the same loops without a logger.
+//!
+//!     cargo bench --bench update
+
+use progress_logger::ProgressLogger;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The best time per iteration of `f` over 5 runs of `iterations`
+fn measure(iterations: u64, mut f: impl FnMut(u64)) -> f64 {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            for i in 0..iterations {
+                f(i);
+            }
+            start.elapsed().as_nanos() as f64 / iterations as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn logger() -> ProgressLogger {
+    ProgressLogger::builder()
+        .with_frequency(Duration::from_secs(3600))
+        .with_memory_reporting(false)
+        .start()
+}
+
+/// Spins for about a microsecond, standing for the work of a slow loop
+fn work(i: u64) -> u64 {
+    let mut x = i;
+    for _ in 0..200 {
+        x = black_box(x.wrapping_mul(6364136223846793005).wrapping_add(1));
+    }
+    x
+}
+
+#[allow(deprecated)]
+fn main() {
+    const HOT: u64 = 200_000_000;
+    const SLOW: u64 = 1_000_000;
+
+    let mut sum = 0u64;
+    let baseline = measure(HOT, |i| sum = black_box(sum.wrapping_add(i)));
+    let mut pl = logger();
+    let update = measure(HOT, |i| pl.update(black_box(i & 1)));
+    let mut pl = logger();
+    let update_light = measure(HOT, |i| pl.update_light(black_box(i & 1)));
+    println!("hot loop, counter only: {:.3} ns", baseline);
+    println!("hot loop, update:       {:.3} ns", update);
+    println!("hot loop, update_light: {:.3} ns", update_light);
+
+    let slow = measure(SLOW, |i| sum = sum.wrapping_add(work(i)));
+    let mut pl = logger();
+    let slow_update = measure(SLOW, |i| {
+        sum = sum.wrapping_add(work(i));
+        pl.update(1u32);
+    });
+    println!("slow loop, no logger:   {:.1} ns", slow);
+    println!("slow loop, update:      {:.1} ns", slow_update);
+    black_box(sum);
+}
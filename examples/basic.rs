@@ -12,18 +12,7 @@ fn main() {
         .parse::<u64>()
         .expect("n should be an integer");
 
-    info!("Light updates");
-    let mut pl = ProgressLogger::builder()
-        .with_expected_updates(n)
-        .with_frequency(Duration::from_secs(1))
-        .start();
-
-    for _ in 0..n {
-        pl.update_light(1u32);
-    }
-    pl.stop();
-
-    info!("Full updates");
+    info!("Updates");
     let mut pl = ProgressLogger::builder()
         .with_expected_updates(n)
         .with_frequency(Duration::from_secs(1))
@@ -0,0 +1,46 @@
+//! Iterating a server-side database cursor, simulated here, fetching rows
+//! in batches. The number of rows comes from the table statistics, so it
+//! is only an estimate.
+
+use progress_logger::ProgressLogger;
+use std::time::Duration;
+
+/// Stands in for `FETCH 1000 FROM cursor`: the table actually has a few
+/// more rows than the statistics say
+struct Cursor {
+    remaining: u64,
+}
+
+impl Cursor {
+    fn fetch(&mut self, max_rows: u64) -> Vec<u64> {
+        std::thread::sleep(Duration::from_millis(20));
+        let n = self.remaining.min(max_rows);
+        self.remaining -= n;
+        (0..n).collect()
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    // `SELECT reltuples FROM pg_class WHERE relname = 'events'`
+    let estimated_rows = 100_000;
+    let mut cursor = Cursor { remaining: 104_321 };
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("rows")
+        .with_approximate_expectation(true)
+        .with_frequency(Duration::from_millis(500))
+        .start();
+    let mut batches = pl.track_batches(estimated_rows);
+    let mut sum = 0;
+    loop {
+        let rows = cursor.fetch(1000);
+        if rows.is_empty() {
+            break;
+        }
+        batches.batch_fetched(rows.len() as u64);
+        sum += rows.iter().sum::<u64>();
+    }
+    batches.finished();
+    println!("sum: {}", sum);
+}
@@ -0,0 +1,44 @@
+//! Compares the latency of `update` calls triggering a report, with
+//! synchronous and background reports. Run in release mode:
+//!
+//!     cargo run --release --example latency
+use progress_logger::{ProgressLogger, SharedSink};
+use std::time::{Duration, Instant};
+
+const CALLS: usize = 2000;
+
+/// Returns the sorted latencies of `CALLS` updates, each triggering a report
+fn update_latencies(async_reports: bool) -> Vec<Duration> {
+    // With no frequency and no spacing, every update reports
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_secs(0))
+        .with_min_report_spacing(Duration::from_secs(0))
+        .with_async_reports(async_reports)
+        .with_sink(SharedSink::new(std::io::sink()))
+        .start();
+    let mut latencies: Vec<Duration> = (0..CALLS)
+        .map(|_| {
+            // Let the background thread, if any, catch up
+            std::thread::sleep(Duration::from_micros(200));
+            let before = Instant::now();
+            pl.update(1u32);
+            before.elapsed()
+        })
+        .collect();
+    pl.stop();
+    latencies.sort_unstable();
+    latencies
+}
+
+fn main() {
+    for &(name, async_reports) in &[("synchronous", false), ("background", true)] {
+        let latencies = update_latencies(async_reports);
+        println!(
+            "{:>11} reports: median {:>10.2?}, p99 {:>10.2?}, max {:>10.2?}",
+            name,
+            latencies[CALLS / 2],
+            latencies[CALLS * 99 / 100],
+            latencies[CALLS - 1]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Library functions taking an `impl Progress`, so that callers decide
+//! whether to report progress.
+
+use progress_logger::{NoProgress, Progress, ProgressLogger};
+
+/// Counts the distinct words in the given lines
+fn count_distinct_words(lines: &[String], mut pl: impl Progress) -> usize {
+    let mut words = std::collections::HashSet::new();
+    for line in lines {
+        for word in line.split_whitespace() {
+            words.insert(word.to_lowercase());
+        }
+        pl.update(1);
+    }
+    words.len()
+}
+
+fn main() {
+    env_logger::init();
+
+    let lines: Vec<String> = (0..1_000_000)
+        .map(|i| format!("word{} word{} common", i % 1000, i % 7))
+        .collect();
+
+    // A library user who doesn't care about progress
+    let distinct = count_distinct_words(&lines, NoProgress);
+    println!("{} distinct words", distinct);
+
+    // One who does, and keeps using the logger afterwards
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("lines")
+        .with_expected_updates(lines.len() as u64)
+        .start();
+    let distinct = count_distinct_words(&lines, &mut pl);
+    pl.report_now();
+    pl.stop();
+    println!("{} distinct words", distinct);
+}
@@ -0,0 +1,30 @@
+//! Processing the chunks of a vector in parallel, counting the progress of
+//! single items.
+
+use progress_logger::{parallel_chunks, ProgressLogger};
+use std::time::Duration;
+
+fn is_prime(n: u64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+}
+
+fn main() {
+    env_logger::init();
+
+    let numbers: Vec<u64> = (0..5_000_000).collect();
+    let pl = ProgressLogger::builder()
+        .with_items_name("numbers")
+        .with_expected_updates(numbers.len() as u64)
+        .with_frequency(Duration::from_secs(1))
+        .start();
+    let primes = std::sync::atomic::AtomicU64::new(0);
+    parallel_chunks(&numbers, 10_000, pl, |chunk, handle| {
+        for &n in chunk {
+            if is_prime(n) {
+                primes.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            handle.update(1);
+        }
+    });
+    println!("{} primes", primes.into_inner());
+}
@@ -0,0 +1,57 @@
+//! A computation in three phases, each with its own logger, nested in the
+//! logger of the whole computation.
+//!
+//!     RUST_LOG=info cargo run --release --example phases
+
+use progress_logger::ProgressLogger;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const KEYS: u64 = 2_000_000;
+const QUERIES: u64 = 1_000_000;
+
+fn main() {
+    env_logger::init();
+
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("steps")
+        .with_frequency(Duration::from_millis(100))
+        .start();
+
+    let mut index = HashMap::new();
+    let mut child = pl.child_with("build index", |builder| {
+        builder
+            .with_items_name("keys")
+            .with_expected_updates(KEYS)
+    });
+    for key in 0..KEYS {
+        index.insert(key, key.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        child.update(1u32);
+    }
+    child.stop();
+    pl.update(1u32);
+
+    let mut hits = Vec::new();
+    let mut child = pl.child_with("query", |builder| {
+        builder
+            .with_items_name("queries")
+            .with_expected_updates(QUERIES)
+    });
+    for query in 0..QUERIES {
+        if let Some(value) = index.get(&(query * 3)) {
+            hits.push(*value);
+        }
+        child.update(1u32);
+    }
+    // Dropping the child logs its completion statement too
+    drop(child);
+    pl.update(1u32);
+
+    let mut child = pl.child_with("merge", |builder| builder.with_items_name("hits"));
+    hits.sort_unstable();
+    child.update(hits.len() as u64);
+    child.stop();
+    pl.update(1u32);
+
+    pl.stop();
+}
@@ -0,0 +1,48 @@
+//! Counts the nodes of a random tree with a recursive traversal, passing a
+//! `ProgressHandle` down the recursion next to the mutable state.
+//!
+//!     RUST_LOG=info cargo run --release --example tree -- 22
+
+use progress_logger::{ProgressHandle, ProgressLogger};
+use std::time::Duration;
+
+/// Visits a tree in which each node has 0 to 3 children, drawn from the
+/// state of a xorshift generator, down to `depth` levels. Returns the
+/// number of leaves.
+fn visit(depth: u32, rng: &mut u64, pl: &ProgressHandle) -> u64 {
+    pl.update(1u32);
+    if depth == 0 {
+        return 1;
+    }
+    *rng ^= *rng << 13;
+    *rng ^= *rng >> 7;
+    *rng ^= *rng << 17;
+    // Two children on average, so that the tree neither dies out nor explodes
+    let children = match *rng % 8 {
+        0 => 0,
+        1..=2 => 1,
+        3..=6 => 3,
+        _ => 2,
+    };
+    if children == 0 {
+        return 1;
+    }
+    (0..children).map(|_| visit(depth - 1, rng, pl)).sum()
+}
+
+fn main() {
+    env_logger::init();
+
+    let depth = std::env::args()
+        .nth(1)
+        .map(|d| d.parse::<u32>().expect("depth should be an integer"))
+        .unwrap_or(20);
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("nodes")
+        .with_frequency(Duration::from_secs(1))
+        .start();
+    let mut rng = 0x2545_f491_4f6c_dd1d;
+    let leaves = visit(depth, &mut rng, &pl.handle());
+    let report = pl.stop();
+    println!("{} nodes, {} leaves", report.total_items, leaves);
+}
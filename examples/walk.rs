@@ -0,0 +1,28 @@
+//! Hashes the files under a directory, counting them first so that the
+//! reports show the time left.
+//!
+//!     RUST_LOG=info cargo run --release --example walk -- /usr/share
+
+use progress_logger::ProgressLogger;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::time::Duration;
+
+fn main() {
+    env_logger::init();
+
+    let root = std::env::args().nth(1).unwrap_or_else(|| ".".to_owned());
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("files")
+        .with_frequency(Duration::from_secs(1))
+        .start();
+    let mut hasher = DefaultHasher::new();
+    for path in pl.walk_dir(&root) {
+        // Files can vanish between the two walks
+        if let Ok(contents) = std::fs::read(&path) {
+            hasher.write(&contents);
+        }
+    }
+    pl.stop();
+    println!("hash: {:016x}", hasher.finish());
+}
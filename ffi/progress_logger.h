@@ -0,0 +1,66 @@
+/* C bindings for the progress-logger crate, built with the `ffi` feature.
+ * See the documentation of the `ffi` module for ownership and thread-safety
+ * rules. Generated by cbindgen from src/ffi.rs: do not edit. */
+
+#ifndef PROGRESS_LOGGER_H
+#define PROGRESS_LOGGER_H
+
+#include <stdint.h>
+
+typedef struct ProgressLogger ProgressLogger;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a new progress logger and starts its timer. `name` is the name of
+ * the items being counted, and can be `NULL` to use the default. An
+ * `expected` number of updates of zero means that it is unknown, and a
+ * `frequency_ms` of zero selects the default frequency. Returns `NULL` on
+ * failure.
+ *
+ * # Safety
+ *
+ * `name` must be either `NULL` or a valid nul-terminated string.
+ */
+ProgressLogger *pl_new(const char *name, uint64_t expected, uint64_t frequency_ms);
+
+/**
+ * Adds `n` to the counter, see `ProgressLogger::update`.
+ *
+ * # Safety
+ *
+ * `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+ */
+void pl_update(ProgressLogger *pl, uint64_t n);
+
+/**
+ * Same as `pl_update`, kept for compatibility.
+ *
+ * # Safety
+ *
+ * `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+ */
+void pl_update_light(ProgressLogger *pl, uint64_t n);
+
+/**
+ * Logs the completion statement and releases the logger.
+ *
+ * # Safety
+ *
+ * `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+ */
+void pl_stop(ProgressLogger *pl);
+
+/**
+ * Sends the reports to standard error. Returns 0 on success, and -1 if a
+ * logger was already installed.
+ */
+int pl_init_stderr_logging(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PROGRESS_LOGGER_H */
@@ -0,0 +1,38 @@
+/* Exercises the C bindings. Built and run by tests/ffi.rs with:
+ *
+ *   cargo test --features ffi --test ffi
+ *
+ * or by hand with:
+ *
+ *   cargo rustc --release --features ffi --crate-type staticlib
+ *   cc ffi/test.c -Iffi target/release/libprogress_logger.a -lpthread -ldl -lm -o target/ffi-test
+ *   ./target/ffi-test
+ */
+
+#include <stdio.h>
+#include "progress_logger.h"
+
+int main(void) {
+    if (pl_init_stderr_logging() != 0) {
+        fprintf(stderr, "could not install the logger\n");
+        return 1;
+    }
+
+    ProgressLogger *pl = pl_new("items", 10000000, 100);
+    if (pl == NULL) {
+        fprintf(stderr, "could not create the logger\n");
+        return 1;
+    }
+    for (uint64_t i = 0; i < 5000000; i++) {
+        pl_update_light(pl, 1);
+    }
+    for (uint64_t i = 0; i < 5000000; i++) {
+        pl_update(pl, 1);
+    }
+    pl_stop(pl);
+
+    /* NULL pointers are ignored */
+    pl_update(NULL, 1);
+    pl_stop(NULL);
+    return 0;
+}
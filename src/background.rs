@@ -0,0 +1,203 @@
+use crate::clock;
+use crate::emit::emit;
+use crate::fields::{Field, FieldSet};
+use crate::memory;
+use crate::pretty::{DurationFormat, NumberFormat, Unit};
+use crate::sink::{sink_failed, SinkWrite};
+use crate::{SharedSink, SinkErrorPolicy};
+use crate::Level;
+use std::fmt::Write;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The number of reports waiting to be rendered above which new ones are
+/// dropped
+const QUEUE_CAPACITY: usize = 16;
+
+/// The state of the logger at a report, captured by the update path
+pub(crate) struct PendingReport {
+    pub(crate) elapsed: Duration,
+    pub(crate) count: u64,
+    pub(crate) throughput: f64,
+    pub(crate) prediction: Option<f64>,
+    pub(crate) prediction_basis: Option<&'static str>,
+    /// the message of a checkpoint, see `ProgressLogger::checkpoint_with_message`
+    pub(crate) label: Option<String>,
+}
+
+/// What the background thread needs to render the reports
+pub(crate) struct Renderer {
+    pub(crate) items: String,
+    pub(crate) number_format: NumberFormat,
+    pub(crate) duration_format: DurationFormat,
+    pub(crate) unit: Unit,
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) fields: FieldSet,
+    pub(crate) memory_reporting: bool,
+    pub(crate) cpu_display: bool,
+    pub(crate) prefix: Option<String>,
+    pub(crate) sink: Option<SharedSink>,
+}
+
+impl Renderer {
+    pub(crate) fn render(&self, report: &PendingReport, dropped: u64) -> String {
+        let fmt = self.number_format;
+        let mut line = String::new();
+        if self.memory_reporting && self.fields.contains(Field::Memory) {
+            let now = clock::now();
+            if let Some(sample) = memory::sample(now) {
+                write!(
+                    line,
+                    "[{}]",
+                    memory::render(fmt, now, sample, self.cpu_display)
+                )
+                .unwrap();
+            }
+        }
+        if self.fields.contains(Field::Elapsed) {
+            write!(line, " {}", self.duration_format.pretty(report.elapsed)).unwrap();
+        }
+        if self.fields.contains(Field::Count) {
+            write!(line, " {}", self.unit.count(fmt, report.count, &self.items)).unwrap();
+        }
+        if let Some(prediction) = report
+            .prediction
+            .filter(|_| self.fields.contains(Field::TimeLeft))
+        {
+            let left = self.duration_format.time_left(fmt, prediction);
+            write!(line, ", {} left", left).unwrap();
+            if let Some(basis) = report.prediction_basis {
+                write!(line, " {}", basis).unwrap();
+            }
+        }
+        if self.fields.contains(Field::Throughput) {
+            let throughput = self.unit.rate(fmt, report.throughput, &self.items);
+            write!(line, " ({})", throughput).unwrap();
+        }
+        if self.fields.contains(Field::Suppressed) {
+            if dropped > 0 {
+                write!(line, " (+{} reports dropped)", dropped).unwrap();
+            }
+            if let Some(errors) = self
+                .sink
+                .as_ref()
+                .map(SharedSink::errors)
+                .filter(|&e| e > 0)
+            {
+                write!(line, " (sink errors: {})", errors).unwrap();
+            }
+        }
+        let line = crate::trim_separators(line);
+        match report.label.as_ref() {
+            Some(label) => format!("{}: {}", label, line),
+            None => line,
+        }
+    }
+
+    /// Emits the line to the sink if any, or through the `log` crate, which
+    /// also takes over from a sink that panicked. Errors of the sink are
+    /// counted: the logger applies `SinkErrorPolicy::Fail` at its next
+    /// report, on its own thread.
+    pub(crate) fn output(&mut self, line: &str) {
+        let prefixed;
+        let line = match self.prefix.as_ref() {
+            Some(prefix) => {
+                prefixed = format!("{}{}", prefix, line);
+                &prefixed
+            }
+            None => line,
+        };
+        if let Some(sink) = self.sink.as_ref() {
+            match sink.write_line(format_args!("{}", line)) {
+                SinkWrite::Written => return,
+                SinkWrite::Failed { error, first } => {
+                    sink_failed(
+                        SinkErrorPolicy::Count,
+                        &self.target,
+                        &self.items,
+                        sink,
+                        &error,
+                        first,
+                    );
+                    return;
+                }
+                SinkWrite::Panicked => {}
+            }
+            self.sink = None;
+            emit(
+                &self.target,
+                Level::Warn,
+                format_args!(
+                    "The sink of the reports of {} panicked, reporting through the log crate from now on",
+                    self.items
+                ),
+            );
+        }
+        emit(&self.target, self.level, format_args!("{}", line));
+    }
+}
+
+/// Renders and emits the reports on a background thread, see
+/// `with_async_reports`
+pub(crate) struct BackgroundReports {
+    sender: Option<SyncSender<(PendingReport, u64)>>,
+    thread: Option<JoinHandle<()>>,
+    /// the reports dropped since the last one sent
+    dropped: u64,
+}
+
+impl BackgroundReports {
+    pub(crate) fn spawn(renderer: Renderer) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<(PendingReport, u64)>(QUEUE_CAPACITY);
+        let thread = std::thread::Builder::new()
+            .name("progress-logger".to_owned())
+            .spawn(move || {
+                let mut renderer = renderer;
+                for (report, dropped) in receiver {
+                    let line = renderer.render(&report, dropped);
+                    #[cfg(feature = "tracing")]
+                    let _fields = crate::emit::report_fields(
+                        &renderer.items,
+                        report.count,
+                        report.throughput,
+                        report.prediction,
+                    );
+                    renderer.output(&line);
+                }
+            })
+            .expect("failed to spawn the thread of the reports");
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            dropped: 0,
+        }
+    }
+
+    /// Queues the report, dropping it if the queue is full
+    pub(crate) fn send(&mut self, report: PendingReport) {
+        if let Some(sender) = self.sender.as_ref() {
+            match sender.try_send((report, self.dropped)) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => self.dropped += 1,
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+    }
+
+    /// Waits for the queued reports to be emitted
+    pub(crate) fn flush(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            // A panic of the thread only loses reports
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for BackgroundReports {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
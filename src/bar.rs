@@ -0,0 +1,58 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The width of the bar, between the brackets
+const BAR_WIDTH: usize = 30;
+
+/// How the periodic reports are shown, see `with_display`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    /// One line per report, through the sink or the `log` crate
+    #[default]
+    Log,
+    /// A single line on standard error, rewritten at each report, starting
+    /// with a bar if the number of updates is expected, e.g. `[=====>    ]
+    /// 43% 32.00s 1.2M items, 42.45 s left (130k items/s)`. Falls back to
+    /// `Log` if standard error is not a terminal or a sink is set.
+    TerminalBar,
+}
+
+/// Rewrites the periodic reports in place on standard error
+#[derive(Default)]
+pub(crate) struct TerminalBar {
+    /// whether the last line written has no newline yet
+    drawn: AtomicBool,
+}
+
+impl TerminalBar {
+    /// Replaces the line on screen with `line`, preceded by a bar filled to
+    /// `fraction`, if any
+    pub(crate) fn draw(&self, fraction: Option<f64>, line: &str) {
+        let mut buf = String::from("\r\x1B[2K");
+        if let Some(fraction) = fraction {
+            let fraction = fraction.clamp(0.0, 1.0);
+            let filled = (fraction * BAR_WIDTH as f64) as usize;
+            buf.push('[');
+            buf.push_str(&"=".repeat(filled));
+            if filled < BAR_WIDTH {
+                buf.push('>');
+                buf.push_str(&" ".repeat(BAR_WIDTH - filled - 1));
+            }
+            buf.push_str(&format!("] {:.0}% ", fraction * 100.0));
+        }
+        buf.push_str(line);
+        let stderr = std::io::stderr();
+        let mut stderr = stderr.lock();
+        // Failing to draw the bar is not worth interrupting the computation
+        let _ = stderr.write_all(buf.as_bytes());
+        let _ = stderr.flush();
+        self.drawn.store(true, Ordering::Relaxed);
+    }
+
+    /// Ends the line on screen, if any, before other messages are written
+    pub(crate) fn end_line(&self) {
+        if self.drawn.swap(false, Ordering::Relaxed) {
+            let _ = std::io::stderr().write_all(b"\n");
+        }
+    }
+}
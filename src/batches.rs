@@ -0,0 +1,62 @@
+use crate::ProgressLogger;
+
+/// Counts rows fetched in batches, e.g. from a server-side database cursor,
+/// obtained with `ProgressLogger::track_batches`. Reports show the rows
+/// and the batches so far, and both throughputs, e.g. `12 000 rows in 12
+/// batches (1 000.00 rows/s, 1.00 batches/s)`.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// // `SELECT reltuples FROM pg_class WHERE relname = 'events'`
+/// let estimated_rows = 10_000;
+/// let mut pl = ProgressLogger::builder()
+///     .with_items_name("rows")
+///     .with_approximate_expectation(true)
+///     .start();
+/// let mut batches = pl.track_batches(estimated_rows);
+/// // The estimate was a bit low
+/// for _ in 0..11 {
+///     // `FETCH 1000 FROM cursor`
+///     batches.batch_fetched(1000);
+/// }
+/// assert_eq!(batches.finished(), 11);
+/// assert_eq!(pl.snapshot().count, 11_000);
+/// ```
+pub struct BatchTracker<'a> {
+    pl: &'a mut ProgressLogger,
+}
+
+impl BatchTracker<'_> {
+    /// Counts a batch of `rows` rows
+    #[inline]
+    pub fn batch_fetched(&mut self, rows: u64) {
+        *self.pl.batches.get_or_insert(0) += 1;
+        self.pl.update(rows);
+    }
+
+    /// The number of batches fetched so far
+    pub fn batches(&self) -> u64 {
+        self.pl.batches.unwrap_or(0)
+    }
+
+    /// Logs the completion statement, see `ProgressLogger::finish`, and
+    /// returns the number of batches fetched
+    pub fn finished(self) -> u64 {
+        self.pl.finish();
+        self.batches()
+    }
+}
+
+impl ProgressLogger {
+    /// Tracks rows fetched in batches, with `expected_rows` as the
+    /// expected number of updates, replacing the one set when building the
+    /// logger, if any. Row counts from table statistics are estimates:
+    /// flag them with `with_approximate_expectation`. Milestones are not
+    /// moved to the new expectation.
+    pub fn track_batches(&mut self, expected_rows: u64) -> BatchTracker<'_> {
+        self.expected_updates = Some(expected_rows);
+        self.batches.get_or_insert(0);
+        BatchTracker { pl: self }
+    }
+}
@@ -0,0 +1,2006 @@
+use crate::background::{BackgroundReports, Renderer};
+use crate::bar::{DisplayMode, TerminalBar};
+use crate::clock;
+use crate::fields::FieldSet;
+use crate::grid::Grid;
+use crate::heartbeat::Heartbeat;
+use crate::io::PreScan;
+use crate::metadata::Metadata;
+use crate::pretty::{sanitize, DurationFormat, NumberFormat, Unit};
+use crate::watchdog::Watchdog;
+use crate::DigitGrouping;
+use crate::{
+    AfterFinish, ConcurrentProgressLogger, ConfigError, EtaEstimator, ExtraField, Field, OnLog,
+    ProgressEvent, ProgressLogger, Reporter, ResolvedConfig, SampleSender, SharedSink,
+    SinkErrorPolicy, UpdateHook,
+};
+use crate::Level;
+use std::io::IsTerminal;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Builder state of a builder without an expected number of updates
+pub struct NoTotal;
+
+/// Builder state of a builder with an expected number of updates
+pub struct WithTotal;
+
+/// A builder with an expected number of updates, which gives access to
+/// the options that depend on the fraction of completed work.
+pub type ProgressLoggerBuilderWithTotal = ProgressLoggerBuilder<WithTotal>;
+
+/// Builds a new progress logger. All the configurations are optional,
+/// To obtain a builder, use `ProgressLogger::builder()`.
+///
+/// Options depending on the fraction of completed work, such as
+/// `with_milestones`, are only available after calling `with_expected_updates`,
+/// so that forgetting the expected number of updates is a compile error, see
+/// `tests/ui`. Code setting the expected number of updates conditionally
+/// keeps a single builder type with `with_expected_updates_opt`.
+pub struct ProgressLoggerBuilder<T = NoTotal> {
+    opts: Options,
+    state: PhantomData<T>,
+}
+
+struct Options {
+    expected_updates: Option<u64>,
+    approximate_expectation: bool,
+    items: Option<String>,
+    frequency: Option<Duration>,
+    log_level: Level,
+    log_target: Option<String>,
+    completion_level: Option<Level>,
+    compact_numbers: bool,
+    digit_grouping: Option<DigitGrouping>,
+    color: Option<bool>,
+    duration_format: DurationFormat,
+    unit: Unit,
+    display: DisplayMode,
+    metadata: Metadata,
+    metadata_in_text: bool,
+    extra_fields: Vec<ExtraField>,
+    stall_warning_fraction: Option<f64>,
+    ignore_global_switch: bool,
+    slow_output_threshold: Option<Duration>,
+    clock_skew_threshold: Option<Duration>,
+    collect_samples: bool,
+    eta_estimator: EtaEstimator,
+    milestones: Vec<f64>,
+    heartbeat: bool,
+    min_report_spacing: Duration,
+    expected_work_units: Option<f64>,
+    output_budget: Option<u64>,
+    after_finish: AfterFinish,
+    sink: Option<SharedSink>,
+    sink_error_policy: SinkErrorPolicy,
+    open_fds: bool,
+    open_fds_warning: Option<u64>,
+    retry_warning: Option<u64>,
+    grid: Option<(u64, u64)>,
+    grid_names: Option<(String, String)>,
+    prescan: Option<PreScan>,
+    start_time_in_text: bool,
+    async_reports: bool,
+    stall_reports: bool,
+    strict_cadence: bool,
+    lock_wait_warning: Option<f64>,
+    target_report_count: Option<u32>,
+    expected_duration: Option<Duration>,
+    sample_sender: Option<Box<dyn SampleSender>>,
+    on_log: Option<OnLog>,
+    update_hook: Option<UpdateHook>,
+    interval_hook: Option<UpdateHook>,
+    reporters: Vec<Box<dyn Reporter>>,
+    periodic_fields: FieldSet,
+    final_fields: FieldSet,
+    memory_reporting: bool,
+    cpu_display: bool,
+    /// the start of every line, see `inheriting`
+    prefix: Option<String>,
+    #[cfg(feature = "desktop-notification")]
+    desktop_notification: bool,
+    #[cfg(feature = "nvml")]
+    gpu_devices: Vec<u32>,
+    #[cfg(all(feature = "query-socket", unix))]
+    query_socket: Option<std::path::PathBuf>,
+}
+
+impl Options {
+    /// The frequency of the reports, before any retuning by
+    /// `with_target_report_count`
+    fn frequency(&self) -> Duration {
+        match (self.target_report_count, self.expected_duration) {
+            (Some(reports), Some(duration)) => (duration / reports).max(self.min_report_spacing),
+            _ => self.frequency.unwrap_or_else(|| Duration::from_secs(10)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.target_report_count.is_some()
+            && self.expected_duration.is_none()
+            && self.expected_updates.is_none()
+        {
+            return Err(ConfigError::ReportCountWithoutTotal);
+        }
+        let frequency = self.frequency();
+        if self.min_report_spacing > frequency {
+            return Err(ConfigError::SpacingAboveFrequency {
+                spacing: self.min_report_spacing,
+                frequency,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ProgressLoggerBuilder {
+    pub(crate) fn new() -> Self {
+        ProgressLoggerBuilder {
+            opts: Options {
+                expected_updates: None,
+                approximate_expectation: false,
+                items: None,
+                frequency: None,
+                log_level: Level::Info,
+                log_target: None,
+                completion_level: None,
+                compact_numbers: false,
+                digit_grouping: None,
+                color: None,
+                duration_format: DurationFormat::Seconds,
+                unit: Unit::Count,
+                display: DisplayMode::Log,
+                metadata: Metadata::default(),
+                metadata_in_text: false,
+                extra_fields: Vec::new(),
+                stall_warning_fraction: None,
+                ignore_global_switch: false,
+                slow_output_threshold: Some(Duration::from_millis(100)),
+                clock_skew_threshold: None,
+                collect_samples: false,
+                eta_estimator: EtaEstimator::GlobalAverage,
+                milestones: Vec::new(),
+                heartbeat: false,
+                min_report_spacing: Duration::from_millis(100),
+                expected_work_units: None,
+                output_budget: None,
+                after_finish: AfterFinish::default(),
+                sink: None,
+                sink_error_policy: SinkErrorPolicy::default(),
+                open_fds: false,
+                open_fds_warning: None,
+                retry_warning: None,
+                grid: None,
+                grid_names: None,
+                prescan: None,
+                start_time_in_text: false,
+                async_reports: false,
+                stall_reports: false,
+                strict_cadence: false,
+                lock_wait_warning: Some(0.1),
+                target_report_count: None,
+                expected_duration: None,
+                sample_sender: None,
+                on_log: None,
+                update_hook: None,
+                interval_hook: None,
+                reporters: Vec::new(),
+                periodic_fields: FieldSet::ALL,
+                final_fields: FieldSet::ALL,
+                memory_reporting: true,
+                cpu_display: true,
+                prefix: None,
+                #[cfg(feature = "desktop-notification")]
+                desktop_notification: false,
+                #[cfg(feature = "nvml")]
+                gpu_devices: Vec::new(),
+                #[cfg(all(feature = "query-socket", unix))]
+                query_socket: None,
+            },
+            state: PhantomData,
+        }
+    }
+
+    /// A builder with the items name, frequency, levels, target, number format,
+    /// fields and sink of `pl`, for its `ChildLogger`s, whose lines start
+    /// with `prefix`
+    pub(crate) fn inheriting(pl: &ProgressLogger, prefix: String) -> Self {
+        let mut builder = Self::new();
+        let opts = &mut builder.opts;
+        opts.items = Some(pl.items_name.clone());
+        opts.frequency = Some(pl.config.frequency);
+        opts.min_report_spacing = pl.min_report_spacing;
+        opts.log_level = pl.level;
+        opts.log_target = Some(pl.target.clone());
+        opts.completion_level = Some(pl.completion_level);
+        match pl.number_format {
+            NumberFormat::SiPrefix => opts.compact_numbers = true,
+            NumberFormat::Grouped(grouping) => opts.digit_grouping = Some(grouping),
+        }
+        opts.duration_format = pl.duration_format;
+        opts.unit = pl.unit;
+        opts.periodic_fields = pl.periodic_fields;
+        opts.final_fields = pl.final_fields;
+        opts.memory_reporting = pl.memory_reporting;
+        opts.cpu_display = pl.cpu_display;
+        opts.eta_estimator = pl.eta.estimator();
+        opts.ignore_global_switch = pl.ignore_global_switch;
+        opts.sink = pl
+            .sink
+            .clone()
+            .filter(|_| !pl.sink_panicked.load(Ordering::Relaxed));
+        opts.sink_error_policy = pl.sink_error_policy;
+        opts.prefix = Some(prefix);
+        builder
+    }
+}
+
+impl ProgressLoggerBuilder<WithTotal> {
+    /// Report progress when the given fractions of the expected updates
+    /// are reached, in addition to the periodic reports. For instance
+    /// `&[0.25, 0.5, 0.75]` reports at each quarter of the computation.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(1000u32)
+    ///     .with_milestones(&[0.25, 0.5, 0.75])
+    ///     .start();
+    /// for _ in 0..1000 {
+    ///     pl.update(1u32);
+    /// }
+    /// pl.stop();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If any fraction is not between 0 and 1.
+    pub fn with_milestones(mut self, fractions: &[f64]) -> Self {
+        for &fraction in fractions {
+            assert!(
+                (0.0..=1.0).contains(&fraction),
+                "milestones should be fractions between 0 and 1, got {}",
+                fraction
+            );
+        }
+        self.opts.milestones.extend_from_slice(fractions);
+        self
+    }
+}
+
+impl<T> ProgressLoggerBuilder<T> {
+    pub(crate) fn set_prescan(&mut self, prescan: PreScan) {
+        self.opts.prescan = Some(prescan);
+    }
+    /// Configure the expected number of updates. An expectation of zero,
+    /// e.g. from a work queue that turned out empty, gives no time to
+    /// completion: reports show `expected 0`, or the updates exceeding it,
+    /// and the completion statement of a run without updates is `Done in
+    /// ..., nothing to do.`
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let pl = ProgressLogger::builder()
+    ///     .with_expected_updates(0u32)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.stop();
+    /// assert!(lines.contents().ends_with(", nothing to do.\n"));
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(0u32)
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_compact_numbers(true)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// pl.update(3u32);
+    /// assert!(pl.time_to_completion().is_none());
+    /// assert!(lines.contents().contains(", 3 updates over the expected 0"));
+    /// ```
+    pub fn with_expected_updates<N: Into<u64>>(
+        mut self,
+        updates: N,
+    ) -> ProgressLoggerBuilder<WithTotal> {
+        self.opts.expected_updates = Some(updates.into());
+        ProgressLoggerBuilder {
+            opts: self.opts,
+            state: PhantomData,
+        }
+    }
+    /// Configure the expected number of updates, if any, keeping the type
+    /// of the builder: unlike `with_expected_updates`, this does not give
+    /// access to the options depending on the fraction of completed work.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// fn logger(expected: Option<u64>) -> ProgressLogger {
+    ///     ProgressLogger::builder()
+    ///         .with_items_name("rows")
+    ///         .with_expected_updates_opt(expected)
+    ///         .start()
+    /// }
+    ///
+    /// assert_eq!(logger(Some(10)).config().expected_updates, Some(10));
+    /// assert_eq!(logger(None).config().expected_updates, None);
+    /// ```
+    pub fn with_expected_updates_opt(mut self, updates: Option<u64>) -> Self {
+        self.opts.expected_updates = updates;
+        self
+    }
+    /// Flag the expected number of updates as an estimate, e.g. a row count
+    /// from table statistics, see `ProgressLogger::track_batches`. The time
+    /// left is then shown as approximate, as is the expectation when the
+    /// count exceeds it.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("rows")
+    ///     .with_approximate_expectation(true)
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_compact_numbers(true)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// let mut batches = pl.track_batches(1000);
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// batches.batch_fetched(500);
+    /// assert!(lines.contents().contains(" 500 rows in 1 batches, ~"));
+    /// assert!(lines.contents().contains(" s left ("));
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// batches.batch_fetched(600);
+    /// assert!(lines.contents().contains(", 100 rows over the expected ~1.00k ("));
+    /// assert!(lines.contents().contains(" batches/s)"));
+    /// batches.finished();
+    /// ```
+    pub fn with_approximate_expectation(mut self, approximate: bool) -> Self {
+        self.opts.approximate_expectation = approximate;
+        self
+    }
+    /// Configure a grid of `outer` × `inner` iterations, such as the
+    /// batches of the epochs of a training loop, advanced with
+    /// `ProgressLogger::inner_step` and `ProgressLogger::outer_step`. The
+    /// count is the overall number of inner iterations, and `outer * inner`
+    /// is the expected number of updates, saturating at `u64::MAX`.
+    ///
+    /// ```
+    /// use progress_logger::{DigitGrouping, MemorySink, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_grid(u64::MAX, 2)
+    ///     .with_digit_grouping(DigitGrouping::Plain)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// assert_eq!(pl.config().expected_updates, Some(u64::MAX));
+    /// pl.outer_step();
+    /// pl.checkpoint();
+    /// assert!(lines.contents().contains(", outer 2/18446744073709551615, inner 0/2 (overall 0.0%)"));
+    /// pl.finish_silently();
+    /// ```
+    pub fn with_grid(mut self, outer: u64, inner: u64) -> ProgressLoggerBuilder<WithTotal> {
+        self.opts.grid = Some((outer, inner));
+        self.with_expected_updates(outer.saturating_mul(inner))
+    }
+    /// Set the names of the outer and inner iterations of the grid, shown
+    /// in reports. Defaults to `outer` and `inner`.
+    pub fn with_grid_names<S: Into<String>>(mut self, outer: S, inner: S) -> Self {
+        self.opts.grid_names = Some((outer.into(), inner.into()));
+        self
+    }
+    /// Set the name of the items being counted.
+    pub fn with_items_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.opts.items = Some(name.into());
+        self
+    }
+    /// Aim at about `reports` periodic reports over the whole run, in place
+    /// of a fixed frequency. With `with_expected_duration`, the frequency is
+    /// the expected duration divided by `reports`. Otherwise, with an
+    /// expected number of updates, it is derived from the rate observed in
+    /// the first report interval, which lasts for the frequency set with
+    /// `with_frequency`. Frequencies are never below the minimum report
+    /// spacing.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let pl = ProgressLogger::builder()
+    ///     .with_target_report_count(100)
+    ///     .with_expected_duration(Duration::from_secs(2 * 3600))
+    ///     .start();
+    /// assert_eq!(pl.frequency(), Duration::from_secs(72));
+    ///
+    /// // Clamped to the minimum report spacing
+    /// let pl = ProgressLogger::builder()
+    ///     .with_target_report_count(100)
+    ///     .with_expected_duration(Duration::from_secs(1))
+    ///     .with_min_report_spacing(Duration::from_millis(100))
+    ///     .start();
+    /// assert_eq!(pl.frequency(), Duration::from_millis(100));
+    ///
+    /// // From the rate of the first interval: about 1000 updates per second
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(100u32)
+    ///     .with_target_report_count(10)
+    ///     .with_frequency(Duration::from_millis(5))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .start();
+    /// for _ in 0..100 {
+    ///     std::thread::sleep(Duration::from_millis(1));
+    ///     pl.update(1u32);
+    /// }
+    /// // About 100ms for the whole run, over 10 reports
+    /// assert!(pl.frequency() >= Duration::from_millis(9));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `reports` is zero.
+    pub fn with_target_report_count(mut self, reports: u32) -> Self {
+        assert!(
+            reports > 0,
+            "the target number of reports should be positive"
+        );
+        self.opts.target_report_count = Some(reports);
+        self
+    }
+    /// The expected duration of the run, see `with_target_report_count`.
+    pub fn with_expected_duration(mut self, duration: Duration) -> Self {
+        self.opts.expected_duration = Some(duration);
+        self
+    }
+    /// Set the frequency of reports on the console.
+    pub fn with_frequency(mut self, freq: Duration) -> Self {
+        self.opts.frequency = Some(freq);
+        self
+    }
+    /// Set the level of the reports, the completion statement and the notes.
+    /// The completion statement can have its own level, see
+    /// `with_completion_level`. Warnings, e.g. about stalls, keep the `Warn`
+    /// level. Defaults to `Info`. Takes a `Level` of this crate or, with the
+    /// `log` feature, of the `log` crate. Reports at a level filtered out by
+    /// the `log` crate are not even rendered, unless they go to a sink set
+    /// with `with_sink`.
+    ///
+    /// ```
+    /// use log::Level;
+    /// use progress_logger::ProgressLogger;
+    /// use std::sync::Mutex;
+    /// # if cfg!(feature = "tracing") { return; }
+    ///
+    /// /// Records the level of the messages
+    /// struct Levels(Mutex<Vec<Level>>);
+    /// impl log::Log for Levels {
+    ///     fn enabled(&self, metadata: &log::Metadata) -> bool {
+    ///         metadata.level() <= Level::Debug
+    ///     }
+    ///     fn log(&self, record: &log::Record) {
+    ///         self.0.lock().unwrap().push(record.level());
+    ///     }
+    ///     fn flush(&self) {}
+    /// }
+    /// static LEVELS: Levels = Levels(Mutex::new(Vec::new()));
+    /// log::set_logger(&LEVELS).unwrap();
+    /// log::set_max_level(log::LevelFilter::Debug);
+    ///
+    /// let mut pl = ProgressLogger::builder().with_log_level(Level::Debug).start();
+    /// pl.update(10u32);
+    /// pl.stop();
+    /// // Filtered out by the backend: not rendered at all
+    /// let mut pl = ProgressLogger::builder().with_log_level(Level::Trace).start();
+    /// pl.update(10u32);
+    /// pl.stop();
+    /// assert_eq!(*LEVELS.0.lock().unwrap(), vec![Level::Debug]);
+    /// ```
+    pub fn with_log_level<L: Into<Level>>(mut self, level: L) -> Self {
+        self.opts.log_level = level.into();
+        self
+    }
+    /// Set the level of the completion statement, and of the cancellation
+    /// one, e.g. to keep the outcome of a run at `Info` while the reports
+    /// are demoted to `Debug` with `with_log_level`. Defaults to the level
+    /// of the reports.
+    pub fn with_completion_level<L: Into<Level>>(mut self, level: L) -> Self {
+        self.opts.completion_level = Some(level.into());
+        self
+    }
+    /// Set the target of the messages logged through the `log` crate, to
+    /// route or filter them separately from the other messages of the
+    /// program, e.g. with `RUST_LOG=progress=debug` for `env_logger`.
+    /// Defaults to `LOG_TARGET`. Messages written to a sink set with
+    /// `with_sink` have no target.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::sync::Mutex;
+    /// # if cfg!(feature = "tracing") { return; }
+    ///
+    /// /// Records the target and level of the messages
+    /// struct Targets(Mutex<Vec<(String, log::Level)>>);
+    /// impl log::Log for Targets {
+    ///     fn enabled(&self, metadata: &log::Metadata) -> bool {
+    ///         metadata.target() == "progress"
+    ///     }
+    ///     fn log(&self, record: &log::Record) {
+    ///         if self.enabled(record.metadata()) {
+    ///             let target = record.target().to_owned();
+    ///             self.0.lock().unwrap().push((target, record.level()));
+    ///         }
+    ///     }
+    ///     fn flush(&self) {}
+    /// }
+    /// static TARGETS: Targets = Targets(Mutex::new(Vec::new()));
+    /// log::set_logger(&TARGETS).unwrap();
+    /// log::set_max_level(log::LevelFilter::Debug);
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_log_target("progress")
+    ///     .with_log_level(log::Level::Debug)
+    ///     .with_completion_level(log::Level::Info)
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.stop();
+    /// // Filtered out by the backend
+    /// let mut pl = ProgressLogger::builder().start();
+    /// pl.update(10u32);
+    /// pl.stop();
+    /// assert_eq!(
+    ///     *TARGETS.0.lock().unwrap(),
+    ///     vec![(String::from("progress"), log::Level::Info)]
+    /// );
+    /// ```
+    pub fn with_log_target<S: Into<String>>(mut self, target: S) -> Self {
+        self.opts.log_target = Some(target.into());
+        self
+    }
+    /// Render counts, throughput and time to completion using SI
+    /// abbreviations (`3.85M`, `12.4k`) rather than the full digits.
+    /// Useful to keep lines short. Defaults to `false`.
+    pub fn with_compact_numbers(mut self, compact: bool) -> Self {
+        self.opts.compact_numbers = compact;
+        self
+    }
+    /// Set how the groups of three digits of full numbers are told apart.
+    /// By default, they are underlined if the reports go to a terminal,
+    /// i.e. there is no sink and the standard error is a terminal, unless
+    /// colors are disabled, see `with_color`, and not separated otherwise,
+    /// to keep escape sequences out of files.
+    ///
+    /// ```
+    /// use progress_logger::{DigitGrouping, ProgressLogger};
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_digit_grouping(DigitGrouping::Comma)
+    ///     .preview()?;
+    /// assert!(preview.contains(" 370,000 updates (6,166.67 updates/s)"));
+    /// assert!(preview.ends_with(". 1,000,000 updates (6,166.67 updates/s)"));
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_digit_grouping(DigitGrouping::Plain)
+    ///     .preview()?;
+    /// assert!(preview.ends_with(". 1000000 updates (6166.67 updates/s)"));
+    /// # Ok::<(), progress_logger::ConfigError>(())
+    /// ```
+    pub fn with_digit_grouping(mut self, grouping: DigitGrouping) -> Self {
+        self.opts.digit_grouping = Some(grouping);
+        self
+    }
+    /// Allow ANSI escape sequences in the reports, which only underline the
+    /// groups of digits, see `with_digit_grouping`. By default, they are
+    /// allowed if the reports go to a terminal and the `NO_COLOR`
+    /// environment variable is unset or empty, see <https://no-color.org>.
+    /// Without them, the groups of digits of the reports going to a
+    /// terminal are separated by spaces.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// // Previews are not written to a terminal
+    /// let preview = ProgressLogger::builder().with_color(true).preview()?;
+    /// assert!(preview.contains("\x1B[4m"));
+    /// let preview = ProgressLogger::builder().with_color(false).preview()?;
+    /// assert!(!preview.contains('\x1B'));
+    /// # Ok::<(), progress_logger::ConfigError>(())
+    /// ```
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.opts.color = Some(enabled);
+        self
+    }
+    /// Set how the elapsed times and the times to completion are rendered,
+    /// e.g. `1d1h53m left` with `DurationFormat::Compact` rather than
+    /// `93212.41 s left`. Defaults to `DurationFormat::Seconds`.
+    ///
+    /// ```
+    /// use progress_logger::{DurationFormat, ProgressLogger};
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_expected_updates(1_000_000u32)
+    ///     .with_compact_numbers(true)
+    ///     .with_duration_format(DurationFormat::Compact)
+    ///     .with_memory_reporting(false)
+    ///     .preview()?;
+    /// assert!(preview.contains("1m0s 370k updates, 1m42s left ("));
+    /// assert!(preview.contains("Done in 2m42s."));
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_expected_updates(1_000_000u32)
+    ///     .with_compact_numbers(true)
+    ///     .with_duration_format(DurationFormat::Colon)
+    ///     .with_memory_reporting(false)
+    ///     .preview()?;
+    /// assert!(preview.contains("1:00 370k updates, 1:42 left ("));
+    /// # Ok::<(), progress_logger::ConfigError>(())
+    /// ```
+    pub fn with_duration_format(mut self, format: DurationFormat) -> Self {
+        self.opts.duration_format = format;
+        self
+    }
+    /// Set the unit of the counts, e.g. `Unit::Bytes` to report `1.07 GB
+    /// (230.15 MB/s)` rather than counts of items, see `Unit`. Defaults to
+    /// `Unit::Count`.
+    pub fn with_items_unit(mut self, unit: Unit) -> Self {
+        self.opts.unit = unit;
+        self
+    }
+    /// Set how the periodic reports are shown, e.g. as a single line
+    /// rewritten in place on interactive terminals with
+    /// `DisplayMode::TerminalBar`. Other messages, such as warnings and the
+    /// completion statement, are still written as usual, below the line.
+    /// Defaults to `DisplayMode::Log`.
+    ///
+    /// ```
+    /// use progress_logger::{DisplayMode, MemorySink, ProgressLogger, SharedSink};
+    ///
+    /// // Reports going to a sink are logged as usual
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_display(DisplayMode::TerminalBar)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.checkpoint();
+    /// pl.stop();
+    /// let output = lines.contents();
+    /// assert_eq!(output.lines().count(), 2);
+    /// assert!(!output.contains('\r'));
+    /// ```
+    pub fn with_display(mut self, display: DisplayMode) -> Self {
+        self.opts.display = display;
+        self
+    }
+    /// Attach a key-value pair to the logger, e.g. an experiment identifier
+    /// or a dataset name. Can be called multiple times to add several pairs.
+    /// The pairs are available with `ProgressLogger::metadata`, and are appended
+    /// to the reports if `with_metadata_in_text` is set.
+    ///
+    /// # Panics
+    ///
+    /// If the key is empty or contains whitespace, `=` or `"`.
+    pub fn with_metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.opts.metadata.push(key.into(), value.into());
+        self
+    }
+    /// Append the metadata to every report, as `key=value` pairs.
+    /// Defaults to `false`.
+    pub fn with_metadata_in_text(mut self, enabled: bool) -> Self {
+        self.opts.metadata_in_text = enabled;
+        self
+    }
+    /// Append a custom field to every periodic report, rendered as `name: value`
+    /// after the standard fields. The value is computed by calling `value_fn`
+    /// each time a report is issued. Can be called multiple times to add
+    /// several fields. A field whose function panics is dropped, with a
+    /// warning, while the reports carry on.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, Progress, ProgressLogger, SharedSink};
+    ///
+    /// # std::panic::set_hook(Box::new(|_| {}));
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_extra_field("queue", || "12".to_owned())
+    ///     .with_extra_field("faulty", || panic!("no value"))
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// for _ in 0..3 {
+    ///     pl.update(1u32);
+    ///     pl.report_now();
+    /// }
+    /// assert_eq!(pl.snapshot().count, 3);
+    /// assert!(pl.snapshot().throughput > 0.0);
+    /// let output = lines.contents();
+    /// let output: Vec<&str> = output.lines().collect();
+    /// // The warning, then the three reports, without the faulty field
+    /// assert_eq!(output.len(), 4);
+    /// assert!(output[0].contains("faulty"));
+    /// assert!(output[1..].iter().all(|line| line.ends_with("[queue: 12]")));
+    /// ```
+    pub fn with_extra_field<S, F>(mut self, name: S, value_fn: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn() -> String + Send + 'static,
+    {
+        let name = sanitize(&name.into()).into_owned();
+        self.opts.extra_fields.push((name, Box::new(value_fn)));
+        self
+    }
+    /// Mention in the completion line the time spent in report intervals
+    /// without progress, if it is at least the given fraction of the total
+    /// time, e.g. `stalled 14% of wall time across 9 episodes`.
+    pub fn with_stall_warning_fraction(mut self, fraction: f64) -> Self {
+        self.opts.stall_warning_fraction = Some(fraction);
+        self
+    }
+    /// Push the elapsed time, excluding pauses, and the count to `sender` at
+    /// every report, e.g. to draw custom curves on a dashboard. Samples are
+    /// sent without blocking: with a full channel they are dropped, and
+    /// once the receiver is gone they are no longer sent.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::sync::mpsc;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let (tx, rx) = mpsc::sync_channel(16);
+    /// let dashboard = std::thread::spawn(move || rx.iter().collect::<Vec<(Duration, u64)>>());
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(10))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_sample_channel(tx)
+    ///     .start();
+    /// let start = Instant::now();
+    /// while start.elapsed() < Duration::from_millis(100) {
+    ///     pl.update(1u32);
+    /// }
+    /// pl.stop();
+    /// let samples = dashboard.join().unwrap();
+    /// assert!(!samples.is_empty());
+    /// // At most one sample per report interval
+    /// assert!(samples.len() <= 10);
+    /// assert!(samples.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1));
+    /// ```
+    pub fn with_sample_channel<S: SampleSender + 'static>(mut self, sender: S) -> Self {
+        self.opts.sample_sender = Some(Box::new(sender));
+        self
+    }
+    /// Call `f` with the throughput, the count and the estimated seconds to
+    /// completion, if any, at every report, e.g. to feed a metrics system,
+    /// and with the final throughput and count when the logger finishes.
+    /// `f` is called even if the reports are filtered out by the level of
+    /// the `log` crate or the global switch, on the thread updating the
+    /// logger, so it should be quick. If `f` panics, the panic is caught,
+    /// reported once as a warning, and `f` is no longer called.
+    ///
+    /// ```
+    /// use progress_logger::{Progress, ProgressLogger};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// // Stands for e.g. a Prometheus counter
+    /// let processed = Arc::new(AtomicU64::new(0));
+    /// let gauge = Arc::clone(&processed);
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_on_log(move |_throughput, count, _eta_secs| {
+    ///         gauge.store(count, Ordering::Relaxed);
+    ///     })
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.report_now();
+    /// assert_eq!(processed.load(Ordering::Relaxed), 10);
+    /// pl.update(5u32);
+    /// pl.stop();
+    /// assert_eq!(processed.load(Ordering::Relaxed), 15);
+    /// ```
+    pub fn with_on_log<F: Fn(f64, u64, Option<f64>) + Send + 'static>(mut self, f: F) -> Self {
+        self.opts.on_log = Some(Box::new(f));
+        self
+    }
+    /// Call `f` with the increment of every update, e.g. to maintain a
+    /// statistic of your own. This puts `f` on the hot path: it is called
+    /// on the thread updating the logger, and prevents the update from
+    /// being a mere addition, so prefer `with_interval_hook` when coarse
+    /// aggregates are enough. Without a hook, updates only pay for an
+    /// untaken branch. If `f` panics, the panic is caught, reported once as
+    /// a warning, and `f` is no longer called.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let largest = Arc::new(AtomicU64::new(0));
+    /// let seen = Arc::clone(&largest);
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_update_hook(move |n| {
+    ///         seen.fetch_max(n, Ordering::Relaxed);
+    ///     })
+    ///     .start();
+    /// for batch in &[3u32, 12, 5] {
+    ///     pl.update(*batch);
+    /// }
+    /// pl.stop();
+    /// assert_eq!(largest.load(Ordering::Relaxed), 12);
+    /// ```
+    pub fn with_update_hook<F: FnMut(u64) + Send + 'static>(mut self, f: F) -> Self {
+        self.opts.update_hook = Some(Box::new(f));
+        self
+    }
+    /// Call `f` with the number of updates counted since the previous call
+    /// at every report, and with the remainder when the logger finishes,
+    /// so that the calls add up to the count. Unlike `with_update_hook`,
+    /// this costs nothing between reports. Like `with_on_log`, `f` is
+    /// called even if the reports are filtered out. If `f` panics, the
+    /// panic is caught, reported once as a warning, and `f` is no longer
+    /// called.
+    ///
+    /// ```
+    /// use progress_logger::{Progress, ProgressLogger};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let deltas = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&deltas);
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_interval_hook(move |delta| sink.lock().unwrap().push(delta))
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.report_now();
+    /// pl.update(5u32);
+    /// pl.stop();
+    /// assert_eq!(*deltas.lock().unwrap(), vec![10, 5]);
+    /// ```
+    pub fn with_interval_hook<F: FnMut(u64) + Send + 'static>(mut self, f: F) -> Self {
+        self.opts.interval_hook = Some(Box::new(f));
+        self
+    }
+    /// Send the events of the logger to `reporter`, e.g. to feed a
+    /// dashboard, see `Reporter`. Can be called multiple times to add
+    /// several reporters. Reporters receive the events the text output
+    /// sees, so they are subject to the global switch, but not to the level
+    /// filter of the `log` crate. A reporter panicking is dropped, with a
+    /// warning, or, with `SinkErrorPolicy::Fail`, the panic propagates.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressEvent, ProgressLogger, SharedSink};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let mut pl = {
+    ///     let events = Arc::clone(&events);
+    ///     ProgressLogger::builder()
+    ///         .with_expected_updates(100u32)
+    ///         .with_milestones(&[0.5])
+    ///         .with_frequency(Duration::from_millis(1))
+    ///         .with_min_report_spacing(Duration::from_millis(0))
+    ///         .with_sink(SharedSink::new(lines.clone()))
+    ///         .with_reporter(move |event: &ProgressEvent| {
+    ///             events.lock().unwrap().push(event.clone());
+    ///         })
+    ///         .start()
+    /// };
+    /// pl.start_phase("load");
+    /// for _ in 0..100 {
+    ///     pl.update(1u32);
+    ///     std::thread::sleep(Duration::from_micros(100));
+    /// }
+    /// pl.note("almost there");
+    /// pl.stop();
+    ///
+    /// // The text output, rebuilt from the events
+    /// let events = events.lock().unwrap();
+    /// let rebuilt: String = events
+    ///     .iter()
+    ///     .filter_map(ProgressEvent::line)
+    ///     .map(|line| format!("{}\n", line))
+    ///     .collect();
+    /// assert_eq!(rebuilt, lines.contents());
+    /// assert!(events.contains(&ProgressEvent::Milestone { count: 50 }));
+    /// assert!(events.contains(&ProgressEvent::PhaseEnded { name: "load".to_owned() }));
+    /// ```
+    pub fn with_reporter<R: Reporter + 'static>(mut self, reporter: R) -> Self {
+        self.opts.reporters.push(Box::new(reporter));
+        self
+    }
+    /// Warn when the workers of `parallel_chunks` or the handles of a
+    /// `ConcurrentProgressLogger`, together, have waited for the logger
+    /// more than the given fraction of the wall time, or never with
+    /// `None`. Defaults to 10%.
+    pub fn with_lock_wait_warning(mut self, fraction: Option<f64>) -> Self {
+        self.opts.lock_wait_warning = fraction;
+        self
+    }
+    /// Keep reporting even when reports are disabled process-wide with
+    /// `set_global_enabled(false)`. Meant for critical loggers.
+    pub fn with_ignore_global_switch(mut self, ignore: bool) -> Self {
+        self.opts.ignore_global_switch = ignore;
+        self
+    }
+    /// Stop writing the reports from `update` if it takes longer than
+    /// `threshold` on average, e.g. because of a blocking network appender,
+    /// so that a slow logging backend cannot stall the computation. With a
+    /// one-time warning, the reports move to a background thread, see
+    /// `with_async_reports`, or, if samples, notifiers or the query socket
+    /// need them on the calling thread, are spaced out so that writing
+    /// them takes at most 1% of the time. The average is over the last 8
+    /// reports, and taken from the 4th one. Defaults to 100 ms, `None`
+    /// disables the check.
+    ///
+    /// ```
+    /// use progress_logger::{ProgressLogger, SharedSink};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// /// A sink taking 50 ms for every line
+    /// #[derive(Clone, Default)]
+    /// struct Slow(Arc<Mutex<Vec<u8>>>);
+    /// impl std::io::Write for Slow {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         std::thread::sleep(Duration::from_millis(50));
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let slow = Slow::default();
+    /// // Report at every update
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_secs(0))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_slow_output_threshold(Some(Duration::from_millis(10)))
+    ///     .with_sink(SharedSink::new(slow.clone()))
+    ///     .start();
+    /// for _ in 0..4 {
+    ///     pl.update(1u32);
+    /// }
+    /// assert!(pl.output_latency().unwrap() >= Duration::from_millis(50));
+    /// // The next reports are written in the background
+    /// let before = Instant::now();
+    /// pl.update(1u32);
+    /// assert!(before.elapsed() < Duration::from_millis(50));
+    /// pl.stop();
+    /// let output = String::from_utf8(slow.0.lock().unwrap().clone()).unwrap();
+    /// assert!(output.contains("from a background thread from now on"));
+    /// ```
+    pub fn with_slow_output_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.opts.slow_output_threshold = threshold;
+        self
+    }
+    /// Detect the monotonic clock going backwards, which can happen on virtual
+    /// machines subject to live migration. The first skew is reported with
+    /// a warning; further skews are reported at most once every `threshold`,
+    /// to avoid flooding the logs.
+    pub fn with_clock_skew_detection(mut self, threshold: Duration) -> Self {
+        self.opts.clock_skew_threshold = Some(threshold);
+        self
+    }
+    /// Estimate the time to completion on a recent throughput rather than
+    /// on the average since the start, so that the estimate follows a
+    /// computation slowing down or speeding up, see `EtaEstimator`. The
+    /// estimator is updated at each report: until it has seen some
+    /// progress, the average is used. Reports then label the time left
+    /// `(EMA ETA)`, `(windowed ETA)` or `(avg ETA)`, and show the
+    /// throughput of the last report interval next to the average one,
+    /// e.g. `(2.00k rows/s, now 350.00 rows/s)`. Only the estimates on the
+    /// expected updates use the estimator, not those on the work units or
+    /// on the key range. Defaults to `EtaEstimator::GlobalAverage`.
+    ///
+    /// Panics if the `alpha` of `EtaEstimator::ExponentialMovingAverage`
+    /// is not in `(0, 1]`.
+    ///
+    /// ```
+    /// use progress_logger::{EtaEstimator, Progress, ProgressLogger};
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(2000u32)
+    ///     .with_eta_estimator(EtaEstimator::ExponentialMovingAverage { alpha: 0.5 })
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .start();
+    /// // A fast start, then a tenfold slowdown
+    /// pl.update(1000u32);
+    /// pl.report_now();
+    /// for _ in 0..10 {
+    ///     std::thread::sleep(Duration::from_millis(10));
+    ///     pl.update(10u32);
+    ///     pl.report_now();
+    /// }
+    /// // 900 updates left at about 1000 per second
+    /// assert!(pl.time_to_completion().unwrap() > Duration::from_millis(500));
+    /// pl.stop();
+    /// ```
+    pub fn with_eta_estimator(mut self, estimator: EtaEstimator) -> Self {
+        if let EtaEstimator::ExponentialMovingAverage { alpha } = estimator {
+            assert!(
+                alpha > 0.0 && alpha <= 1.0,
+                "the alpha of an exponential moving average must be in (0, 1], not {}",
+                alpha
+            );
+        }
+        self.opts.eta_estimator = estimator;
+        self
+    }
+    /// Estimate the time to completion on the throughput of the last
+    /// `window`, same as `with_eta_estimator(EtaEstimator::Window(window))`.
+    /// At most 1024 counts are kept, which shortens the window if reports
+    /// are more frequent.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, Progress, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(2000u32)
+    ///     .with_throughput_window(Duration::from_millis(100))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// // A fast start, then a tenfold slowdown
+    /// pl.update(1000u32);
+    /// pl.report_now();
+    /// for _ in 0..10 {
+    ///     std::thread::sleep(Duration::from_millis(10));
+    ///     pl.update(10u32);
+    ///     pl.report_now();
+    /// }
+    /// let output = lines.contents();
+    /// let lines: Vec<&str> = output.lines().collect();
+    /// assert!(lines[0].contains(" s left (avg ETA)"));
+    /// assert!(lines[10].contains(" s left (windowed ETA)"));
+    /// assert!(lines[10].contains(" updates/s, now "));
+    /// // 900 updates left at about 1000 per second, rather than at the
+    /// // average of about 10 000 per second
+    /// assert!(pl.time_to_completion().unwrap() > Duration::from_millis(500));
+    /// pl.stop();
+    /// ```
+    pub fn with_throughput_window(self, window: Duration) -> Self {
+        self.with_eta_estimator(EtaEstimator::Window(window))
+    }
+    /// Record a `ProgressSample` at every report, to be retrieved with
+    /// `ProgressLogger::samples` for offline analysis. At most 4096 samples
+    /// are kept: past that, every other one is dropped and reports are
+    /// sampled half as often. Defaults to `false`.
+    pub fn with_collect_samples(mut self, collect: bool) -> Self {
+        self.opts.collect_samples = collect;
+        self
+    }
+    /// Show a desktop notification when the logger is stopped, to be
+    /// warned of the completion of long computations. Failures to show the
+    /// notification are ignored. Requires the `desktop-notification` feature.
+    #[cfg(feature = "desktop-notification")]
+    pub fn with_desktop_notification(mut self, enabled: bool) -> Self {
+        self.opts.desktop_notification = enabled;
+        self
+    }
+    /// Report the memory and swap usage of the machine at the start of the
+    /// periodic reports, e.g. `[mem: 1 024 kB, swap: 0 kB]`, and the memory
+    /// growth of the phases, see `start_phase`. Defaults to `true`. When
+    /// disabled the memory is never sampled, which saves the cost of
+    /// querying the system in short-lived loggers. Without the `memory`
+    /// feature the memory is only reported if set with `set_memory_probe`.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("rows")
+    ///     .with_expected_updates(4u32)
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_memory_reporting(false)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// pl.update(1u32);
+    /// pl.start_phase("load");
+    /// pl.update(3u32);
+    /// pl.end_phase();
+    /// assert!(pl.phase_memory_deltas().is_empty());
+    /// pl.stop();
+    /// let output = lines.contents();
+    /// let output: Vec<&str> = output.lines().collect();
+    /// assert_eq!(output.len(), 2);
+    /// assert!(!output[0].starts_with('['));
+    /// assert!(output[0].contains(" 1 rows, "));
+    /// assert!(output[0].contains(" s left ("));
+    /// assert!(output[1].starts_with("Done in "));
+    /// assert!(output[1].contains(" 4 rows "));
+    /// assert!(!output.concat().contains("mem"));
+    /// ```
+    pub fn with_memory_reporting(mut self, enabled: bool) -> Self {
+        self.opts.memory_reporting = enabled;
+        self
+    }
+    /// Show the CPU utilization of the current process next to the memory
+    /// usage of the machine, e.g. `[mem: 1 024 kB, swap: 0 kB, cpu: 142.5%]`,
+    /// 100% per busy core, measured since the previous refresh of the shared
+    /// sample, see
+    /// `set_memory_sampling_interval`. Nothing is shown until two refreshes
+    /// have been made, nor with a probe set with `set_memory_probe`.
+    /// Defaults to `true`.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, Progress, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// progress_logger::set_memory_sampling_interval(Duration::from_secs(0));
+    /// let lines = MemorySink::new();
+    /// for cpu_display in [true, false] {
+    ///     let mut pl = ProgressLogger::builder()
+    ///         .with_cpu_display(cpu_display)
+    ///         .with_min_report_spacing(Duration::from_millis(0))
+    ///         .with_sink(SharedSink::new(lines.clone()))
+    ///         .start();
+    ///     for _ in 0..2 {
+    ///         std::thread::sleep(Duration::from_millis(10));
+    ///         pl.update(1u32);
+    ///         pl.report_now();
+    ///     }
+    ///     pl.finish_silently();
+    /// }
+    /// let output = lines.contents();
+    /// let lines: Vec<&str> = output.lines().collect();
+    /// assert_eq!(lines.len(), 4);
+    /// // The first refresh has nothing to compare to
+    /// assert!(!lines[0].contains("cpu"));
+    /// assert_eq!(lines[1].contains(", cpu: "), cfg!(feature = "memory"));
+    /// assert!(!lines[3].contains("cpu"));
+    /// ```
+    pub fn with_cpu_display(mut self, enabled: bool) -> Self {
+        self.opts.cpu_display = enabled;
+        self
+    }
+    /// Report the memory usage of the GPU with the given index, e.g.
+    /// `gpu0: 38.2 / 40.0 GiB`. Can be called multiple times to report on
+    /// several devices. If NVML cannot be initialized (e.g. there is no
+    /// driver) a warning is logged, and GPU memory is not reported.
+    /// Requires the `nvml` feature.
+    #[cfg(feature = "nvml")]
+    pub fn with_gpu_memory(mut self, device_index: u32) -> Self {
+        self.opts.gpu_devices.push(device_index);
+        self
+    }
+    /// Serve the state of the logger on a unix domain socket at the given
+    /// path: every client connecting to it receives a JSON snapshot as of
+    /// the last report, followed by a newline. The socket is only accessible
+    /// by the owner of the process, and is removed when the logger is
+    /// stopped or dropped. If the socket cannot be created a warning is
+    /// logged and the logger works as usual. Requires the `query-socket`
+    /// feature.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::io::Read;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// let path = std::env::temp_dir().join(format!("progress-doc-{}.sock", std::process::id()));
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("rows")
+    ///     .with_query_socket(&path)
+    ///     .start();
+    /// pl.update(10u32);
+    ///
+    /// let mut response = String::new();
+    /// UnixStream::connect(&path).unwrap().read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("{\"version\":"));
+    /// assert!(response.contains(",\"items\":\"rows\","));
+    /// pl.stop();
+    /// assert!(!path.exists());
+    /// ```
+    #[cfg(all(feature = "query-socket", unix))]
+    pub fn with_query_socket<P: Into<std::path::PathBuf>>(mut self, path: P) -> Self {
+        self.opts.query_socket = Some(path.into());
+        self
+    }
+    /// Keep a one character per report interval timeline of the run,
+    /// printed in the completion line and available with
+    /// `ProgressLogger::heartbeat`, e.g. `....ss...!....`. Its length is
+    /// bounded: very long runs are downsampled. Defaults to `false`.
+    pub fn with_heartbeat_string(mut self, enabled: bool) -> Self {
+        self.opts.heartbeat = enabled;
+        self
+    }
+    /// The minimum time between two reports, whatever triggers them: reports
+    /// due earlier are skipped, and their number is mentioned in the next
+    /// one. This guards the logging backend against misconfigurations, such as
+    /// a very short frequency or many milestones. The completion statement
+    /// and the status lines rendered by `MultiLoggerDisplay::refresh` are
+    /// never skipped. Defaults to 100 milliseconds.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let milestones: Vec<f64> = (1..100).map(|i| i as f64 / 100.0).collect();
+    /// let start = Instant::now();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(0))
+    ///     .with_expected_updates(100u32)
+    ///     .with_milestones(&milestones)
+    ///     .with_min_report_spacing(Duration::from_millis(50))
+    ///     .with_collect_samples(true)
+    ///     .start();
+    /// for _ in 0..100 {
+    ///     std::thread::sleep(Duration::from_millis(3));
+    ///     pl.update(1u32);
+    /// }
+    /// let max_reports = start.elapsed().as_millis() / 50 + 1;
+    /// assert!(pl.samples().len() as u128 <= max_reports);
+    /// pl.stop();
+    /// ```
+    pub fn with_min_report_spacing(mut self, spacing: Duration) -> Self {
+        self.opts.min_report_spacing = spacing;
+        self
+    }
+    /// The expected total of work units accumulated with `update_weighted`,
+    /// e.g. the size in bytes of a file whose records are being counted.
+    /// The time to completion is then estimated on the fraction of work
+    /// units done, while the reports still show the count of items, along
+    /// with an estimate of their total derived from the average work units
+    /// per item observed so far, see `ProgressLogger::estimated_total_items`.
+    /// Takes precedence over `with_expected_updates` for the time to
+    /// completion.
+    pub fn with_expected_work_units(mut self, total: f64) -> Self {
+        self.opts.expected_work_units = Some(total);
+        self
+    }
+    /// Caps the bytes written by the periodic reports and notes of the logger,
+    /// for logging pipelines billed by volume. Once half of the budget is
+    /// used, a notice is logged and the frequency doubles at each report;
+    /// once the budget is exhausted, only the completion statement is
+    /// logged. Sizes are estimated as the length of the messages.
+    /// Checkpoints use up the budget too, but are always written.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_output_budget(1000)
+    ///     .start();
+    /// for _ in 0..100 {
+    ///     std::thread::sleep(Duration::from_millis(1));
+    ///     pl.update(1u32);
+    /// }
+    /// assert!(pl.output_bytes() <= 1000);
+    /// pl.stop();
+    /// ```
+    pub fn with_output_budget(mut self, bytes: u64) -> Self {
+        self.opts.output_budget = Some(bytes);
+        self
+    }
+    /// What to do with updates arriving after `ProgressLogger::finish`.
+    /// Defaults to ignoring them, with a warning on the first one.
+    pub fn with_after_finish(mut self, policy: AfterFinish) -> Self {
+        self.opts.after_finish = policy;
+        self
+    }
+    /// Write the messages of the logger to the given sink, one per line, in
+    /// place of logging them with the `log` crate. See `SharedSink`. If the
+    /// sink panics, the logger warns and goes back to the `log` crate.
+    ///
+    /// ```
+    /// use progress_logger::{Progress, ProgressLogger, SharedSink};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// struct Faulty;
+    /// impl std::io::Write for Faulty {
+    ///     fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+    ///         panic!("disk on fire")
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// # std::panic::set_hook(Box::new(|_| {}));
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(10))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_sink(SharedSink::new(Faulty))
+    ///     .start();
+    /// let start = Instant::now();
+    /// while start.elapsed() < Duration::from_millis(100) {
+    ///     pl.update(1u32);
+    /// }
+    /// // The reports carry on at the configured cadence
+    /// let interval = pl.snapshot().interval.unwrap();
+    /// assert!(interval >= Duration::from_millis(10));
+    /// pl.stop();
+    /// ```
+    pub fn with_sink(mut self, sink: SharedSink) -> Self {
+        self.opts.sink = Some(sink);
+        self
+    }
+    /// Set what to do when the sink fails to write a message, e.g. because
+    /// the disk is full. By default, the first error is logged through the
+    /// `log` crate, the later ones are counted, and the reports go on, with
+    /// a `(sink errors: N)` note. The count is in the snapshots and in the
+    /// completion statement, and in `SharedSink::errors` along with
+    /// `SharedSink::first_error`.
+    ///
+    /// ```
+    /// use progress_logger::{ProgressLogger, SharedSink, SinkErrorPolicy};
+    /// use std::io::{self, Write};
+    /// use std::panic::{self, AssertUnwindSafe};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// /// Fails the second and third writes, as a disk filling up and then
+    /// /// freed
+    /// #[derive(Clone, Default)]
+    /// struct Flaky(Arc<Mutex<(u32, Vec<u8>)>>);
+    /// impl Write for Flaky {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         let mut state = self.0.lock().unwrap();
+    ///         state.0 += 1;
+    ///         if (2..=3).contains(&state.0) {
+    ///             return Err(io::Error::new(io::ErrorKind::Other, "no space left"));
+    ///         }
+    ///         state.1.write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let flaky = Flaky::default();
+    /// let sink = SharedSink::new(flaky.clone());
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(0))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_sink(sink.clone())
+    ///     .start();
+    /// for _ in 0..4 {
+    ///     std::thread::sleep(Duration::from_millis(1));
+    ///     pl.update(1u32);
+    /// }
+    /// assert_eq!(pl.snapshot().sink_errors, 2);
+    /// assert_eq!(sink.first_error().as_deref(), Some("no space left"));
+    /// pl.stop();
+    /// let output = String::from_utf8(flaky.0.lock().unwrap().1.clone()).unwrap();
+    /// let lines: Vec<_> = output.lines().collect();
+    /// assert_eq!(lines.len(), 3);
+    /// assert!(lines[1].ends_with(" (sink errors: 2)"));
+    /// assert!(lines[2].ends_with(", 2 sink errors (first: no space left)"));
+    ///
+    /// // Interrupt the computation instead
+    /// # panic::set_hook(Box::new(|_| {}));
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(0))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_sink(SharedSink::new(Flaky::default()))
+    ///     .with_sink_error_policy(SinkErrorPolicy::Fail)
+    ///     .start();
+    /// let failed = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     for _ in 0..4 {
+    ///         std::thread::sleep(Duration::from_millis(1));
+    ///         pl.update(1u32);
+    ///     }
+    /// }));
+    /// assert!(failed.is_err());
+    /// assert_eq!(pl.snapshot().count, 2);
+    /// ```
+    pub fn with_sink_error_policy(mut self, policy: SinkErrorPolicy) -> Self {
+        self.opts.sink_error_policy = policy;
+        self
+    }
+    /// Report the number of file descriptors open in the process, as
+    /// `fds: 812`, to spot descriptor leaks before they make the process
+    /// fail. The count is obtained by listing `/proc/self/fd` (`/dev/fd`
+    /// on other unix systems), and omitted on other platforms. Defaults to
+    /// `false`.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_open_fds(true)
+    ///     .start();
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// pl.update(1u32);
+    /// # #[cfg(unix)]
+    /// assert!(pl.peak_open_fds().unwrap() > 0);
+    /// pl.stop();
+    /// ```
+    pub fn with_open_fds(mut self, enabled: bool) -> Self {
+        self.opts.open_fds = enabled;
+        self
+    }
+    /// Report the number of open file descriptors, as `with_open_fds`, and
+    /// log a warning when it exceeds `threshold`.
+    pub fn with_open_fds_warning(mut self, threshold: u64) -> Self {
+        self.opts.open_fds = true;
+        self.opts.open_fds_warning = Some(threshold);
+        self
+    }
+    /// Warn when more than `retries` retries are counted with
+    /// `ProgressLogger::record_retry` between two reports.
+    pub fn with_retry_warning(mut self, retries: u64) -> Self {
+        self.opts.retry_warning = Some(retries);
+        self
+    }
+    /// Start the completion statement with the wall-clock start time, as
+    /// `Started 2024-05-12 03:15 UTC, done in 4.2h. ...`
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::SystemTime;
+    ///
+    /// let pl = ProgressLogger::builder().with_start_time_in_text(true).start();
+    /// assert!(pl.started_at() <= SystemTime::now());
+    /// assert_eq!(pl.snapshot().started_at, pl.started_at());
+    /// pl.stop();
+    /// ```
+    pub fn with_start_time_in_text(mut self, enabled: bool) -> Self {
+        self.opts.start_time_in_text = enabled;
+        self
+    }
+    /// Render and emit the periodic reports on a background thread, so that
+    /// `update` never refreshes the memory statistics, formats a line or
+    /// writes it: it only hands the count and the time over, bounding the
+    /// latency of the calls that trigger a report. Reports arriving while
+    /// 16 are already waiting are dropped, and counted in the next one.
+    /// The completion statement is emitted by `stop` after the pending
+    /// reports.
+    ///
+    /// Background reports show the memory, elapsed time, count, time left
+    /// and throughput: the other fields, samples, notifiers and the query
+    /// socket are only available with synchronous reports. Warnings, which
+    /// are rare, are still emitted synchronously.
+    ///
+    /// See the `latency` example for a comparison of the worst-case latency
+    /// of `update`.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, Progress, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_async_reports(true)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.report_now();
+    /// pl.stop();
+    /// // The pending report is emitted before the completion statement
+    /// let output = lines.contents();
+    /// let output: Vec<&str> = output.lines().collect();
+    /// assert_eq!(output.len(), 2);
+    /// assert!(output[1].starts_with("Done"));
+    /// ```
+    pub fn with_async_reports(mut self, enabled: bool) -> Self {
+        self.opts.async_reports = enabled;
+        self
+    }
+    /// Keep reporting at the configured frequency while the updates stall,
+    /// e.g. on a slow network call, so that the process does not look hung.
+    /// A dedicated thread wakes up at every frequency and, if the logger
+    /// did not check the clock for a whole frequency, emits a report ending
+    /// with `stalled for` the time since the last check. Updates only share
+    /// their state with the thread when they check the clock, so the count
+    /// in these reports may miss the last updates if their rate dropped
+    /// abruptly. The reports are not sent to the reporters, and stop while
+    /// the logger is paused or finished. The thread is shut down when the
+    /// logger is stopped or dropped. Without this option, no thread is
+    /// spawned and updates are unaffected.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(10))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_stall_reports(true)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.update(10u32);
+    /// // Waiting on something slow
+    /// std::thread::sleep(Duration::from_millis(100));
+    /// pl.stop();
+    /// let output = lines.contents();
+    /// let stalled: Vec<&str> = output.lines().filter(|l| l.contains("stalled for")).collect();
+    /// assert!(!stalled.is_empty());
+    /// assert!(stalled.iter().all(|l| l.contains(" 10 updates ")));
+    /// ```
+    pub fn with_stall_reports(mut self, enabled: bool) -> Self {
+        self.opts.stall_reports = enabled;
+        self
+    }
+    /// Report at the configured frequency even when updates are sparser,
+    /// rather than at the first update after a report is due, which can be
+    /// late by up to the gap between two updates. The thread of
+    /// `with_stall_reports` emits the reports the updates do not, with the
+    /// count of the last clock check, ending with `stalled for` if the
+    /// logger did not check the clock for a whole frequency. The logger and
+    /// the thread agree on the last report, so no report is emitted twice.
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(20))
+    ///     .with_strict_cadence(true)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// // Updates 100ms apart, reports about every 20ms
+    /// for _ in 0..3 {
+    ///     std::thread::sleep(Duration::from_millis(100));
+    ///     pl.update(1u32);
+    /// }
+    /// pl.stop();
+    /// let output = lines.contents();
+    /// assert!(output.lines().count() > 6);
+    /// ```
+    pub fn with_strict_cadence(mut self, enabled: bool) -> Self {
+        self.opts.strict_cadence = enabled;
+        self
+    }
+    /// Select the fields of the periodic reports, e.g. to keep them terse.
+    /// Fields are rendered in a fixed order, whatever the order in the
+    /// slice, and fields without a value (e.g. the work units, if never
+    /// updated) are omitted anyway. Defaults to all the fields.
+    ///
+    /// ```
+    /// use progress_logger::{Field, MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("rows")
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_periodic_fields(&[Field::Count, Field::Throughput])
+    ///     .with_final_fields(&[Field::Count])
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// std::thread::sleep(Duration::from_millis(2));
+    /// pl.update(1u32);
+    /// pl.stop();
+    /// let output = lines.contents();
+    /// let output: Vec<&str> = output.lines().collect();
+    /// assert!(output[0].ends_with(" rows/s)"));
+    /// assert!(!output[0].contains("mem:"));
+    /// assert!(output[1].starts_with("Done. "));
+    /// assert!(!output[1].contains("rows/s"));
+    /// ```
+    pub fn with_periodic_fields(mut self, fields: &[Field]) -> Self {
+        self.opts.periodic_fields = FieldSet::new(fields);
+        self
+    }
+    /// Select the fields of the completion statement, see
+    /// `with_periodic_fields`. Defaults to all the fields.
+    pub fn with_final_fields(mut self, fields: &[Field]) -> Self {
+        self.opts.final_fields = FieldSet::new(fields);
+        self
+    }
+    /// Builds a logger shared by several threads, starting the internal
+    /// timer, see `ConcurrentProgressLogger`.
+    pub fn start_concurrent(self) -> ConcurrentProgressLogger {
+        ConcurrentProgressLogger::new(self.start())
+    }
+    /// Renders a periodic report and a completion statement with this
+    /// configuration, without running anything, to check what the output of
+    /// the actual run will look like. The run is fabricated: 37% of the
+    /// expected updates (of 1 000 000 updates without an expectation) after
+    /// a minute, then all of them. The memory usage and the extra fields
+    /// are the current ones. Nothing is logged, and no background thread or
+    /// socket is started.
+    ///
+    /// Returns the two lines, separated by a newline, or an error if some
+    /// options would not have the intended effect.
+    ///
+    /// ```
+    /// use progress_logger::{ConfigError, Field, ProgressLogger};
+    /// use std::time::Duration;
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_items_name("edges")
+    ///     .with_expected_updates(1_000_000u32)
+    ///     .with_compact_numbers(true)
+    ///     .preview()?;
+    /// let (report, done) = preview.split_once('\n').unwrap();
+    /// assert!(report.ends_with("60.00s 370k edges, 102.16 s left (6.17k edges/s)"));
+    /// assert_eq!(done, "Done in 162.16s. 1.00M edges (6.17k edges/s)");
+    ///
+    /// let preview = ProgressLogger::builder()
+    ///     .with_periodic_fields(&[Field::Count, Field::Throughput])
+    ///     .with_final_fields(&[Field::Count, Field::Metadata])
+    ///     .with_metadata("shard", "7")
+    ///     .with_metadata_in_text(true)
+    ///     .with_compact_numbers(true)
+    ///     .preview()?;
+    /// assert_eq!(preview, "370k updates (6.17k updates/s)\nDone. 1.00M updates [shard=7]");
+    ///
+    /// let invalid = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(10))
+    ///     .preview();
+    /// assert_eq!(
+    ///     invalid,
+    ///     Err(ConfigError::SpacingAboveFrequency {
+    ///         spacing: Duration::from_millis(100),
+    ///         frequency: Duration::from_millis(10),
+    ///     })
+    /// );
+    /// let invalid = ProgressLogger::builder().with_target_report_count(100).preview();
+    /// assert_eq!(invalid, Err(ConfigError::ReportCountWithoutTotal));
+    /// # Ok::<(), ConfigError>(())
+    /// ```
+    pub fn preview(self) -> Result<String, ConfigError> {
+        self.opts.validate()?;
+        let mut pl = self.silenced().start();
+        let now = clock::now();
+        let total = pl.expected_updates.filter(|&n| n > 0).unwrap_or(1_000_000);
+        let started = |elapsed: Duration| now.checked_sub(elapsed).unwrap_or(now);
+        let minute = Duration::from_secs(60);
+        pl.start = started(minute);
+        pl.count = (total as f64 * 0.37).round() as u64;
+        let report = pl.report_line(now).unwrap_or_default();
+        pl.start = started(minute.div_f64(0.37));
+        pl.count = total;
+        let done = pl.completion_line(now);
+        Ok(format!("{}\n{}", report, done))
+    }
+    /// Describes the configuration the logger would have if started now,
+    /// see `ResolvedConfig::describe`. Nothing is written.
+    ///
+    /// ```
+    /// use progress_logger::{AfterFinish, ProgressLogger, LOG_TARGET, VERSION};
+    /// use std::time::Duration;
+    ///
+    /// let description = ProgressLogger::builder()
+    ///     .with_items_name("edges")
+    ///     .with_expected_updates(1000u32)
+    ///     .with_milestones(&[0.25, 0.5])
+    ///     .with_frequency(Duration::from_secs(1))
+    ///     .with_output_budget(1 << 20)
+    ///     .with_compact_numbers(true)
+    ///     .with_after_finish(AfterFinish::Panic)
+    ///     .with_memory_reporting(false)
+    ///     .describe_pending();
+    /// assert_eq!(
+    ///     description,
+    ///     format!(
+    ///         "items: edges\n\
+    ///          reports: every 1.00s, at least 100.00ms apart\n\
+    ///          level: Info, completion at Info, target: {}\n\
+    ///          expected: 1000 edges\n\
+    ///          milestones: 250, 500\n\
+    ///          output budget: 1048576 bytes\n\
+    ///          numbers: compact\n\
+    ///          unit: Count\n\
+    ///          time left: GlobalAverage\n\
+    ///          display: Log\n\
+    ///          memory: off\n\
+    ///          updates after finish: Panic\n\
+    ///          version: {}",
+    ///         LOG_TARGET, VERSION
+    ///     )
+    /// );
+    /// ```
+    pub fn describe_pending(self) -> String {
+        // Silencing the logger replaces its sink
+        let sink = self.opts.sink.as_ref().map(|sink| sink.name().to_owned());
+        let mut pl = self.silenced().start();
+        pl.config.sink = sink;
+        pl.describe()
+    }
+    /// Turns off everything a logger started only to be inspected could
+    /// write or trigger
+    fn silenced(mut self) -> Self {
+        self.opts.sink = Some(SharedSink::new(std::io::sink()));
+        self.opts.ignore_global_switch = true;
+        self.opts.async_reports = false;
+        self.opts.stall_reports = false;
+        self.opts.strict_cadence = false;
+        self.opts.prescan = None;
+        self.opts.sample_sender = None;
+        self.opts.on_log = None;
+        self.opts.update_hook = None;
+        self.opts.interval_hook = None;
+        self.opts.reporters.clear();
+        #[cfg(feature = "desktop-notification")]
+        {
+            self.opts.desktop_notification = false;
+        }
+        #[cfg(all(feature = "query-socket", unix))]
+        {
+            self.opts.query_socket = None;
+        }
+        self
+    }
+    /// Builds the `ProgressLogger`, starting the internal timer.
+    pub fn start(self) -> ProgressLogger {
+        let opts = self.opts;
+        let now = clock::now();
+        let frequency = opts.frequency();
+        let items_name = opts.items.unwrap_or_else(|| "updates".to_owned());
+        // Milestones are stored as counts, in decreasing order, so that the
+        // next one is at the end of the vector
+        let expected_updates = opts.expected_updates.unwrap_or(0);
+        // With nothing expected, there is no fraction to reach
+        let mut milestones: Vec<u64> = opts
+            .milestones
+            .iter()
+            .filter(|_| expected_updates > 0)
+            .map(|fraction| (fraction * expected_updates as f64).ceil() as u64)
+            .collect();
+        milestones.sort_unstable_by(|a, b| b.cmp(a));
+        milestones.dedup();
+        // Without an expected duration, the frequency is derived from the
+        // rate observed in the first report interval
+        let target_report_count = match opts.expected_duration {
+            None if expected_updates > 0 => opts.target_report_count,
+            _ => None,
+        };
+        let number_format = if opts.compact_numbers {
+            NumberFormat::SiPrefix
+        } else {
+            let terminal = opts.sink.is_none() && std::io::stderr().is_terminal();
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let default = if opts.color.unwrap_or(terminal && !no_color) {
+                DigitGrouping::Underline
+            } else if terminal {
+                DigitGrouping::Space
+            } else {
+                DigitGrouping::Plain
+            };
+            NumberFormat::Grouped(opts.digit_grouping.unwrap_or(default))
+        };
+        let target = opts
+            .log_target
+            .unwrap_or_else(|| crate::LOG_TARGET.to_owned());
+        let background_reports = if opts.async_reports {
+            Some(BackgroundReports::spawn(Renderer {
+                items: items_name.clone(),
+                number_format,
+                duration_format: opts.duration_format,
+                unit: opts.unit,
+                level: opts.log_level,
+                target: target.clone(),
+                fields: opts.periodic_fields,
+                memory_reporting: opts.memory_reporting,
+                cpu_display: opts.cpu_display,
+                prefix: opts.prefix.clone(),
+                sink: opts.sink.clone(),
+            }))
+        } else {
+            None
+        };
+        let grid_names = opts.grid_names;
+        let grid = opts.grid.map(|(outer, inner)| {
+            let (outer_name, inner_name) =
+                grid_names.unwrap_or_else(|| ("outer".to_owned(), "inner".to_owned()));
+            Grid::new(outer, inner, outer_name, inner_name, now)
+        });
+        #[allow(unused_mut)]
+        let bar = if opts.display == DisplayMode::TerminalBar
+            && opts.sink.is_none()
+            && std::io::stderr().is_terminal()
+        {
+            Some(TerminalBar::default())
+        } else {
+            None
+        };
+        let config = ResolvedConfig {
+            items: items_name.clone(),
+            frequency,
+            level: opts.log_level,
+            log_target: target.clone(),
+            completion_level: opts.completion_level.unwrap_or(opts.log_level),
+            expected_updates: opts.expected_updates,
+            expected_work_units: opts.expected_work_units,
+            milestones: milestones.iter().rev().copied().collect(),
+            min_report_spacing: opts.min_report_spacing,
+            output_budget: opts.output_budget,
+            stall_warning_fraction: opts.stall_warning_fraction,
+            clock_skew_threshold: opts.clock_skew_threshold,
+            compact_numbers: opts.compact_numbers,
+            digit_grouping: match number_format {
+                NumberFormat::Grouped(grouping) => Some(grouping),
+                NumberFormat::SiPrefix => None,
+            },
+            unit: opts.unit,
+            eta_estimator: opts.eta_estimator,
+            display: if bar.is_some() {
+                DisplayMode::TerminalBar
+            } else {
+                DisplayMode::Log
+            },
+            memory_reporting: opts.memory_reporting,
+            cpu_reporting: opts.memory_reporting && opts.cpu_display,
+            sink: opts.sink.as_ref().map(|sink| sink.name().to_owned()),
+            after_finish: opts.after_finish,
+            version: crate::VERSION,
+        };
+        let mut pl = ProgressLogger {
+            start: now,
+            started_at: SystemTime::now(),
+            start_time_in_text: opts.start_time_in_text,
+            count: 0,
+            expected_updates: opts.expected_updates,
+            items: sanitize(&items_name).into_owned(),
+            items_name,
+            last_logged: now,
+            last_logged_count: 0,
+            heartbeat: if opts.heartbeat {
+                Some(Heartbeat::new())
+            } else {
+                None
+            },
+            warned_in_interval: false,
+            min_report_spacing: opts.min_report_spacing,
+            rate_limited_reports: 0,
+            expected_work_units: opts.expected_work_units,
+            output_budget: opts.output_budget,
+            output_bytes: 0,
+            config,
+            sink: opts.sink,
+            sink_panicked: AtomicBool::new(false),
+            prefix: opts.prefix,
+            sink_error_policy: opts.sink_error_policy,
+            ratios: Vec::new(),
+            grid,
+            batches: None,
+            approximate_expectation: opts.approximate_expectation,
+            background_reports,
+            watchdog: None,
+            lock_wait: Duration::from_secs(0),
+            target_report_count,
+            sample_sender: opts.sample_sender,
+            on_log: opts.on_log,
+            update_hook: opts.update_hook,
+            interval_hook: opts.interval_hook,
+            exclusive: Default::default(),
+            reporters: opts.reporters,
+            phases: Default::default(),
+            lock_wait_warning: opts.lock_wait_warning,
+            retries: 0,
+            last_logged_retries: 0,
+            backoff: None,
+            max_backoff: None,
+            retry_warning: opts.retry_warning,
+            periodic_fields: opts.periodic_fields,
+            final_fields: opts.final_fields,
+            open_fds_enabled: opts.open_fds,
+            open_fds_warning: opts.open_fds_warning,
+            open_fds_warned: false,
+            peak_open_fds: None,
+            finished: false,
+            after_finish: opts.after_finish,
+            post_finish_updates: 0,
+            paused_at: None,
+            paused_duration: Duration::from_secs(0),
+            output_throttled: false,
+            last_report_at: None,
+            last_interval: None,
+            last_logged_work_units: 0.0,
+            stall_episodes: 0,
+            stalled_duration: Duration::from_secs(0),
+            stalled_in_interval: false,
+            stall_warning_fraction: opts.stall_warning_fraction,
+            ignore_global_switch: opts.ignore_global_switch,
+            output_latency: Default::default(),
+            slow_output_threshold: opts.slow_output_threshold,
+            clock_skew_threshold: opts.clock_skew_threshold,
+            clock_skew_events: 0,
+            last_clock_skew_warning: None,
+            ettc: None,
+            work_units: 0.0,
+            work_unit_throughput: None,
+            last_note: None,
+            suppressed_notes: 0,
+            eta: crate::eta::Eta::new(opts.eta_estimator),
+            curve: {
+                let mut curve = crate::ThroughputCurve::new();
+                curve.observe(Duration::from_secs(0), 0);
+                curve
+            },
+            samples: if opts.collect_samples {
+                Some(crate::samples::SampleLog::new())
+            } else {
+                None
+            },
+            key_fraction: None,
+            next_milestone: milestones.last().copied().unwrap_or(u64::MAX),
+            next_check_at: 1,
+            check_every: 1,
+            last_check: now,
+            active_at_check: Duration::from_secs(0),
+            count_at_check: 0,
+            next_event: milestones.last().copied().unwrap_or(u64::MAX).min(1),
+            milestones,
+            #[cfg(feature = "desktop-notification")]
+            desktop_notification: opts.desktop_notification,
+            #[cfg(all(feature = "query-socket", unix))]
+            query_server: None,
+            #[cfg(feature = "async")]
+            publisher: None,
+            #[cfg(feature = "nvml")]
+            gpu_memory: if opts.gpu_devices.is_empty() {
+                None
+            } else {
+                Some(crate::gpu::GpuMemory::new(opts.gpu_devices))
+            },
+            managed_by_display: false,
+            bar,
+            frequency,
+            number_format,
+            duration_format: opts.duration_format,
+            unit: opts.unit,
+            level: opts.log_level,
+            completion_level: opts.completion_level.unwrap_or(opts.log_level),
+            target,
+            memory_reporting: opts.memory_reporting,
+            cpu_display: opts.cpu_display,
+            peak_memory_kb: None,
+            metadata: opts.metadata,
+            metadata_in_text: opts.metadata_in_text,
+            extra_fields: opts.extra_fields,
+        };
+        pl.dispatch(|pl| ProgressEvent::Started {
+            config: pl.config.clone(),
+        });
+        if let Some(prescan) = opts.prescan {
+            let line = match prescan.estimated_from {
+                Some(bytes) => format!(
+                    "Estimated {} {} from the first {} bytes in {:.2?}",
+                    prescan.records, pl.items, bytes, prescan.elapsed
+                ),
+                None => format!(
+                    "Counted {} {} in {:.2?}",
+                    prescan.records, pl.items, prescan.elapsed
+                ),
+            };
+            pl.publish(pl.level, ProgressEvent::Notice { line });
+        }
+        if opts.stall_reports || opts.strict_cadence {
+            let renderer = pl.renderer();
+            pl.watchdog = Some(Watchdog::spawn(
+                renderer,
+                pl.frequency,
+                pl.ignore_global_switch,
+                opts.strict_cadence,
+            ));
+        }
+        #[cfg(all(feature = "query-socket", unix))]
+        {
+            if let Some(path) = opts.query_socket {
+                match crate::query::QueryServer::bind(path.clone(), pl.snapshot().to_json()) {
+                    Ok(server) => pl.query_server = Some(server),
+                    Err(e) => crate::emit::emit(
+                        &pl.target,
+                        Level::Warn,
+                        format_args!("could not serve progress on {}: {}", path.display(), e),
+                    ),
+                }
+            }
+        }
+        pl
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{ProgressLogger, ProgressLoggerBuilder, ProgressReport};
+use std::ops::{Deref, DerefMut};
+
+/// A logger for a phase of the computation tracked by a parent logger,
+/// obtained with `ProgressLogger::child`. Its lines are prefixed with the
+/// name of the phase, e.g. `[build index] Done in 2.31s. 1 000 000 nodes
+/// (432 900.12 nodes/s)`, which gives the duration of the phase. The parent
+/// runs a phase of the same name meanwhile, see `start_phase`, ended along
+/// with the child, whether by `stop` or by dropping it.
+///
+/// The child dereferences to its own `ProgressLogger`, to update it.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let mut pl = ProgressLogger::builder().with_items_name("queries").start();
+/// let mut index = pl.child_with("build index", |builder| {
+///     builder
+///         .with_items_name("nodes")
+///         .with_expected_updates(1000u32)
+/// });
+/// for _ in 0..1000 {
+///     index.update(1u32);
+/// }
+/// // Logs `[build index] Done in ...`, and ends the phase
+/// let report = index.stop();
+/// assert_eq!(report.total_items, 1000);
+/// {
+///     let mut warm_up = pl.child("warm up");
+///     warm_up.update(10u32);
+///     // Dropping the child ends the phase as well
+/// }
+/// for _ in 0..100 {
+///     pl.update(1u32);
+/// }
+/// pl.stop();
+/// ```
+pub struct ChildLogger<'a> {
+    parent: &'a mut ProgressLogger,
+    pl: ProgressLogger,
+}
+
+impl ChildLogger<'_> {
+    /// Logs the completion statement of the child, ending the phase of the
+    /// parent, and returns the outcome of the phase
+    pub fn stop(mut self) -> ProgressReport {
+        self.pl.finish();
+        self.pl.report()
+    }
+}
+
+impl Deref for ChildLogger<'_> {
+    type Target = ProgressLogger;
+
+    fn deref(&self) -> &ProgressLogger {
+        &self.pl
+    }
+}
+
+impl DerefMut for ChildLogger<'_> {
+    fn deref_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+}
+
+impl Drop for ChildLogger<'_> {
+    fn drop(&mut self) {
+        self.pl.finish();
+        self.parent.end_phase();
+    }
+}
+
+impl ProgressLogger {
+    /// Starts a logger for the phase `name` of the computation, inheriting
+    /// the items name, frequency, level, number format, fields and sink of
+    /// this logger, see `ChildLogger`. This logger runs the phase `name`
+    /// until the child stops, and its periodic reports show it, see
+    /// `Field::Phases`.
+    pub fn child<S: Into<String>>(&mut self, name: S) -> ChildLogger<'_> {
+        self.child_with(name, |builder| builder)
+    }
+
+    /// Same as `child`, with `configure` overriding the inherited options
+    /// or adding others, e.g. the expected number of updates of the phase
+    pub fn child_with<S, T, F>(&mut self, name: S, configure: F) -> ChildLogger<'_>
+    where
+        S: Into<String>,
+        F: FnOnce(ProgressLoggerBuilder) -> ProgressLoggerBuilder<T>,
+    {
+        self.start_phase(name);
+        let name = self.phases.running().unwrap_or_default();
+        let builder = ProgressLoggerBuilder::inheriting(self, format!("[{}] ", name));
+        ChildLogger {
+            pl: configure(builder).start(),
+            parent: self,
+        }
+    }
+}
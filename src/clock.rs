@@ -0,0 +1,47 @@
+//! The clock of the loggers. With the `test-util` feature it runs ahead of
+//! `Instant::now` by an offset that tests advance, see
+//! `test_util::advance`, so that time-based behaviour can be tested without
+//! sleeping, including by the thread of the stall reports, see
+//! `watchdog::clock_advanced`. Durations measured for their own sake, such
+//! as the output latency, keep reading the real clock.
+
+use std::time::Instant;
+
+#[cfg(feature = "test-util")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "test-util")]
+use std::time::Duration;
+
+/// How far the clock was advanced, in nanoseconds
+#[cfg(feature = "test-util")]
+static OFFSET_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// The current time of the loggers
+#[inline]
+pub(crate) fn now() -> Instant {
+    #[cfg(feature = "test-util")]
+    {
+        Instant::now() + Duration::from_nanos(OFFSET_NANOS.load(Ordering::Relaxed))
+    }
+    #[cfg(not(feature = "test-util"))]
+    {
+        Instant::now()
+    }
+}
+
+/// Moves the clock forward by `duration`, then waits for the threads of
+/// `with_stall_reports` and `with_strict_cadence` to check it
+#[cfg(feature = "test-util")]
+pub(crate) fn advance(duration: Duration) {
+    OFFSET_NANOS.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    crate::watchdog::clock_advanced();
+}
+
+/// Moves the clock back by `duration`, at most to where it started
+#[cfg(feature = "test-util")]
+pub(crate) fn rewind(duration: Duration) {
+    let nanos = duration.as_nanos() as u64;
+    let _ = OFFSET_NANOS.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |offset| {
+        Some(offset.saturating_sub(nanos))
+    });
+}
@@ -0,0 +1,197 @@
+use crate::{AfterFinish, Progress, ProgressLogger, ProgressReport, ProgressSnapshot};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+struct Shared {
+    /// the aggregate count of all the handles
+    count: AtomicU64,
+    /// the aggregate count at which the logger wants to be updated next:
+    /// its next clock check or milestone, or zero once finished, see
+    /// `ProgressLogger::next_event`
+    next_event: AtomicU64,
+    pl: Mutex<ProgressLogger>,
+}
+
+impl Shared {
+    /// Locks the logger, accounting for the time spent waiting, as
+    /// `ChunkHandle` does
+    fn lock(&self) -> MutexGuard<'_, ProgressLogger> {
+        match self.pl.try_lock() {
+            Ok(pl) => pl,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                let start = Instant::now();
+                let mut pl = self.pl.lock().unwrap_or_else(|e| e.into_inner());
+                pl.add_lock_wait(start.elapsed().max(Duration::from_nanos(1)));
+                pl
+            }
+        }
+    }
+
+    /// Brings the count of the logger up to date, letting it report if due
+    fn sync(&self, pl: &mut ProgressLogger) {
+        if pl.finished {
+            self.next_event.store(0, Ordering::Relaxed);
+            return;
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        pl.update(count.saturating_sub(pl.count));
+        self.next_event.store(pl.next_event, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let count = *self.count.get_mut();
+        let pl = self.pl.get_mut().unwrap_or_else(|e| e.into_inner());
+        pl.count = pl.count.max(count);
+        pl.finish();
+    }
+}
+
+/// A logger shared by several threads, obtained with
+/// `ProgressLoggerBuilder::start_concurrent`. Clones are cheap handles to
+/// the same logger, which can be moved into the closures of thread pools.
+/// Updates after the completion statement follow the policy of
+/// `with_after_finish`, as for `ProgressLogger`.
+///
+/// Updates add to an atomic counter, with no lock: only the update
+/// reaching the next clock check of the logger tries to lock it, and
+/// skips the check if another thread holds it, so workers never wait for
+/// each other. Reports show the aggregate count and throughput of all the
+/// handles. The completion statement is logged by `stop`, or when the
+/// last handle is dropped, only once. The time handles spend waiting for
+/// the logger, in `snapshot`, `report_now`, `stop` or updates after the
+/// completion statement, is in the `lock_wait` of the snapshots.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let pl = ProgressLogger::builder()
+///     .with_items_name("nodes")
+///     .with_expected_updates(4_000_000u32)
+///     .start_concurrent();
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let pl = pl.clone();
+///         std::thread::spawn(move || {
+///             for _ in 0..1_000_000 {
+///                 pl.update_light(1u32);
+///             }
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// assert_eq!(pl.count(), 4_000_000);
+/// assert_eq!(pl.snapshot().count, 4_000_000);
+/// let report = pl.stop();
+/// assert_eq!(report.total_items, 4_000_000);
+/// ```
+#[derive(Clone)]
+pub struct ConcurrentProgressLogger {
+    shared: Arc<Shared>,
+}
+
+impl ConcurrentProgressLogger {
+    pub(crate) fn new(pl: ProgressLogger) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                count: AtomicU64::new(pl.count),
+                next_event: AtomicU64::new(pl.next_event),
+                pl: Mutex::new(pl),
+            }),
+        }
+    }
+
+    /// Adds `n` to the shared counter, reporting if due, see
+    /// `ProgressLogger::update`
+    #[inline]
+    pub fn update<N: Into<u64>>(&self, n: N) {
+        let n = n.into();
+        let count = self.shared.count.fetch_add(n, Ordering::Relaxed) + n;
+        if count >= self.shared.next_event.load(Ordering::Relaxed) {
+            self.update_at_event(n);
+        }
+    }
+
+    /// Same as `update`, which is already as cheap as an atomic increment
+    /// between clock checks
+    #[inline]
+    pub fn update_light<N: Into<u64>>(&self, n: N) {
+        self.update(n);
+    }
+
+    /// Lets the logger check the clock, unless another thread is already
+    /// at it. Once finished, waits for the logger to apply the policy of
+    /// `with_after_finish` to the `n` updates just counted.
+    #[cold]
+    fn update_at_event(&self, n: u64) {
+        let mut pl = match self.shared.pl.try_lock() {
+            Ok(pl) => pl,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) if self.shared.next_event.load(Ordering::Relaxed) == 0 => {
+                self.shared.lock()
+            }
+            Err(TryLockError::WouldBlock) => return,
+        };
+        if pl.finished {
+            if pl.after_finish != AfterFinish::CountSilently {
+                self.shared.count.fetch_sub(n, Ordering::Relaxed);
+            }
+            pl.update(n);
+        } else {
+            self.shared.sync(&mut pl);
+        }
+    }
+
+    /// The aggregate count of all the handles
+    pub fn count(&self) -> u64 {
+        self.shared.count.load(Ordering::Relaxed)
+    }
+
+    /// The state of the logger, with the aggregate count
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let mut pl = self.shared.lock();
+        self.shared.sync(&mut pl);
+        pl.snapshot()
+    }
+
+    /// Reports the aggregate count right away, regardless of the
+    /// configured frequency, waiting for the thread holding the logger, if
+    /// any
+    pub fn report_now(&self) {
+        let mut pl = self.shared.lock();
+        self.shared.sync(&mut pl);
+        Progress::report_now(&mut *pl);
+    }
+
+    /// Same as `report_now`, named after `ProgressLogger::checkpoint`
+    pub fn checkpoint(&self) {
+        self.report_now();
+    }
+
+    /// Logs the completion statement with the aggregate count, if not
+    /// already logged, and returns the outcome of the run so far. Other
+    /// handles can still count, but there are no more reports.
+    pub fn stop(self) -> ProgressReport {
+        let mut pl = self.shared.lock();
+        pl.count = pl.count.max(self.count());
+        pl.finish();
+        self.shared.next_event.store(0, Ordering::Relaxed);
+        pl.report()
+    }
+}
+
+impl Progress for ConcurrentProgressLogger {
+    #[inline]
+    fn update(&mut self, n: u64) {
+        ConcurrentProgressLogger::update(self, n);
+    }
+
+    fn report_now(&mut self) {
+        ConcurrentProgressLogger::report_now(self);
+    }
+}
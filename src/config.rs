@@ -0,0 +1,241 @@
+use crate::snapshot::{json_number, json_string};
+use crate::{AfterFinish, DigitGrouping, DisplayMode, EtaEstimator, Level, Unit};
+use std::fmt::{self, Write as _};
+use std::time::Duration;
+
+/// A configuration rejected by `ProgressLoggerBuilder::preview`, as some
+/// of its options would not have the intended effect
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// `with_target_report_count` is ignored without an expected number of
+    /// updates or an expected duration
+    ReportCountWithoutTotal,
+    /// the minimum spacing between reports exceeds the frequency, spacing
+    /// out the reports more than requested
+    SpacingAboveFrequency {
+        spacing: Duration,
+        frequency: Duration,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::ReportCountWithoutTotal => write!(
+                f,
+                "a target number of reports requires an expected number of updates or an expected duration"
+            ),
+            ConfigError::SpacingAboveFrequency { spacing, frequency } => write!(
+                f,
+                "the minimum spacing between reports, {:.2?}, exceeds the frequency, {:.2?}",
+                spacing, frequency
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The configuration of a logger, with the defaults resolved, as of the call
+/// to `start`. Useful to archive along with the results of a run, as the
+/// interpretation of the numbers may depend on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedConfig {
+    pub items: String,
+    pub frequency: Duration,
+    /// the level of the periodic reports
+    pub level: Level,
+    pub log_target: String,
+    pub completion_level: Level,
+    pub expected_updates: Option<u64>,
+    pub expected_work_units: Option<f64>,
+    /// the counts at which milestone reports are due, in increasing order
+    pub milestones: Vec<u64>,
+    pub min_report_spacing: Duration,
+    pub output_budget: Option<u64>,
+    pub stall_warning_fraction: Option<f64>,
+    pub clock_skew_threshold: Option<Duration>,
+    pub compact_numbers: bool,
+    /// the grouping of the digits, `None` with compact numbers
+    pub digit_grouping: Option<DigitGrouping>,
+    pub unit: Unit,
+    pub eta_estimator: EtaEstimator,
+    /// the display in effect, `Log` if the terminal bar fell back to it
+    pub display: DisplayMode,
+    pub memory_reporting: bool,
+    pub cpu_reporting: bool,
+    /// the name of the sink, if any, see `SharedSink::name`
+    pub sink: Option<String>,
+    pub after_finish: AfterFinish,
+    /// the version of the crate that started the logger, see `VERSION`
+    pub version: &'static str,
+}
+
+impl ResolvedConfig {
+    /// Describes the configuration in a few lines of text, one per option,
+    /// e.g. for a `--progress-config` flag. Options left unset are omitted.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "items: {}", self.items).unwrap();
+        writeln!(
+            out,
+            "reports: every {:.2?}, at least {:.2?} apart",
+            self.frequency, self.min_report_spacing
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "level: {:?}, completion at {:?}, target: {}",
+            self.level, self.completion_level, self.log_target
+        )
+        .unwrap();
+        if let Some(expected_updates) = self.expected_updates {
+            writeln!(out, "expected: {} {}", expected_updates, self.items).unwrap();
+        }
+        if let Some(expected_work_units) = self.expected_work_units {
+            writeln!(out, "expected work units: {}", expected_work_units).unwrap();
+        }
+        if !self.milestones.is_empty() {
+            let milestones: Vec<String> = self.milestones.iter().map(u64::to_string).collect();
+            writeln!(out, "milestones: {}", milestones.join(", ")).unwrap();
+        }
+        if let Some(output_budget) = self.output_budget {
+            writeln!(out, "output budget: {} bytes", output_budget).unwrap();
+        }
+        if let Some(fraction) = self.stall_warning_fraction {
+            writeln!(
+                out,
+                "stall warning: above {:.0}% of wall time",
+                fraction * 100.0
+            )
+            .unwrap();
+        }
+        if let Some(threshold) = self.clock_skew_threshold {
+            writeln!(out, "clock skew warning: above {:.2?}", threshold).unwrap();
+        }
+        match self.digit_grouping {
+            Some(grouping) if !self.compact_numbers => {
+                writeln!(out, "numbers: grouped, {:?}", grouping).unwrap()
+            }
+            _ => writeln!(out, "numbers: compact").unwrap(),
+        }
+        writeln!(out, "unit: {:?}", self.unit).unwrap();
+        writeln!(out, "time left: {:?}", self.eta_estimator).unwrap();
+        writeln!(out, "display: {:?}", self.display).unwrap();
+        let memory = match (self.memory_reporting, self.cpu_reporting) {
+            (false, _) => "off",
+            (true, false) => "on",
+            (true, true) => "on, with CPU",
+        };
+        writeln!(out, "memory: {}", memory).unwrap();
+        if let Some(sink) = self.sink.as_ref() {
+            writeln!(out, "sink: {}", sink).unwrap();
+        }
+        writeln!(out, "updates after finish: {:?}", self.after_finish).unwrap();
+        write!(out, "version: {}", self.version).unwrap();
+        out
+    }
+
+    /// Renders the configuration as a single line JSON object, with
+    /// durations in seconds
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+        write!(out, "\"version\":{}", json_string(self.version)).unwrap();
+        write!(out, ",\"items\":{}", json_string(&self.items)).unwrap();
+        write!(
+            out,
+            ",\"frequency_secs\":{}",
+            json_number(self.frequency.as_secs_f64())
+        )
+        .unwrap();
+        write!(out, ",\"level\":{}", json_string(&format!("{:?}", self.level))).unwrap();
+        write!(out, ",\"log_target\":{}", json_string(&self.log_target)).unwrap();
+        write!(
+            out,
+            ",\"completion_level\":{}",
+            json_string(&format!("{:?}", self.completion_level))
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"expected_updates\":{}",
+            self.expected_updates
+                .map_or("null".to_owned(), |n| n.to_string())
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"expected_work_units\":{}",
+            self.expected_work_units
+                .map_or("null".to_owned(), json_number)
+        )
+        .unwrap();
+        out.push_str(",\"milestones\":[");
+        for (i, milestone) in self.milestones.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "{}", milestone).unwrap();
+        }
+        out.push(']');
+        write!(
+            out,
+            ",\"min_report_spacing_secs\":{}",
+            json_number(self.min_report_spacing.as_secs_f64())
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"output_budget\":{}",
+            self.output_budget
+                .map_or("null".to_owned(), |n| n.to_string())
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"stall_warning_fraction\":{}",
+            self.stall_warning_fraction
+                .map_or("null".to_owned(), json_number)
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"clock_skew_threshold_secs\":{}",
+            self.clock_skew_threshold
+                .map_or("null".to_owned(), |d| json_number(d.as_secs_f64()))
+        )
+        .unwrap();
+        write!(out, ",\"compact_numbers\":{}", self.compact_numbers).unwrap();
+        write!(
+            out,
+            ",\"digit_grouping\":{}",
+            self.digit_grouping
+                .map_or("null".to_owned(), |g| json_string(&format!("{:?}", g)))
+        )
+        .unwrap();
+        write!(out, ",\"unit\":{}", json_string(&format!("{:?}", self.unit))).unwrap();
+        write!(
+            out,
+            ",\"eta_estimator\":{}",
+            json_string(&format!("{:?}", self.eta_estimator))
+        )
+        .unwrap();
+        write!(out, ",\"display\":{}", json_string(&format!("{:?}", self.display))).unwrap();
+        write!(out, ",\"memory_reporting\":{}", self.memory_reporting).unwrap();
+        write!(out, ",\"cpu_reporting\":{}", self.cpu_reporting).unwrap();
+        write!(
+            out,
+            ",\"sink\":{}",
+            self.sink.as_deref().map_or("null".to_owned(), json_string)
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"after_finish\":{}",
+            json_string(&format!("{:?}", self.after_finish))
+        )
+        .unwrap();
+        out.push('}');
+        out
+    }
+}
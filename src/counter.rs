@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A counter that can be shared between threads and loggers, e.g. the bytes
+/// going through a `ProgressReader`, see `ProgressLogger::link_ratio`.
+#[derive(Clone, Debug, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The ratio between two counters, rendered in reports as `name 3.41:1`
+pub(crate) struct Ratio {
+    pub(crate) name: String,
+    pub(crate) numerator: Counter,
+    pub(crate) denominator: Counter,
+}
+
+impl Ratio {
+    pub(crate) fn render(&self) -> String {
+        let denominator = self.denominator.get();
+        if denominator == 0 {
+            format!("{} n/a", self.name)
+        } else {
+            format!(
+                "{} {:.2}:1",
+                self.name,
+                self.numerator.get() as f64 / denominator as f64
+            )
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+/// The number of slots of a `ThroughputCurve`
+pub(crate) const CURVE_SLOTS: usize = 256;
+
+/// The width of the slots of a new `ThroughputCurve`
+const INITIAL_RESOLUTION: Duration = Duration::from_millis(1);
+
+/// The count as a function of the elapsed time, downsampled in constant
+/// space, kept by every logger from its clock checks, see
+/// `ProgressLogger::throughput_curve`.
+///
+/// The run is split in 256 slots of equal duration, each keeping the last
+/// observation falling in it. When an observation falls past the last slot,
+/// adjacent slots are merged, doubling their duration, so that the curve
+/// always covers the whole run. The first observation is kept as well, so
+/// the curve starts and ends with the first and last observations.
+///
+/// ```
+/// use progress_logger::ThroughputCurve;
+/// use std::time::Duration;
+///
+/// let mut curve = ThroughputCurve::new();
+/// for second in 0..=3600 {
+///     curve.observe(Duration::from_secs(second), 10 * second);
+/// }
+/// let points = curve.points();
+/// assert!(points.len() <= 257);
+/// assert_eq!(points[0], (Duration::from_secs(0), 0));
+/// assert_eq!(points[points.len() - 1], (Duration::from_secs(3600), 36_000));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThroughputCurve {
+    /// the first observation, then at most one observation per slot
+    points: Vec<(Duration, u64)>,
+    /// the duration of the slots
+    resolution: Duration,
+}
+
+impl Default for ThroughputCurve {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThroughputCurve {
+    pub fn new() -> Self {
+        Self {
+            points: Vec::with_capacity(CURVE_SLOTS + 1),
+            resolution: INITIAL_RESOLUTION,
+        }
+    }
+
+    /// Records `count` updates after `elapsed`. Observations must come in
+    /// order of elapsed time: earlier ones are ignored.
+    pub fn observe(&mut self, elapsed: Duration, count: u64) {
+        let last = match self.points.last() {
+            Some(&(last, _)) => last,
+            None => {
+                self.points.push((elapsed, count));
+                return;
+            }
+        };
+        if elapsed < last {
+            return;
+        }
+        while self.slot(elapsed) >= CURVE_SLOTS as u128 {
+            self.merge();
+        }
+        // The first observation is never replaced
+        if self.points.len() > 1 && self.slot(last) == self.slot(elapsed) {
+            *self.points.last_mut().unwrap() = (elapsed, count);
+        } else {
+            self.points.push((elapsed, count));
+        }
+    }
+
+    /// The observations kept, as pairs of elapsed time and count, in order
+    pub fn points(&self) -> &[(Duration, u64)] {
+        &self.points
+    }
+
+    /// The duration of the slots, at most 1/256 of the run once it is
+    /// longer than 256 ms
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.points.capacity() * std::mem::size_of::<(Duration, u64)>()
+    }
+
+    fn slot(&self, elapsed: Duration) -> u128 {
+        elapsed.as_nanos() / self.resolution.as_nanos()
+    }
+
+    /// Doubles the duration of the slots, keeping the last observation of
+    /// each pair of slots merged
+    fn merge(&mut self) {
+        self.resolution *= 2;
+        let mut kept = 1;
+        for i in 1..self.points.len() {
+            let point = self.points[i];
+            if kept > 1 && self.slot(self.points[kept - 1].0) == self.slot(point.0) {
+                self.points[kept - 1] = point;
+            } else {
+                self.points[kept] = point;
+                kept += 1;
+            }
+        }
+        self.points.truncate(kept);
+    }
+}
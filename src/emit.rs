@@ -0,0 +1,291 @@
+//! Emission of the messages of all loggers through the `log` crate, or
+//! `tracing` with the `tracing` feature. Without either, messages only go
+//! to the sinks set with `with_sink` and to the reporters.
+//!
+//! A logging backend may itself use a `ProgressLogger`, e.g. to track the
+//! flush of a large batch, so that emitting a report can trigger a nested
+//! report from the same thread. Nested messages are dropped rather than
+//! interleaved with the one being emitted: the counters are still updated,
+//! only the message is lost.
+//!
+//! ```
+//! use progress_logger::ProgressLogger;
+//! use std::sync::Mutex;
+//! use std::time::Duration;
+//! # if cfg!(feature = "tracing") { return; }
+//!
+//! /// A backend tracking its own work with a logger, which reports
+//! /// through the backend itself
+//! struct Sink {
+//!     flushed: Mutex<Option<ProgressLogger>>,
+//! }
+//!
+//! impl log::Log for Sink {
+//!     fn enabled(&self, _: &log::Metadata) -> bool {
+//!         true
+//!     }
+//!
+//!     fn log(&self, record: &log::Record) {
+//!         eprintln!("{}", record.args());
+//!         if let Some(pl) = self.flushed.lock().unwrap().as_mut() {
+//!             pl.update(1u32);
+//!         }
+//!     }
+//!
+//!     fn flush(&self) {}
+//! }
+//!
+//! let sink: &'static Sink = Box::leak(Box::new(Sink {
+//!     flushed: Mutex::new(None),
+//! }));
+//! log::set_logger(sink).unwrap();
+//! log::set_max_level(log::LevelFilter::Info);
+//! let flushed = ProgressLogger::builder()
+//!     .with_frequency(Duration::from_millis(0))
+//!     .with_min_report_spacing(Duration::from_millis(0))
+//!     .start();
+//! sink.flushed.lock().unwrap().replace(flushed);
+//!
+//! let mut pl = ProgressLogger::builder()
+//!     .with_frequency(Duration::from_millis(0))
+//!     .with_min_report_spacing(Duration::from_millis(0))
+//!     .start();
+//! for _ in 0..100 {
+//!     std::thread::sleep(Duration::from_millis(1));
+//!     pl.update(1u32);
+//! }
+//! pl.stop();
+//! let flushed = sink.flushed.lock().unwrap().take().unwrap();
+//! assert!(flushed.snapshot().count > 0);
+//! ```
+//!
+//! Messages are logged with the `LOG_TARGET` target, which includes the
+//! version of the crate, so that the reports of two semver-incompatible
+//! versions linked in the same program can be told apart, or filtered
+//! separately. Loggers can use their own target instead, see
+//! `ProgressLoggerBuilder::with_log_target`.
+//!
+//! With the `tracing` feature, messages are emitted as `tracing` events
+//! instead, always with the `LOG_TARGET` target: `tracing` stores the
+//! target in the static metadata of each call site, so it cannot change at
+//! runtime. The target set with `with_log_target` is recorded in the
+//! `log_target` field of every event instead, for subscribers to filter
+//! on. The events of reports and completion statements also record
+//! `items_name`, `count`, `throughput_per_sec` and, when known, `ettc_secs`
+//! as fields, for collectors to query them without parsing the message.
+//! The `log` crate is then optional: the levels of the API are the
+//! `Level` of this crate.
+//!
+//! No lock of the crate is held while a message is emitted: loggers render
+//! their messages first, and the only lock, the snapshot of the query
+//! socket, is released before emission.
+
+#[cfg(feature = "tracing")]
+use std::cell::RefCell;
+use std::cell::Cell;
+use std::fmt;
+
+/// The level of a message, mirroring the levels of the `log` crate, from
+/// which it converts with the `log` feature
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// failures, such as sink errors
+    Error = 1,
+    /// warnings, such as stalls
+    Warn,
+    /// the default level of the reports
+    Info,
+    Debug,
+    Trace,
+}
+
+#[cfg(feature = "log")]
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<Level> for log::Level {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => log::Level::Error,
+            Level::Warn => log::Level::Warn,
+            Level::Info => log::Level::Info,
+            Level::Debug => log::Level::Debug,
+            Level::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// The target of the messages logged by the loggers, `progress_logger::v`
+/// followed by the semver-compatible part of the version of the crate:
+/// the major version, or the major and minor versions before 1.0.
+///
+/// ```
+/// assert!(progress_logger::LOG_TARGET.starts_with("progress_logger::v"));
+/// assert!(progress_logger::VERSION.starts_with(&progress_logger::LOG_TARGET[18..]));
+/// ```
+pub const LOG_TARGET: &str = if MAJOR.len() == 1 && MAJOR.as_bytes()[0] == b'0' {
+    concat!(
+        "progress_logger::v",
+        env!("CARGO_PKG_VERSION_MAJOR"),
+        ".",
+        env!("CARGO_PKG_VERSION_MINOR")
+    )
+} else {
+    concat!("progress_logger::v", env!("CARGO_PKG_VERSION_MAJOR"))
+};
+
+const MAJOR: &str = env!("CARGO_PKG_VERSION_MAJOR");
+
+thread_local! {
+    /// Whether this thread is currently emitting a message
+    static EMITTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The figures of the report being emitted by this thread, recorded as
+/// fields of its event, see `report_fields`
+#[cfg(feature = "tracing")]
+struct Figures {
+    items: String,
+    count: u64,
+    throughput_per_sec: f64,
+    ettc: Option<f64>,
+}
+
+#[cfg(feature = "tracing")]
+thread_local! {
+    static FIGURES: RefCell<Option<Figures>> = const { RefCell::new(None) };
+}
+
+/// Forgets the figures of the report once emitted
+#[cfg(feature = "tracing")]
+pub(crate) struct FiguresGuard(());
+
+#[cfg(feature = "tracing")]
+impl Drop for FiguresGuard {
+    fn drop(&mut self) {
+        FIGURES.with(|figures| figures.borrow_mut().take());
+    }
+}
+
+/// Records the figures of a report, for the messages emitted by this
+/// thread until the guard is dropped to carry them as fields
+#[cfg(feature = "tracing")]
+pub(crate) fn report_fields(
+    items: &str,
+    count: u64,
+    throughput_per_sec: f64,
+    ettc: Option<f64>,
+) -> FiguresGuard {
+    FIGURES.with(|figures| {
+        figures.borrow_mut().replace(Figures {
+            items: items.to_owned(),
+            count,
+            throughput_per_sec,
+            ettc,
+        })
+    });
+    FiguresGuard(())
+}
+
+/// Clears the flag when the emission is over, even if the backend panics
+struct EmittingGuard<'a>(&'a Cell<bool>);
+
+impl Drop for EmittingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+/// Evaluates `$body` with `$name` bound to the `tracing` level matching
+/// `$level`, which the macros of `tracing` need as a constant
+#[cfg(feature = "tracing")]
+macro_rules! with_tracing_level {
+    ($level:expr, $name:ident => $body:expr) => {
+        match $level {
+            Level::Error => {
+                const $name: tracing::Level = tracing::Level::ERROR;
+                $body
+            }
+            Level::Warn => {
+                const $name: tracing::Level = tracing::Level::WARN;
+                $body
+            }
+            Level::Info => {
+                const $name: tracing::Level = tracing::Level::INFO;
+                $body
+            }
+            Level::Debug => {
+                const $name: tracing::Level = tracing::Level::DEBUG;
+                $body
+            }
+            Level::Trace => {
+                const $name: tracing::Level = tracing::Level::TRACE;
+                $body
+            }
+        }
+    };
+}
+
+/// Emits a message with the given target and level, unless this thread is
+/// already emitting one.
+pub(crate) fn emit(target: &str, level: Level, args: fmt::Arguments) {
+    EMITTING.with(|emitting| {
+        if emitting.replace(true) {
+            return;
+        }
+        let _guard = EmittingGuard(emitting);
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        log::log!(target: target, level.into(), "{}", args);
+        #[cfg(feature = "tracing")]
+        FIGURES.with(|figures| match figures.borrow().as_ref() {
+            Some(figures) => with_tracing_level!(level, LEVEL => tracing::event!(
+                target: LOG_TARGET,
+                LEVEL,
+                log_target = target,
+                items_name = figures.items.as_str(),
+                count = figures.count,
+                throughput_per_sec = figures.throughput_per_sec,
+                ettc_secs = figures.ettc,
+                "{}",
+                args
+            )),
+            None => with_tracing_level!(level, LEVEL => tracing::event!(
+                target: LOG_TARGET,
+                LEVEL,
+                log_target = target,
+                "{}",
+                args
+            )),
+        });
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let _ = (target, level, args);
+    });
+}
+
+/// Whether the backend takes messages with the given target and level
+pub(crate) fn enabled(target: &str, level: Level) -> bool {
+    #[cfg(all(feature = "log", not(feature = "tracing")))]
+    {
+        log::log_enabled!(target: target, level.into())
+    }
+    #[cfg(feature = "tracing")]
+    {
+        let _ = target;
+        with_tracing_level!(level, LEVEL => tracing::enabled!(target: LOG_TARGET, LEVEL))
+    }
+    #[cfg(not(any(feature = "log", feature = "tracing")))]
+    {
+        let _ = (target, level);
+        false
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The maximum number of counts kept by `EtaEstimator::Window`: past it,
+/// the oldest ones are evicted early, shortening the window
+pub(crate) const WINDOW_CAPACITY: usize = 1024;
+
+/// The throughput the time to completion is estimated on, see
+/// `with_eta_estimator`.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum EtaEstimator {
+    /// The average throughput since the start, the default
+    #[default]
+    GlobalAverage,
+    /// The throughput of the report intervals, smoothed with weight `alpha`
+    /// for the last one, between 0 excluded and 1: the higher, the faster
+    /// the estimate follows changes of speed
+    ExponentialMovingAverage { alpha: f64 },
+    /// The throughput over the reports of the last window of time
+    Window(Duration),
+}
+
+/// The state of an `EtaEstimator`, updated at each report
+pub(crate) enum Eta {
+    GlobalAverage,
+    ExponentialMovingAverage { alpha: f64, rate: Option<f64> },
+    Window(ThroughputWindow),
+}
+
+impl Eta {
+    pub(crate) fn new(estimator: EtaEstimator) -> Self {
+        match estimator {
+            EtaEstimator::GlobalAverage => Eta::GlobalAverage,
+            EtaEstimator::ExponentialMovingAverage { alpha } => {
+                Eta::ExponentialMovingAverage { alpha, rate: None }
+            }
+            EtaEstimator::Window(window) => Eta::Window(ThroughputWindow::new(window)),
+        }
+    }
+
+    pub(crate) fn estimator(&self) -> EtaEstimator {
+        match self {
+            Eta::GlobalAverage => EtaEstimator::GlobalAverage,
+            Eta::ExponentialMovingAverage { alpha, .. } => {
+                EtaEstimator::ExponentialMovingAverage { alpha: *alpha }
+            }
+            Eta::Window(window) => EtaEstimator::Window(window.window),
+        }
+    }
+
+    /// Records the report at `now`, with the count and the last report
+    /// interval, if any
+    pub(crate) fn observe(&mut self, now: Instant, count: u64, interval: Option<(Duration, u64)>) {
+        match self {
+            Eta::GlobalAverage => {}
+            Eta::ExponentialMovingAverage { alpha, rate } => {
+                if let Some(instant) = interval.and_then(instant_rate) {
+                    *rate =
+                        Some(rate.map_or(instant, |rate| *alpha * instant + (1.0 - *alpha) * rate));
+                }
+            }
+            Eta::Window(window) => window.push(now, count),
+        }
+    }
+
+    /// The throughput to estimate the time to completion on, falling back
+    /// to `average` until the estimator has seen some progress, and its
+    /// label in reports, `None` for the global average
+    pub(crate) fn throughput(&self, average: f64) -> (f64, Option<&'static str>) {
+        let (estimate, label) = match self {
+            Eta::GlobalAverage => return (average, None),
+            Eta::ExponentialMovingAverage { rate, .. } => (*rate, "(EMA ETA)"),
+            Eta::Window(window) => (window.throughput(), "(windowed ETA)"),
+        };
+        match estimate.filter(|t| t.is_finite() && *t > 0.0) {
+            Some(estimate) => (estimate, Some(label)),
+            None => (average, Some("(avg ETA)")),
+        }
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Eta::Window(window) => window.heap_size(),
+            _ => 0,
+        }
+    }
+}
+
+/// The throughput of a report interval, in updates per second
+pub(crate) fn instant_rate((duration, count): (Duration, u64)) -> Option<f64> {
+    Some(count as f64 / duration.as_secs_f64()).filter(|t| t.is_finite())
+}
+
+/// The counts at the reports of the last `window`
+pub(crate) struct ThroughputWindow {
+    pub(crate) window: Duration,
+    counts: VecDeque<(Instant, u64)>,
+}
+
+impl ThroughputWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            counts: VecDeque::new(),
+        }
+    }
+
+    /// Records `count` at `now`, evicting the counts older than the window
+    fn push(&mut self, now: Instant, count: u64) {
+        while let Some(&(at, _)) = self.counts.front() {
+            if now.saturating_duration_since(at) <= self.window
+                && self.counts.len() < WINDOW_CAPACITY
+            {
+                break;
+            }
+            self.counts.pop_front();
+        }
+        self.counts.push_back((now, count));
+    }
+
+    /// The throughput over the window, in updates per second, `None` with
+    /// fewer than two counts
+    fn throughput(&self) -> Option<f64> {
+        let &(first_at, first_count) = self.counts.front()?;
+        let &(last_at, last_count) = self.counts.back()?;
+        let elapsed = last_at.saturating_duration_since(first_at).as_secs_f64();
+        Some(last_count.saturating_sub(first_count) as f64 / elapsed)
+    }
+
+    fn heap_size(&self) -> usize {
+        self.counts.capacity() * std::mem::size_of::<(Instant, u64)>()
+    }
+}
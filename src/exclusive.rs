@@ -0,0 +1,64 @@
+//! Detection, in debug builds, of a logger used by two threads at once,
+//! which safe code cannot do, but unsafe code or foreign callers through
+//! the C API can, corrupting the counts. Moving a logger to another thread
+//! is fine: only overlapping uses are caught. Release builds check nothing.
+
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(debug_assertions)]
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// A non-zero number identifying this thread
+    static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The thread currently updating the logger, if any
+#[derive(Default)]
+pub(crate) struct ExclusiveUse {
+    #[cfg(debug_assertions)]
+    thread: AtomicU64,
+}
+
+impl ExclusiveUse {
+    /// Marks the logger as used by this thread, panicking if another one is
+    /// using it. Returns whether this is the outermost use of this thread,
+    /// which must then call `exit`.
+    #[inline]
+    pub(crate) fn enter(&self) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            let thread = THREAD.with(|thread| *thread);
+            match self
+                .thread
+                .compare_exchange(0, thread, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => true,
+                Err(other) => {
+                    debug_assert_eq!(
+                        other, thread,
+                        "a ProgressLogger is being updated by two threads at once: share a \
+                         ConcurrentProgressLogger instead, see \
+                         ProgressLoggerBuilder::start_concurrent"
+                    );
+                    false
+                }
+            }
+        }
+        #[cfg(not(debug_assertions))]
+        false
+    }
+
+    /// Ends the use started by `enter`, if it returned `true`
+    #[inline]
+    pub(crate) fn exit(&self, outermost: bool) {
+        #[cfg(debug_assertions)]
+        if outermost {
+            self.thread.store(0, Ordering::Release);
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = outermost;
+    }
+}
@@ -0,0 +1,19 @@
+/// Counts the file descriptors open in the current process, by listing a
+/// directory with one entry per descriptor, without inspecting them.
+/// Returns `None` on platforms without such a directory.
+pub(crate) fn count_open_fds() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    let dir = "/proc/self/fd";
+    #[cfg(all(unix, not(target_os = "linux")))]
+    let dir = "/dev/fd";
+    #[cfg(unix)]
+    {
+        let entries = std::fs::read_dir(dir).ok()?;
+        // The listing itself holds a descriptor, which is not counted
+        Some((entries.count() as u64).saturating_sub(1))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
@@ -0,0 +1,136 @@
+//! C bindings, enabled with the `ffi` feature. The corresponding header,
+//! `ffi/progress_logger.h`, is generated by cbindgen from this module, see
+//! `cbindgen.toml`, and `ffi/test.c` shows how to use them.
+//!
+//! To link the crate in a C or C++ program, build it as a static library:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! # Ownership and thread safety
+//!
+//! `pl_new` returns an owned pointer, which must be released by passing it to
+//! `pl_stop` exactly once; the pointer must not be used afterwards. A logger
+//! can be moved to another thread, but it must not be used by several threads
+//! at the same time: calls on the same pointer must be externally synchronized.
+//! Debug builds of the crate catch some unsynchronized calls, which then do
+//! nothing, as for any other panic.
+//!
+//! No panic crosses the language boundary: if the Rust side panics, the
+//! functions return without doing anything (`pl_new` returns `NULL`).
+
+use crate::ProgressLogger;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::time::Duration;
+
+/// Creates a new progress logger and starts its timer. `name` is the name of
+/// the items being counted, and can be `NULL` to use the default. An
+/// `expected` number of updates of zero means that it is unknown, and a
+/// `frequency_ms` of zero selects the default frequency. Returns `NULL` on
+/// failure.
+///
+/// # Safety
+///
+/// `name` must be either `NULL` or a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pl_new(
+    name: *const c_char,
+    expected: u64,
+    frequency_ms: u64,
+) -> *mut ProgressLogger {
+    let name = if name.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+    };
+    catch_unwind(move || {
+        let mut builder = ProgressLogger::builder();
+        if let Some(name) = name {
+            builder = builder.with_items_name(name);
+        }
+        if frequency_ms > 0 {
+            builder = builder.with_frequency(Duration::from_millis(frequency_ms));
+        }
+        let pl = builder
+            .with_expected_updates_opt(Some(expected).filter(|&n| n > 0))
+            .start();
+        Box::into_raw(Box::new(pl))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Adds `n` to the counter, see `ProgressLogger::update`.
+///
+/// # Safety
+///
+/// `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn pl_update(pl: *mut ProgressLogger, n: u64) {
+    if let Some(pl) = pl.as_mut() {
+        let _ = catch_unwind(AssertUnwindSafe(|| pl.update(n)));
+    }
+}
+
+/// Same as `pl_update`, kept for compatibility.
+///
+/// # Safety
+///
+/// `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn pl_update_light(pl: *mut ProgressLogger, n: u64) {
+    if let Some(pl) = pl.as_mut() {
+        let _ = catch_unwind(AssertUnwindSafe(|| pl.update(n)));
+    }
+}
+
+/// Logs the completion statement and releases the logger.
+///
+/// # Safety
+///
+/// `pl` must be a pointer returned by `pl_new` and not yet stopped, or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn pl_stop(pl: *mut ProgressLogger) {
+    if !pl.is_null() {
+        let pl = Box::from_raw(pl);
+        let _ = catch_unwind(AssertUnwindSafe(move || pl.stop()));
+    }
+}
+
+/// A minimal logger writing records to standard error, for programs that
+/// don't configure the `log` crate on the Rust side.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static STDERR_LOGGER: StderrLogger = StderrLogger;
+
+/// Sends the reports to standard error. Returns 0 on success, and -1 if a
+/// logger was already installed.
+#[no_mangle]
+pub extern "C" fn pl_init_stderr_logging() -> c_int {
+    let installed = catch_unwind(|| {
+        log::set_logger(&STDERR_LOGGER)
+            .map(|()| log::set_max_level(log::LevelFilter::Info))
+            .is_ok()
+    });
+    match installed {
+        Ok(true) => 0,
+        _ => -1,
+    }
+}
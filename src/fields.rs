@@ -0,0 +1,67 @@
+/// The items that can appear in the reports, see `with_periodic_fields`
+/// and `with_final_fields`. Regardless of the selection, fields are always
+/// rendered in the order below.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// memory and swap usage, along with GPU memory and open file
+    /// descriptors, if enabled; periodic reports only
+    Memory,
+    /// the time elapsed since the start
+    Elapsed,
+    /// the count of items
+    Count,
+    /// the position in the grid set with `with_grid` in periodic reports,
+    /// and the durations of the outer iterations in the final line
+    Grid,
+    /// the work units accumulated with `update_weighted`
+    WorkUnits,
+    /// the total items estimated from the expected work units; periodic
+    /// reports only
+    EstimatedTotal,
+    /// the fraction of the key range covered; periodic reports only
+    KeyRange,
+    /// the estimated time to completion; periodic reports only
+    TimeLeft,
+    /// the throughput
+    Throughput,
+    /// the ratios linked with `link_ratio`
+    Ratios,
+    /// the retries counted with `record_retry`, with the current backoff
+    /// in periodic reports and the largest one in the final line
+    Retries,
+    /// the fields added with `with_extra_field`; periodic reports only
+    ExtraFields,
+    /// the number of suppressed notes and rate limited reports, and the
+    /// errors of the sink, see `with_sink_error_policy`
+    Suppressed,
+    /// the time the workers of `parallel_chunks` waited for the logger;
+    /// final line only
+    LockWait,
+    /// the running phase in periodic reports, and the memory growth of
+    /// each phase in the final line, see `start_phase`
+    Phases,
+    /// the time spent stalled, see `with_stall_warning_fraction`; final
+    /// line only
+    Stalls,
+    /// the heartbeat string, see `with_heartbeat_string`; final line only
+    Heartbeat,
+    /// the metadata, if `with_metadata_in_text` is set
+    Metadata,
+}
+
+/// A set of fields, as a bit set indexed by the discriminant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FieldSet(u32);
+
+impl FieldSet {
+    pub(crate) const ALL: FieldSet = FieldSet(u32::MAX);
+
+    pub(crate) fn new(fields: &[Field]) -> Self {
+        FieldSet(fields.iter().fold(0, |set, &field| set | 1 << field as u32))
+    }
+
+    #[inline]
+    pub(crate) fn contains(self, field: Field) -> bool {
+        self.0 & 1 << field as u32 != 0
+    }
+}
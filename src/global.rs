@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static GLOBALLY_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables the reports of all the progress loggers of the process,
+/// for instance to silence progress output during an incident without
+/// redeploying. Loggers keep counting while disabled, they just don't emit
+/// any line. Loggers built with `with_ignore_global_switch(true)` are not affected.
+///
+/// If several semver-incompatible versions of the crate are linked in the
+/// same program, each has its own switch, affecting only its loggers.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// progress_logger::set_global_enabled(false);
+/// let mut pl = ProgressLogger::builder().start();
+/// pl.update(10u32);
+/// pl.stop(); // silent
+/// progress_logger::set_global_enabled(true);
+/// # assert!(progress_logger::is_globally_enabled());
+/// ```
+pub fn set_global_enabled(enabled: bool) {
+    GLOBALLY_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether reports are globally enabled, see `set_global_enabled`.
+#[inline]
+pub fn is_globally_enabled() -> bool {
+    GLOBALLY_ENABLED.load(Ordering::Relaxed)
+}
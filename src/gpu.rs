@@ -0,0 +1,57 @@
+use crate::emit::emit;
+use crate::LOG_TARGET;
+use crate::Level;
+use nvml_wrapper::Nvml;
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// Samples the memory usage of GPUs through NVML
+pub(crate) struct GpuMemory {
+    /// `None` if NVML could not be initialized, e.g. for lack of a driver
+    nvml: Option<Nvml>,
+    devices: Vec<u32>,
+}
+
+impl GpuMemory {
+    pub(crate) fn new(devices: Vec<u32>) -> Self {
+        let nvml = match Nvml::init() {
+            Ok(nvml) => Some(nvml),
+            Err(e) => {
+                emit(
+                    LOG_TARGET,
+                    Level::Warn,
+                    format_args!(
+                        "GPU memory reporting disabled, could not initialize NVML: {}",
+                        e
+                    ),
+                );
+                None
+            }
+        };
+        Self { nvml, devices }
+    }
+
+    /// Renders the memory usage of the devices, e.g. `gpu0: 38.2 / 40.0 GiB`.
+    /// Devices that cannot be queried are omitted.
+    pub(crate) fn render(&self) -> Option<String> {
+        let nvml = self.nvml.as_ref()?;
+        let rendered: Vec<String> = self
+            .devices
+            .iter()
+            .filter_map(|&index| {
+                let info = nvml.device_by_index(index).ok()?.memory_info().ok()?;
+                Some(format!(
+                    "gpu{}: {:.1} / {:.1} GiB",
+                    index,
+                    info.used as f64 / GIB,
+                    info.total as f64 / GIB
+                ))
+            })
+            .collect();
+        if rendered.is_empty() {
+            None
+        } else {
+            Some(rendered.join(", "))
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::pretty::NumberFormat;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// The maximum number of outer iteration durations kept: the oldest ones
+/// are dropped first
+pub(crate) const DURATION_CAPACITY: usize = 256;
+
+/// The position in a grid of outer × inner iterations, see
+/// `ProgressLoggerBuilder::with_grid`
+pub(crate) struct Grid {
+    pub(crate) outer_total: u64,
+    pub(crate) inner_total: u64,
+    pub(crate) outer_name: String,
+    pub(crate) inner_name: String,
+    /// the completed outer iterations
+    pub(crate) outer: u64,
+    /// the completed inner iterations of the current outer one
+    pub(crate) inner: u64,
+    outer_started: Instant,
+    /// the duration of the last completed outer iterations
+    pub(crate) durations: Vec<Duration>,
+}
+
+impl Grid {
+    pub(crate) fn new(
+        outer_total: u64,
+        inner_total: u64,
+        outer_name: String,
+        inner_name: String,
+        now: Instant,
+    ) -> Self {
+        Self {
+            outer_total,
+            inner_total,
+            outer_name,
+            inner_name,
+            outer: 0,
+            inner: 0,
+            outer_started: now,
+            durations: Vec::new(),
+        }
+    }
+
+    /// Goes back to the first iteration, see `ProgressLogger::restart`
+    pub(crate) fn restart(&mut self, now: Instant) {
+        self.outer = 0;
+        self.inner = 0;
+        self.outer_started = now;
+        self.durations.clear();
+    }
+
+    /// Completes the current outer iteration, returning the inner
+    /// iterations it skipped, which still count towards the overall
+    /// progress
+    pub(crate) fn outer_step(&mut self, now: Instant) -> u64 {
+        let skipped = self.inner_total.saturating_sub(self.inner);
+        if self.durations.len() >= DURATION_CAPACITY {
+            self.durations.remove(0);
+        }
+        self.durations.push(now - self.outer_started);
+        self.outer_started = now;
+        self.outer += 1;
+        self.inner = 0;
+        skipped
+    }
+
+    /// Renders the position as `epoch 3/10, batch 412/5 000 (overall 28.2%)`,
+    /// numbering outer iterations from 1 while they are in progress
+    pub(crate) fn render(&self, fmt: NumberFormat, line: &mut String) {
+        // Saturating, as with the expected updates of `with_grid`
+        let linear = self
+            .outer
+            .saturating_mul(self.inner_total)
+            .saturating_add(self.inner.min(self.inner_total));
+        let total = self.outer_total.saturating_mul(self.inner_total);
+        let overall = if total > 0 {
+            100.0 * linear as f64 / total as f64
+        } else {
+            100.0
+        };
+        write!(
+            line,
+            ", {} {}/{}, {} {}/{} (overall {:.1}%)",
+            self.outer_name,
+            fmt.int(self.outer.saturating_add(1).min(self.outer_total)),
+            fmt.int(self.outer_total),
+            self.inner_name,
+            fmt.int(self.inner),
+            fmt.int(self.inner_total),
+            overall
+        )
+        .unwrap();
+    }
+
+    /// Renders the durations of the last completed outer iterations
+    pub(crate) fn render_durations(&self, line: &mut String) {
+        if self.durations.is_empty() {
+            return;
+        }
+        write!(line, ", {} durations", self.outer_name).unwrap();
+        if self.outer as usize > self.durations.len() {
+            write!(line, " (last {})", self.durations.len()).unwrap();
+        }
+        line.push(':');
+        for duration in self.durations.iter() {
+            write!(line, " {:.2?}", duration).unwrap();
+        }
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.durations.capacity() * std::mem::size_of::<Duration>()
+            + self.outer_name.capacity()
+            + self.inner_name.capacity()
+    }
+}
@@ -0,0 +1,139 @@
+//! Progress inside recursive algorithms, which have no single loop to
+//! instrument. Passing `&mut ProgressLogger` down the recursion conflicts
+//! with the other mutable state threaded through it, so a `ProgressHandle`
+//! borrows the logger once and is then shared by `&` reference.
+
+use crate::progress::Progress;
+use crate::ProgressLogger;
+use std::cell::{Cell, RefCell};
+
+/// A handle to a logger updated through `&self`, see
+/// `ProgressLogger::handle`. It is meant to be passed down recursive calls
+/// next to mutable state, which a `&mut ProgressLogger` would conflict with.
+///
+/// Updates made while the logger is borrowed by `with_logger` are kept
+/// aside and applied as soon as the borrow ends, so no update is lost
+/// whatever the nesting.
+///
+/// ```
+/// use progress_logger::{ProgressHandle, ProgressLogger};
+///
+/// struct Node {
+///     children: Vec<Node>,
+/// }
+///
+/// // The depth of each node, in visit order, threaded as mutable state
+/// fn visit(node: &Node, depth: usize, depths: &mut Vec<usize>, pl: &ProgressHandle) {
+///     depths.push(depth);
+///     pl.update(1u32);
+///     for child in &node.children {
+///         visit(child, depth + 1, depths, pl);
+///     }
+/// }
+///
+/// let leaf = || Node { children: vec![] };
+/// let tree = Node {
+///     children: vec![Node { children: vec![leaf(), leaf()] }, leaf()],
+/// };
+/// let mut pl = ProgressLogger::builder().with_items_name("nodes").start();
+/// let mut depths = Vec::new();
+/// visit(&tree, 0, &mut depths, &pl.handle());
+/// assert_eq!(pl.count(), 5);
+/// assert_eq!(depths, [0, 1, 2, 2, 1]);
+/// pl.stop();
+/// ```
+pub struct ProgressHandle<'a> {
+    pl: RefCell<&'a mut ProgressLogger>,
+    /// the updates made while the logger was borrowed
+    pending: Cell<u64>,
+}
+
+impl<'a> ProgressHandle<'a> {
+    pub(crate) fn new(pl: &'a mut ProgressLogger) -> Self {
+        Self {
+            pl: RefCell::new(pl),
+            pending: Cell::new(0),
+        }
+    }
+
+    /// Adds `cnt` to the counter of the logger, see `ProgressLogger::update`
+    #[inline]
+    pub fn update<N: Into<u64>>(&self, cnt: N) {
+        let cnt = cnt.into();
+        match self.pl.try_borrow_mut() {
+            Ok(mut pl) => pl.update(cnt),
+            Err(_) => self.pending.set(self.pending.get() + cnt),
+        }
+    }
+
+    /// Reports progress right away, see `ProgressLogger::checkpoint`. It
+    /// does nothing if called from `with_logger`, which can checkpoint the
+    /// logger itself.
+    pub fn checkpoint(&self) {
+        if let Ok(mut pl) = self.pl.try_borrow_mut() {
+            pl.checkpoint();
+        }
+    }
+
+    /// The count of the logger, including the updates not applied yet.
+    /// Called from `with_logger`, it only counts the latter.
+    pub fn count(&self) -> u64 {
+        let applied = self.pl.try_borrow().map_or(0, |pl| pl.count());
+        applied + self.pending.get()
+    }
+
+    /// Calls `f` with the logger, for the operations not offered by the
+    /// handle. Updates made through the handle while `f` runs are applied
+    /// when it returns.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let mut pl = ProgressLogger::builder().start();
+    /// let handle = pl.handle();
+    /// handle.with_logger(|pl| {
+    ///     pl.update(1u32);
+    ///     handle.update(2u32);
+    ///     assert_eq!(pl.count(), 1);
+    /// });
+    /// assert_eq!(handle.count(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If called from `f` itself.
+    pub fn with_logger<R>(&self, f: impl FnOnce(&mut ProgressLogger) -> R) -> R {
+        let mut pl = self
+            .pl
+            .try_borrow_mut()
+            .expect("ProgressHandle::with_logger called from its own closure");
+        let result = f(&mut pl);
+        let pending = self.pending.take();
+        if pending > 0 {
+            pl.update(pending);
+        }
+        result
+    }
+}
+
+impl Drop for ProgressHandle<'_> {
+    /// Applies the updates left aside if `f` of `with_logger` panicked
+    fn drop(&mut self) {
+        let pl = self.pl.get_mut();
+        let pending = self.pending.take();
+        if pending > 0 {
+            pl.update(pending);
+        }
+    }
+}
+
+impl Progress for &ProgressHandle<'_> {
+    #[inline]
+    fn update(&mut self, n: u64) {
+        ProgressHandle::update(self, n);
+    }
+
+    fn report_now(&mut self) {
+        self.checkpoint();
+    }
+}
@@ -0,0 +1,90 @@
+/// The maximum length of the heartbeat string: longer runs are downsampled
+pub(crate) const HEARTBEAT_CAPACITY: usize = 512;
+
+/// An interval without anything noteworthy
+pub(crate) const NORMAL: char = '.';
+/// An interval in which the counter did not advance
+pub(crate) const STALLED: char = 's';
+/// An interval in which a warning was logged
+pub(crate) const WARNING: char = '!';
+
+/// A one character per report interval timeline of a run, e.g.
+/// `....ss...!....`. When the capacity is reached, pairs of characters are
+/// merged and each character covers twice as many intervals as before, so
+/// that the whole run is covered in constant space.
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    chars: String,
+    /// the number of intervals covered by each character
+    stride: usize,
+    /// the merged character for the intervals not yet covered by `chars`
+    pending: Option<char>,
+    pending_intervals: usize,
+}
+
+/// The most severe of two characters, which survives merging
+fn worst(a: char, b: char) -> char {
+    let severity = |c| match c {
+        WARNING => 2,
+        STALLED => 1,
+        _ => 0,
+    };
+    if severity(b) > severity(a) {
+        b
+    } else {
+        a
+    }
+}
+
+impl Heartbeat {
+    pub(crate) fn new() -> Self {
+        Self {
+            chars: String::with_capacity(HEARTBEAT_CAPACITY),
+            stride: 1,
+            pending: None,
+            pending_intervals: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, c: char) {
+        let merged = self.pending.map_or(c, |p| worst(p, c));
+        self.pending_intervals += 1;
+        if self.pending_intervals < self.stride {
+            self.pending = Some(merged);
+            return;
+        }
+        self.chars.push(merged);
+        self.pending = None;
+        self.pending_intervals = 0;
+        if self.chars.len() >= HEARTBEAT_CAPACITY {
+            let downsampled: String = self
+                .chars
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| pair.iter().map(|&b| b as char).fold(NORMAL, worst))
+                .collect();
+            self.chars = downsampled;
+            self.stride *= 2;
+        }
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.chars.capacity()
+    }
+
+    /// The timeline so far, including the intervals not yet merged in a
+    /// full character
+    pub(crate) fn render(&self) -> String {
+        let mut s = self.chars.clone();
+        if let Some(pending) = self.pending {
+            s.push(pending);
+        }
+        s
+    }
+
+    /// The number of report intervals covered by each character
+    pub(crate) fn stride(&self) -> usize {
+        self.stride
+    }
+}
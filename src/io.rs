@@ -0,0 +1,307 @@
+use crate::builder::WithTotal;
+use crate::{Counter, ProgressLogger, ProgressLoggerBuilder};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The number of bytes the pre-scans of `ProgressLogger::for_lines_in` and
+/// `ProgressLogger::for_records` read before falling back to an estimate
+pub const PRESCAN_LIMIT: u64 = 1 << 30;
+
+/// The outcome of a pre-scan, logged when the logger starts
+pub(crate) struct PreScan {
+    pub(crate) records: u64,
+    pub(crate) elapsed: Duration,
+    /// the bytes scanned, if the count is extrapolated from them
+    pub(crate) estimated_from: Option<u64>,
+}
+
+/// The name of a file as shown in reports: its stem, or the whole path if
+/// it has none.
+fn file_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+impl ProgressLogger {
+    /// Creates a builder counting `lines`, with the number of lines of the
+    /// file at `path` as the expected number of updates, see `for_records`.
+    pub fn for_lines_in<P: AsRef<Path>>(path: P) -> io::Result<ProgressLoggerBuilder<WithTotal>> {
+        let mut file = File::open(path)?;
+        Ok(Self::for_records(&mut file, b'\n')?.with_items_name("lines"))
+    }
+
+    /// Creates a builder counting `records`, with the number of records
+    /// separated by `delimiter` as the expected number of updates. The last
+    /// record counts whether or not it ends with the delimiter. The reader
+    /// is scanned up to `PRESCAN_LIMIT` bytes, extrapolating the count from
+    /// them if it is longer, and is then rewound to where it was, ready for
+    /// the actual processing. The time taken by the scan is logged when the
+    /// logger starts.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::io::Cursor;
+    ///
+    /// let mut input = Cursor::new("a\nb\nc\n");
+    /// let pl = ProgressLogger::for_records(&mut input, b'\n')?.start();
+    /// assert_eq!(pl.snapshot().expected_updates, Some(3));
+    /// assert_eq!(input.position(), 0);
+    ///
+    /// // Without the trailing newline
+    /// let path = std::env::temp_dir().join("progress-prescan-example.txt");
+    /// std::fs::write(&path, "a\nb\nc")?;
+    /// let pl = ProgressLogger::for_lines_in(&path)?.start();
+    /// assert_eq!(pl.snapshot().expected_updates, Some(3));
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn for_records<R: Read + Seek>(
+        reader: &mut R,
+        delimiter: u8,
+    ) -> io::Result<ProgressLoggerBuilder<WithTotal>> {
+        Self::for_records_with_limit(reader, delimiter, PRESCAN_LIMIT)
+    }
+
+    /// Like `for_records`, scanning at most `limit` bytes
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::io::Cursor;
+    ///
+    /// let mut input = Cursor::new("a\nb\n".repeat(100));
+    /// // Extrapolated from the first 40 bytes
+    /// let pl = ProgressLogger::for_records_with_limit(&mut input, b'\n', 40)?.start();
+    /// assert_eq!(pl.snapshot().expected_updates, Some(200));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn for_records_with_limit<R: Read + Seek>(
+        reader: &mut R,
+        delimiter: u8,
+        limit: u64,
+    ) -> io::Result<ProgressLoggerBuilder<WithTotal>> {
+        let scan_start = Instant::now();
+        let origin = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))? - origin;
+        reader.seek(SeekFrom::Start(origin))?;
+        let mut buf = vec![0; 1 << 16];
+        let (mut scanned, mut delimiters, mut last) = (0u64, 0u64, None);
+        let mut limited = (&mut *reader).take(limit);
+        loop {
+            let n = match limited.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            delimiters += buf[..n].iter().filter(|&&b| b == delimiter).count() as u64;
+            scanned += n as u64;
+            last = Some(buf[n - 1]);
+        }
+        reader.seek(SeekFrom::Start(origin))?;
+        let (records, estimated_from) = if scanned < len {
+            let estimate = (delimiters as f64 * len as f64 / scanned.max(1) as f64).round();
+            (estimate as u64, Some(scanned))
+        } else {
+            let unterminated = last.is_some_and(|last| last != delimiter);
+            (delimiters + unterminated as u64, None)
+        };
+        let mut builder = ProgressLogger::builder()
+            .with_items_name("records")
+            .with_expected_updates(records);
+        builder.set_prescan(PreScan {
+            records,
+            elapsed: scan_start.elapsed(),
+            estimated_from,
+        });
+        Ok(builder)
+    }
+}
+
+/// A reader counting the bytes read through it.
+///
+/// ```
+/// use progress_logger::ProgressReader;
+/// use std::io::Read;
+///
+/// let path = std::env::temp_dir().join("progress-reader-example.csv");
+/// std::fs::write(&path, "a,b\n1,2\n")?;
+/// // Reports `N bytes`, with the time to completion based on the file size
+/// let mut reader = ProgressReader::open(&path)?;
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+/// assert_eq!(reader.logger().snapshot().expected_updates, Some(8));
+/// assert_eq!(reader.logger().snapshot().count, 8);
+/// reader.stop();
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ProgressReader<R> {
+    inner: R,
+    pl: ProgressLogger,
+    bytes: Counter,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R, pl: ProgressLogger) -> Self {
+        Self {
+            inner,
+            pl,
+            bytes: Counter::new(),
+        }
+    }
+}
+
+impl<R> ProgressReader<R> {
+    pub fn logger(&self) -> &ProgressLogger {
+        &self.pl
+    }
+
+    pub fn logger_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+
+    /// A counter of the bytes read so far, which can be shared, e.g. with
+    /// `ProgressLogger::link_ratio`
+    pub fn counter(&self) -> Counter {
+        self.bytes.clone()
+    }
+
+    /// Stops the logger, logging the completion statement, and returns the
+    /// wrapped reader
+    pub fn stop(self) -> R {
+        self.pl.stop();
+        self.inner
+    }
+}
+
+impl ProgressReader<File> {
+    /// Opens the file at `path`, counting `bytes` with the file size as
+    /// the expected number of updates. The file name is attached to the
+    /// reports as the `file` metadata.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let builder = ProgressLogger::builder()
+            .with_items_name("bytes")
+            .with_metadata("file", file_name(path))
+            .with_metadata_in_text(true);
+        Self::open_with(path, builder)
+    }
+
+    /// Like `open`, but building the logger from the given builder, which
+    /// is only configured with the file size as the expected number of
+    /// updates.
+    pub fn open_with<P: AsRef<Path>, T>(
+        path: P,
+        builder: ProgressLoggerBuilder<T>,
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let pl = builder.with_expected_updates(len).start();
+        Ok(Self::new(file, pl))
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.add(n as u64);
+        self.pl.update(n as u64);
+        Ok(n)
+    }
+}
+
+/// A writer counting the bytes written through it.
+///
+/// ```
+/// use progress_logger::ProgressWriter;
+/// use std::io::Write;
+///
+/// let path = std::env::temp_dir().join("progress-writer-example.csv");
+/// let mut writer = ProgressWriter::create(&path)?;
+/// for i in 0..1000 {
+///     writeln!(writer, "{},{}", i, i * i)?;
+/// }
+/// let written = writer.logger().snapshot().count;
+/// writer.stop()?;
+/// assert_eq!(std::fs::metadata(&path)?.len(), written);
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ProgressWriter<W> {
+    inner: W,
+    pl: ProgressLogger,
+    bytes: Counter,
+}
+
+impl<W: Write> ProgressWriter<W> {
+    pub fn new(inner: W, pl: ProgressLogger) -> Self {
+        Self {
+            inner,
+            pl,
+            bytes: Counter::new(),
+        }
+    }
+
+    pub fn logger(&self) -> &ProgressLogger {
+        &self.pl
+    }
+
+    pub fn logger_mut(&mut self) -> &mut ProgressLogger {
+        &mut self.pl
+    }
+
+    /// A counter of the bytes written so far, which can be shared, e.g.
+    /// with `ProgressLogger::link_ratio`
+    pub fn counter(&self) -> Counter {
+        self.bytes.clone()
+    }
+
+    /// Flushes the wrapped writer, then stops the logger, logging the
+    /// completion statement, and returns the wrapped writer
+    pub fn stop(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        self.pl.stop();
+        Ok(self.inner)
+    }
+}
+
+impl ProgressWriter<File> {
+    /// Creates (or truncates) the file at `path`, counting `bytes`. The
+    /// file name is attached to the reports as the `file` metadata. The
+    /// final size is not known in advance, so there is no time to
+    /// completion: see `create_with` to provide an estimate.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let builder = ProgressLogger::builder()
+            .with_items_name("bytes")
+            .with_metadata("file", file_name(path))
+            .with_metadata_in_text(true);
+        Self::create_with(path, builder)
+    }
+
+    /// Like `create`, but building the logger from the given builder
+    pub fn create_with<P: AsRef<Path>, T>(
+        path: P,
+        builder: ProgressLoggerBuilder<T>,
+    ) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(file, builder.start()))
+    }
+}
+
+impl<W: Write> Write for ProgressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes.add(n as u64);
+        self.pl.update(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
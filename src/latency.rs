@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// The number of reports averaged by `OutputLatency`
+const WINDOW: usize = 8;
+
+/// The time taken to write the last reports, see
+/// `with_slow_output_threshold`
+#[derive(Default)]
+pub(crate) struct OutputLatency {
+    recent: [Duration; WINDOW],
+    /// the number of reports measured so far
+    measured: usize,
+}
+
+impl OutputLatency {
+    pub(crate) fn record(&mut self, latency: Duration) {
+        self.recent[self.measured % WINDOW] = latency;
+        self.measured += 1;
+    }
+
+    /// The average over the last reports, `None` before the first one
+    pub(crate) fn average(&self) -> Option<Duration> {
+        let len = self.measured.min(WINDOW);
+        if len == 0 {
+            return None;
+        }
+        Some(self.recent[..len].iter().sum::<Duration>() / len as u32)
+    }
+
+    /// Whether the average exceeds `threshold`, once a few reports have
+    /// been measured, so that a single hiccup is not enough
+    pub(crate) fn exceeds(&self, threshold: Duration) -> bool {
+        self.measured >= WINDOW / 2 && self.average().is_some_and(|average| average > threshold)
+    }
+}
@@ -1,8 +1,204 @@
-#[macro_use]
-extern crate log;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::time::{Duration, Instant};
-use sysinfo::{System, SystemExt};
+#[cfg(feature = "std")]
+mod background;
+#[cfg(feature = "std")]
+mod bar;
+#[cfg(feature = "std")]
+mod batches;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod child;
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod counter;
+#[cfg(feature = "std")]
+mod curve;
+#[cfg(feature = "std")]
+mod emit;
+#[cfg(feature = "std")]
+mod eta;
+#[cfg(feature = "std")]
+mod exclusive;
+#[cfg(feature = "std")]
+mod fds;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod fields;
+#[cfg(feature = "std")]
+mod global;
+#[cfg(feature = "nvml")]
+mod gpu;
+#[cfg(feature = "std")]
+mod grid;
+#[cfg(feature = "std")]
+mod handle;
+#[cfg(feature = "std")]
+mod heartbeat;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "std")]
+mod latency;
+#[cfg(feature = "std")]
+mod memory;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "std")]
+mod multi;
+#[cfg(feature = "desktop-notification")]
+mod notification;
+#[cfg(feature = "async")]
+mod notifier;
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "std")]
+mod phases;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "std")]
+mod pretty;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(all(feature = "query-socket", unix))]
+mod query;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "std")]
+mod reporter;
+#[cfg(feature = "std")]
+mod samples;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(all(feature = "test-util", feature = "std"))]
+pub mod test_util;
+#[cfg(feature = "std")]
+mod walk;
+#[cfg(feature = "std")]
+mod watchdog;
+
+#[cfg(feature = "std")]
+pub use bar::DisplayMode;
+#[cfg(feature = "std")]
+pub use batches::BatchTracker;
+#[cfg(feature = "std")]
+pub use builder::{NoTotal, ProgressLoggerBuilder, ProgressLoggerBuilderWithTotal, WithTotal};
+#[cfg(feature = "std")]
+pub use child::ChildLogger;
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentProgressLogger;
+#[cfg(feature = "std")]
+pub use config::{ConfigError, ResolvedConfig};
+#[cfg(feature = "std")]
+pub use counter::Counter;
+#[cfg(feature = "std")]
+pub use curve::ThroughputCurve;
+#[cfg(feature = "std")]
+use emit::emit;
+#[cfg(feature = "std")]
+pub use emit::{Level, LOG_TARGET};
+#[cfg(feature = "std")]
+pub use eta::EtaEstimator;
+#[cfg(feature = "std")]
+pub use fields::Field;
+#[cfg(feature = "std")]
+use fields::FieldSet;
+#[cfg(feature = "std")]
+pub use global::{is_globally_enabled, set_global_enabled};
+#[cfg(feature = "std")]
+pub use handle::ProgressHandle;
+#[cfg(feature = "std")]
+use heartbeat::Heartbeat;
+#[cfg(feature = "std")]
+pub use io::{ProgressReader, ProgressWriter, PRESCAN_LIMIT};
+#[cfg(feature = "std")]
+pub use memory::{memory_refreshes, set_memory_probe, set_memory_sampling_interval};
+#[cfg(feature = "std")]
+use metadata::Metadata;
+#[cfg(feature = "std")]
+pub use multi::{DisplayOrdering, MultiLoggerDisplay};
+#[cfg(feature = "async")]
+pub use notifier::{AtFraction, NextReport, ProgressNotifier};
+#[cfg(feature = "std")]
+pub use parallel::{parallel_chunks, ChunkHandle};
+#[cfg(feature = "std")]
+use pretty::{sanitize, NumberFormat};
+#[cfg(feature = "std")]
+pub use pretty::{DigitGrouping, DurationFormat, Unit};
+#[cfg(feature = "std")]
+pub use progress::{NoProgress, Progress};
+#[cfg(feature = "std")]
+pub use report::ProgressReport;
+#[cfg(feature = "std")]
+pub use reporter::{ProgressEvent, Reporter, WarningKind};
+#[cfg(feature = "std")]
+pub use samples::{ProgressSample, ThroughputSlope};
+#[cfg(feature = "std")]
+use sink::{sink_failed, SinkWrite};
+#[cfg(feature = "std")]
+pub use sink::{MemorySink, SharedSink, SinkErrorPolicy};
+#[cfg(feature = "std")]
+pub use snapshot::ProgressSnapshot;
+#[cfg(feature = "std")]
+use std::fmt::Write as _;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(feature = "std")]
+pub use stream::SampleSender;
+#[cfg(feature = "std")]
+pub use walk::DirWalk;
+
+#[cfg(feature = "no_std_core")]
+pub mod no_std_core;
+
+/// The version of the crate, as found in the `version` field of the JSON
+/// renderings of snapshots and configurations.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// What to do with updates arriving after `ProgressLogger::finish`, see
+/// `with_after_finish`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AfterFinish {
+    /// Don't count them, logging a warning on the first one
+    #[default]
+    Ignore,
+    /// Count them, without reporting
+    CountSilently,
+    /// Panic, to track down the code updating a finished logger
+    Panic,
+}
+
+/// How many times `update` aims at reading the clock per report interval
+#[cfg(feature = "std")]
+const CLOCK_CHECKS_PER_INTERVAL: u32 = 16;
+/// The maximum number of updates between two clock checks, bounding how
+/// late a report can be when the rate of updates drops after a hot phase
+#[cfg(feature = "std")]
+const MAX_UPDATES_BETWEEN_CHECKS: u64 = 1 << 12;
+
+// Loggers can be built on one thread and moved to another: this is part of
+// the API, so adding a field that is not `Send` should fail to compile.
+#[cfg(feature = "std")]
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<ProgressLogger>();
+    assert_send::<ProgressLoggerBuilder>();
+};
 
 /// A tool to report the progress of computations. It can be built and configured
 /// using the `builder` function. If given the expected number of updates,
@@ -11,16 +207,24 @@ use sysinfo::{System, SystemExt};
 /// Progress is reported every 10 seconds by default. See the examples about how
 /// to change it.
 ///
-/// There are three methods to update the internal counter:
-///
-///  - `update`, for events that don't happen frequently
-///  - `update_light`, which tries to report (by checking the configured frequency
-///     of updates) only once every million updates. To be used in situations where
-///     updates are frequent: it's an order of magnitude faster than `update`.
+/// The internal counter is updated with `update`, which is cheap enough to
+/// be called in hot loops, as it tunes how often it reads the clock to the
+/// rate of updates, or with one of its variants, such as `update_weighted`.
 ///
 /// Reports are issued on the console using the `info!()` macro from the `log` crate.
 /// Therefore, the reports depend on your logging configuration.
 ///
+/// By default, the logger has no thread of its own: the time since the
+/// last report is only checked by the update methods. Reports are therefore
+/// emitted at the first update after the configured frequency has elapsed,
+/// and the actual interval between reports can be up to the frequency plus
+/// the time between two updates. For instance, with a frequency of 10
+/// seconds and an update every 7 seconds, reports come every 14 seconds.
+/// With `with_strict_cadence`, a thread reports every 10 seconds instead.
+///
+/// Both the logger and its builder are `Send`, so they can be moved to the
+/// thread doing the work. This is guaranteed for future versions as well.
+///
 /// Inspired by `ProgressLogger` in the [`dsiutil`](http://dsiutils.di.unimi.it/docs/it/unimi/dsi/logging/ProgressLogger.html) Java library.
 ///
 /// # Examples
@@ -69,231 +273,2797 @@ use sysinfo::{System, SystemExt};
 /// }
 /// pl.stop();
 /// ```
+///
+/// ## Attaching metadata
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let mut pl = ProgressLogger::builder()
+///     .with_metadata("experiment", "42")
+///     .with_metadata("dataset", "road network")
+///     .with_metadata_in_text(true)
+///     .start();
+/// for i in 0..10000 {
+///     pl.update(1u32);
+/// }
+/// assert_eq!(pl.metadata()[0], ("experiment".to_owned(), "42".to_owned()));
+/// pl.stop();
+/// ```
+///
+/// ## Custom fields
+/// ```
+/// use progress_logger::ProgressLogger;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// let queue_depth = Arc::new(AtomicUsize::new(0));
+/// let depth = Arc::clone(&queue_depth);
+/// // Reports end with `[queue_depth: 42]`
+/// let mut pl = ProgressLogger::builder()
+///     .with_extra_field("queue_depth", move || depth.load(Ordering::Relaxed).to_string())
+///     .start();
+/// for i in 0..10000 {
+///     queue_depth.store(i % 100, Ordering::Relaxed);
+///     pl.update(1u32);
+/// }
+/// pl.stop();
+/// ```
+///
+/// ## Rate-limited messages
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let mut pl = ProgressLogger::builder().start();
+/// for i in 0..10000 {
+///     if i % 100 == 0 {
+///         // Logged at most once every 10 seconds, the others are counted
+///         pl.note(format!("resyncing shard {}", i / 100));
+///     }
+///     pl.update(1u32);
+/// }
+/// pl.stop();
+/// ```
+///
+/// ## Compact numbers
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// // Reports numbers like `3.85M` rather than `3852000`
+/// let mut pl = ProgressLogger::builder()
+///     .with_compact_numbers(true)
+///     .start();
+/// for i in 0..10000 {
+///     pl.update(1u32);
+/// }
+/// pl.stop();
+/// ```
+#[cfg(feature = "std")]
 pub struct ProgressLogger {
     start: Instant,
+    /// the wall-clock time of `start`, unaffected by later clock changes
+    started_at: SystemTime,
+    start_time_in_text: bool,
     count: u64,
     expected_updates: Option<u64>,
+    /// the name of the items, as given by the user
+    items_name: String,
+    /// the name of the items, sanitized for rendering
     items: String,
     last_logged: Instant,
+    /// the value of the counter at the last report
+    last_logged_count: u64,
+    heartbeat: Option<Heartbeat>,
+    /// whether a warning has been logged since the last report
+    warned_in_interval: bool,
+    /// the time of the last report, if any
+    last_report_at: Option<Instant>,
+    /// the duration and counter increment between the last two reports
+    last_interval: Option<(Duration, u64)>,
+    /// the value of the work units at the last report
+    last_logged_work_units: f64,
+    /// the gaps of at least the frequency between two clock checks, see
+    /// `stall_episodes`
+    stall_episodes: u64,
+    stalled_duration: Duration,
+    /// whether a stall has been recorded since the last report, marking
+    /// the interval in the heartbeat string
+    stalled_in_interval: bool,
+    stall_warning_fraction: Option<f64>,
+    ignore_global_switch: bool,
+    clock_skew_threshold: Option<Duration>,
+    clock_skew_events: u32,
+    last_clock_skew_warning: Option<Instant>,
     /// the estimated time to completion, in seconds
     ettc: Option<f64>,
-    throughput: Option<f64>,
+    work_units: f64,
+    /// the expected total of work units, see `with_expected_work_units`
+    expected_work_units: Option<f64>,
+    work_unit_throughput: Option<f64>,
+    last_note: Option<Instant>,
+    suppressed_notes: u64,
+    samples: Option<samples::SampleLog>,
+    /// the count over time, fed at the clock checks
+    curve: ThroughputCurve,
+    /// the state of the estimator of the time to completion, see
+    /// `with_eta_estimator`
+    eta: eta::Eta,
+    /// the fraction of the key range covered, see `update_position_in_range`
+    key_fraction: Option<f64>,
+    /// the counts at which to report regardless of the time, in decreasing order
+    milestones: Vec<u64>,
+    /// the last element of `milestones`, or `u64::MAX` if there is none
+    next_milestone: u64,
+    /// the count at which to check the clock next
+    next_check_at: u64,
+    /// the number of updates between clock checks
+    check_every: u64,
+    last_check: Instant,
+    /// the active elapsed time and the count at `last_check`
+    active_at_check: Duration,
+    count_at_check: u64,
+    /// the smallest count requiring more than an increment: the minimum of
+    /// `next_check_at` and `next_milestone`, or zero once finished
+    next_event: u64,
+    min_report_spacing: Duration,
+    config: ResolvedConfig,
+    /// where to write the messages in place of the `log` crate
+    sink: Option<SharedSink>,
+    /// whether the sink panicked, see `output`
+    sink_panicked: AtomicBool,
+    /// the start of every line, e.g. the phase of a `ChildLogger`
+    prefix: Option<String>,
+    sink_error_policy: SinkErrorPolicy,
+    ratios: Vec<counter::Ratio>,
+    grid: Option<grid::Grid>,
+    /// the batches counted by `BatchTracker`, `None` if not tracking batches
+    batches: Option<u64>,
+    /// whether the expected number of updates is an estimate, see
+    /// `with_approximate_expectation`
+    approximate_expectation: bool,
+    /// the time workers waited to acquire the logger, see `parallel_chunks`
+    lock_wait: Duration,
+    lock_wait_warning: Option<f64>,
+    /// the number of reports to derive the frequency from, once the rate of
+    /// updates is known, see `with_target_report_count`
+    target_report_count: Option<u32>,
+    /// where to push the `(elapsed, count)` samples, see `with_sample_channel`
+    sample_sender: Option<Box<dyn SampleSender>>,
+    on_log: Option<OnLog>,
+    /// called with the increment of every update, see `with_update_hook`
+    update_hook: Option<UpdateHook>,
+    /// called with the count of every interval, see `with_interval_hook`
+    interval_hook: Option<UpdateHook>,
+    /// the thread updating the logger, checked in debug builds
+    exclusive: exclusive::ExclusiveUse,
+    /// the receivers of the events, see `with_reporter`
+    reporters: Vec<Box<dyn Reporter>>,
+    phases: phases::Phases,
+    /// the thread rendering the reports, if enabled with `with_async_reports`
+    background_reports: Option<background::BackgroundReports>,
+    /// the thread reporting while updates stall, if enabled with
+    /// `with_stall_reports`
+    watchdog: Option<watchdog::Watchdog>,
+    retries: u64,
+    last_logged_retries: u64,
+    /// the current and the largest backoff between retries
+    backoff: Option<Duration>,
+    max_backoff: Option<Duration>,
+    /// the retries per report interval above which to warn
+    retry_warning: Option<u64>,
+    periodic_fields: FieldSet,
+    final_fields: FieldSet,
+    open_fds_enabled: bool,
+    open_fds_warning: Option<u64>,
+    /// whether the last count of open descriptors exceeded the threshold
+    open_fds_warned: bool,
+    peak_open_fds: Option<u64>,
+    /// whether the completion statement has been logged
+    finished: bool,
+    after_finish: AfterFinish,
+    post_finish_updates: u64,
+    /// the start of the current pause, if paused
+    paused_at: Option<Instant>,
+    /// the cumulative duration of the pauses ended so far
+    paused_duration: Duration,
+    output_budget: Option<u64>,
+    /// the bytes of the messages emitted so far
+    output_bytes: u64,
+    /// whether the reports are being spaced out to fit the output budget
+    output_throttled: bool,
+    /// the time taken to write the last synchronous reports
+    output_latency: latency::OutputLatency,
+    /// the average write time above which to stop writing the reports on
+    /// the calling thread, `None` once handled
+    slow_output_threshold: Option<Duration>,
+    /// the reports skipped since the last one because of `min_report_spacing`
+    rate_limited_reports: u64,
+    #[cfg(feature = "desktop-notification")]
+    desktop_notification: bool,
+    memory_reporting: bool,
+    /// whether to show the CPU utilization along with the memory, see
+    /// `with_cpu_display`
+    cpu_display: bool,
+    peak_memory_kb: Option<u64>,
+    #[cfg(feature = "nvml")]
+    gpu_memory: Option<gpu::GpuMemory>,
+    #[cfg(all(feature = "query-socket", unix))]
+    query_server: Option<query::QueryServer>,
+    #[cfg(feature = "async")]
+    publisher: Option<notifier::Publisher>,
+    /// whether the logger has been added to a `MultiLoggerDisplay`
+    managed_by_display: bool,
+    /// the line rewritten at each report, see `with_display`
+    bar: Option<bar::TerminalBar>,
     frequency: Duration,
-    system: System,
+    number_format: NumberFormat,
+    duration_format: DurationFormat,
+    /// the unit of the counts, see `with_items_unit`
+    unit: Unit,
+    /// the level of the reports, see `with_log_level`
+    level: Level,
+    /// the level of the completion statement, see `with_completion_level`
+    completion_level: Level,
+    /// the target of the messages, see `with_log_target`
+    target: String,
+    metadata: Metadata,
+    metadata_in_text: bool,
+    extra_fields: Vec<ExtraField>,
 }
 
+/// The statistics of a report interval, see `update_statistics`
+#[cfg(feature = "std")]
+struct IntervalStats {
+    elapsed: Duration,
+    throughput: f64,
+    work_unit_throughput: f64,
+    prediction: Option<f64>,
+    /// the label of the prediction, `(windowed ETA)` or `(avg ETA)`, when
+    /// a throughput window is set
+    prediction_basis: Option<&'static str>,
+    interval_count: u64,
+    interval_work_units: f64,
+}
+
+/// A named value computed by a user supplied function at every report
+#[cfg(feature = "std")]
+type ExtraField = (String, Box<dyn Fn() -> String + Send>);
+
+/// A function called with the throughput, the count and the estimated
+/// seconds to completion at every report, see `with_on_log`
+#[cfg(feature = "std")]
+type OnLog = Box<dyn Fn(f64, u64, Option<f64>) + Send>;
+
+/// A function called with a number of updates, see `with_update_hook` and
+/// `with_interval_hook`
+#[cfg(feature = "std")]
+type UpdateHook = Box<dyn FnMut(u64) + Send>;
+
+#[cfg(feature = "std")]
 impl ProgressLogger {
     /// Creates a builder to configure a new progress logger
     pub fn builder() -> ProgressLoggerBuilder {
-        ProgressLoggerBuilder {
-            expected_updates: None,
-            items: None,
-            frequency: None,
-        }
+        ProgressLoggerBuilder::new()
     }
 
     fn log(&mut self) {
-        let elapsed = Instant::now() - self.start;
+        let outermost = self.exclusive.enter();
+        self.log_unchecked();
+        self.exclusive.exit(outermost);
+    }
+
+    fn log_unchecked(&mut self) {
+        if self.managed_by_display {
+            // The display takes care of rendering the reports
+            return;
+        }
+        let now = clock::now();
+        let too_early = self.last_report_at.is_some_and(|last_report_at| {
+            now.saturating_duration_since(last_report_at) < self.min_report_spacing
+        });
+        if too_early {
+            self.rate_limited_reports += 1;
+            return;
+        }
+        if self.background_reports.is_some() {
+            self.send_background_report(now, None);
+            return;
+        }
+        if let Some(line) = self.report_line(now) {
+            if self.charge_output(line.len(), true) {
+                self.dispatch(|pl| ProgressEvent::Report {
+                    snapshot: pl.snapshot_at(now),
+                    line: Some(line.clone()),
+                });
+                let before = Instant::now();
+                #[cfg(feature = "tracing")]
+                let _fields = emit::report_fields(
+                    &self.items,
+                    self.count,
+                    self.count as f64 / self.active_elapsed_at(now).as_secs_f64(),
+                    self.ettc,
+                );
+                match self.bar.as_ref() {
+                    Some(bar) => bar.draw(self.completed_fraction(), &line),
+                    None => self.output(self.level, format_args!("{}", line)),
+                }
+                self.output_latency.record(before.elapsed());
+                self.check_output_latency();
+            }
+        }
+    }
+
+    /// Once the reports take too long to write on average, moves them to a
+    /// background thread, or spaces them out if some features need them on
+    /// the calling thread, see `with_slow_output_threshold`
+    fn check_output_latency(&mut self) {
+        let threshold = match self.slow_output_threshold {
+            Some(threshold) if self.output_latency.exceeds(threshold) => threshold,
+            _ => return,
+        };
+        self.slow_output_threshold = None;
+        let average = self.output_latency.average().unwrap_or_default();
+        #[allow(unused_mut)]
+        let mut synchronous = self.samples.is_some();
+        #[cfg(all(feature = "query-socket", unix))]
+        {
+            synchronous |= self.query_server.is_some();
+        }
+        #[cfg(feature = "async")]
+        {
+            synchronous |= self.publisher.is_some();
+        }
+        let remedy = if synchronous {
+            // Writing takes at most 1% of the time
+            self.frequency = self.frequency.max(average * 100);
+            format!("reporting every {:.2?}", self.frequency)
+        } else {
+            self.background_reports = Some(background::BackgroundReports::spawn(self.renderer()));
+            String::from("reporting from a background thread")
+        };
+        self.publish(
+            Level::Warn,
+            ProgressEvent::Warning {
+                kind: WarningKind::SlowOutput,
+                line: format!(
+                    "Writing the reports of {} took {:.2?} on average, above {:.2?}: {} from now on",
+                    self.items, average, threshold, remedy
+                ),
+            },
+        );
+    }
+
+    /// What a thread needs to render the periodic reports of this logger
+    pub(crate) fn renderer(&self) -> background::Renderer {
+        let sink = self
+            .sink
+            .clone()
+            .filter(|_| !self.sink_panicked.load(Ordering::Relaxed));
+        background::Renderer {
+            items: self.items_name.clone(),
+            number_format: self.number_format,
+            duration_format: self.duration_format,
+            unit: self.unit,
+            level: self.level,
+            target: self.target.clone(),
+            fields: self.periodic_fields,
+            memory_reporting: self.memory_reporting,
+            cpu_display: self.cpu_display,
+            prefix: self.prefix.clone(),
+            sink,
+        }
+    }
+
+    /// The fraction of the work done, from the keys or the expected
+    /// number of updates, to fill the bar of `DisplayMode::TerminalBar`
+    fn completed_fraction(&self) -> Option<f64> {
+        self.key_fraction.or_else(|| {
+            self.expected_updates
+                .filter(|&expected| expected > 0)
+                .map(|expected| self.count as f64 / expected as f64)
+        })
+    }
+
+    /// Tells the watchdog, if any, that the logger is alive at `now`
+    fn feed_watchdog(&self, now: Instant) {
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.observe(now, self.active_elapsed_at(now), self.count, self.frequency);
+        }
+    }
+
+    /// The average time `update` took to write the last reports, see
+    /// `with_slow_output_threshold`, `None` if none was written on the
+    /// calling thread
+    pub fn output_latency(&self) -> Option<Duration> {
+        self.output_latency.average()
+    }
+
+    /// Closes the report interval, handing the report over to the
+    /// background thread, see `with_async_reports`
+    pub(crate) fn send_background_report(&mut self, now: Instant, label: Option<String>) {
+        self.check_retry_rate();
+        let stats = self.update_statistics(now);
+        if !self.is_enabled() {
+            return;
+        }
+        if self.sink_error_policy == SinkErrorPolicy::Fail {
+            if let Some(sink) = self.sink.as_ref().filter(|sink| sink.errors() > 0) {
+                let error = sink.first_error().unwrap_or_default();
+                panic!(
+                    "failed to write the reports of {} to {}: {}",
+                    self.items,
+                    sink.name(),
+                    error
+                );
+            }
+        }
+        self.dispatch(|pl| ProgressEvent::Report {
+            snapshot: pl.snapshot_at(now),
+            line: None,
+        });
+        let count = self.count;
+        if let Some(background_reports) = self.background_reports.as_mut() {
+            background_reports.send(background::PendingReport {
+                elapsed: stats.elapsed,
+                count,
+                throughput: stats.throughput,
+                prediction: stats.prediction,
+                prediction_basis: stats.prediction_basis,
+                label,
+            });
+        }
+    }
+
+    /// Accounts for a message of `len` bytes against the output budget, if
+    /// any, returning whether it can be emitted. Past half of the budget, the
+    /// frequency doubles at each report; once the budget is exhausted, only
+    /// the messages that are not `limited`, i.e. the checkpoints, and the
+    /// completion statement get through.
+    fn charge_output(&mut self, len: usize, limited: bool) -> bool {
+        let budget = match self.output_budget {
+            Some(budget) => budget,
+            None => return true,
+        };
+        let len = len as u64;
+        if limited && self.output_bytes + len > budget {
+            return false;
+        }
+        self.output_bytes += len;
+        if self.output_bytes >= budget / 2 {
+            if !self.output_throttled {
+                self.output_throttled = true;
+                self.publish(
+                    self.level,
+                    ProgressEvent::Notice {
+                        line: format!(
+                            "Half of the output budget of {} bytes used: spacing out the reports of {}",
+                            budget, self.items
+                        ),
+                    },
+                );
+            }
+            self.frequency = self.frequency.saturating_mul(2);
+        }
+        true
+    }
+
+    /// Closes the report interval ending at `now`, updating the statistics
+    /// with cheap arithmetic only
+    fn update_statistics(&mut self, now: Instant) -> IntervalStats {
+        if self.heartbeat.is_some() {
+            self.push_watchdog_stalls();
+            let stalled = self.stalled_in_interval || self.count == self.last_logged_count;
+            if let Some(heartbeat) = self.heartbeat.as_mut() {
+                heartbeat.push(if self.warned_in_interval {
+                    heartbeat::WARNING
+                } else if stalled {
+                    heartbeat::STALLED
+                } else {
+                    heartbeat::NORMAL
+                });
+            }
+        }
+        self.warned_in_interval = false;
+        self.stalled_in_interval = false;
+        self.feed_watchdog(now);
+        let interval_count = self.count - self.last_logged_count;
+        let interval_work_units = self.work_units - self.last_logged_work_units;
+        self.last_interval = self
+            .last_report_at
+            .map(|last_report_at| (now - last_report_at, interval_count));
+        self.last_report_at = Some(now);
+        self.last_logged_count = self.count;
+        self.last_logged_work_units = self.work_units;
+        let elapsed = self.active_elapsed_at(now);
         let throughput = self.count as f64 / elapsed.as_secs_f64();
-        self.throughput.replace(throughput);
-        self.system.refresh_memory();
-        let used_kb = PrettyNumber::from(self.system.get_used_memory());
-        let used_swap_kb = PrettyNumber::from(self.system.get_used_swap());
-        if let Some(expected_updates) = self.expected_updates {
-            let prediction = (expected_updates - self.count) as f64 / throughput;
-            self.ettc.replace(prediction);
-            info!(
-                "[mem: {} kB, swap: {} kB] {:.2?} {} {}, {:.2} s left ({:.2} {}/s)",
-                used_kb,
-                used_swap_kb,
-                elapsed,
-                PrettyNumber::from(self.count),
-                self.items,
-                prediction,
-                PrettyNumber::from(throughput),
-                self.items
-            );
+        let work_unit_throughput = self.work_units / elapsed.as_secs_f64();
+        if self.work_units > 0.0 {
+            self.work_unit_throughput.replace(work_unit_throughput);
+        }
+        self.eta.observe(now, self.count, self.last_interval);
+        let (count_throughput, prediction_basis) = self.eta.throughput(throughput);
+        let prediction = match self.key_fraction {
+            Some(fraction) if fraction > 0.0 => {
+                Some(elapsed.as_secs_f64() * (1.0 - fraction) / fraction)
+            }
+            Some(_) => None,
+            None => match self.expected_work_units {
+                Some(expected) if self.work_units > 0.0 => {
+                    Some((expected - self.work_units).max(0.0) / work_unit_throughput)
+                }
+                Some(_) => None,
+                // An expectation of zero, or one already exceeded, gives no
+                // time to completion
+                None => self
+                    .expected_updates
+                    .filter(|&expected_updates| {
+                        expected_updates > 0 && expected_updates >= self.count
+                    })
+                    .map(|expected_updates| {
+                        (expected_updates - self.count) as f64 / count_throughput
+                    }),
+            },
+        };
+        // Only the predictions on the count of updates use the estimator
+        let prediction_basis = prediction_basis
+            .filter(|_| self.key_fraction.is_none() && self.expected_work_units.is_none());
+        self.ettc = prediction;
+        if let Some(sender) = self.sample_sender.as_mut() {
+            if !sender.send_sample(elapsed, self.count) {
+                // Nobody is listening anymore
+                self.sample_sender = None;
+            }
+        }
+        if self.on_log.is_some() {
+            self.run_on_log(throughput, prediction);
+        }
+        if self.interval_hook.is_some() {
+            self.run_interval_hook(interval_count);
+        }
+        if throughput > 0.0 {
+            if let Some(reports) = self.target_report_count.take() {
+                let expected_updates = self.expected_updates.unwrap_or(0) as f64;
+                // A throughput tiny enough for the run to overflow a
+                // `Duration` spaces the reports out as much as possible
+                let run = Duration::try_from_secs_f64(expected_updates / throughput)
+                    .unwrap_or(Duration::MAX);
+                self.frequency = (run / reports).max(self.min_report_spacing);
+            }
+        }
+        IntervalStats {
+            elapsed,
+            throughput,
+            work_unit_throughput,
+            prediction,
+            prediction_basis,
+            interval_count,
+            interval_work_units,
+        }
+    }
+
+    /// Updates the statistics and renders the periodic report. Returns `None`
+    /// if reports are disabled.
+    fn report_line(&mut self, now: Instant) -> Option<String> {
+        let enabled = self.is_enabled();
+        self.render_report_line(now, enabled)
+    }
+
+    /// The status line of a logger in a `MultiLoggerDisplay`, rendered even
+    /// without an enabled output if the display `draws` the lines itself
+    pub(crate) fn display_line(&mut self, now: Instant, draws: bool) -> Option<String> {
+        let enabled =
+            self.is_enabled() || draws && (self.ignore_global_switch || is_globally_enabled());
+        self.render_report_line(now, enabled)
+    }
+
+    fn render_report_line(&mut self, now: Instant, enabled: bool) -> Option<String> {
+        self.check_retry_rate();
+        let IntervalStats {
+            elapsed,
+            throughput,
+            work_unit_throughput,
+            prediction,
+            prediction_basis,
+            interval_count,
+            interval_work_units,
+        } = self.update_statistics(now);
+        #[cfg(all(feature = "query-socket", unix))]
+        {
+            if let Some(server) = self.query_server.as_ref() {
+                server.set_snapshot(self.snapshot_at(now).to_json());
+            }
+        }
+        #[cfg(feature = "async")]
+        {
+            if let Some(publisher) = self.publisher.as_ref() {
+                publisher.publish(self.snapshot_at(now));
+            }
+        }
+        // Track the peak and warn about leaks even without reports
+        let open_fds = self.sample_open_fds();
+        if !enabled && self.samples.is_none() {
+            return None;
+        }
+        let memory_sample = self.memory_sample(now);
+        if let Some(samples) = self.samples.as_mut() {
+            samples.push(ProgressSample {
+                timestamp: now,
+                count: self.count,
+                throughput,
+                ettc: prediction,
+                memory_kb: memory_sample.map_or(0, |(sample, _)| sample.used_kb),
+                interval: self.last_interval.map(|(interval, _)| interval),
+                interval_count_delta: self.last_interval.map(|(_, delta)| delta),
+            });
+        }
+        if !enabled {
+            return None;
+        }
+        let fmt = self.number_format;
+        let fields = self.periodic_fields;
+        let mut line = String::new();
+        if fields.contains(Field::Memory) {
+            let mut resources = Vec::new();
+            if let Some(sample) = memory_sample {
+                resources.push(memory::render(fmt, now, sample, self.cpu_display));
+            }
+            #[cfg(feature = "nvml")]
+            {
+                if let Some(gpu) = self.gpu_memory.as_ref().and_then(|gpu| gpu.render()) {
+                    resources.push(gpu);
+                }
+            }
+            if let Some(fds) = open_fds {
+                resources.push(format!("fds: {}", fmt.int(fds)));
+            }
+            if !resources.is_empty() {
+                write!(line, "[{}]", resources.join(", ")).unwrap();
+            }
+        }
+        if fields.contains(Field::Elapsed) {
+            write!(line, " {}", self.duration_format.pretty(elapsed)).unwrap();
+        }
+        if fields.contains(Field::Count) {
+            let count = self.unit.count(fmt, self.count, &self.items);
+            write!(line, " {}", count).unwrap();
+            self.append_batches(&mut line);
+        }
+        if let Some(grid) = self.grid.as_ref().filter(|_| fields.contains(Field::Grid)) {
+            grid.render(fmt, &mut line);
+        }
+        if fields.contains(Field::WorkUnits) && self.work_units > 0.0 {
+            write!(line, " ({} work units", fmt.real(self.work_units)).unwrap();
+            if let Some(per_item) = self.work_units_per_item() {
+                write!(line, ", avg {} per item", fmt.real(per_item)).unwrap();
+            }
+            if interval_count > 0 {
+                let per_item = interval_work_units / interval_count as f64;
+                write!(line, ", last interval {} per item", fmt.real(per_item)).unwrap();
+            }
+            line.push(')');
+        }
+        if fields.contains(Field::EstimatedTotal) {
+            if let Some(estimate) = self.estimated_total_items() {
+                let estimate = self.unit.count(fmt, estimate, &self.items);
+                write!(line, ", ~{} expected", estimate).unwrap();
+            }
+        }
+        if fields.contains(Field::KeyRange) {
+            if let Some(fraction) = self.key_fraction {
+                write!(line, ", {:.1}% of key range", fraction * 100.0).unwrap();
+            }
+        }
+        if fields.contains(Field::TimeLeft) {
+            let approximate = if self.approximate_expectation {
+                "~"
+            } else {
+                ""
+            };
+            if let Some(prediction) = prediction {
+                let left = self.duration_format.time_left(fmt, prediction);
+                write!(line, ", {}{} left", approximate, left).unwrap();
+                if let Some(basis) = prediction_basis {
+                    write!(line, " {}", basis).unwrap();
+                }
+            }
+            match self.expected_updates {
+                Some(expected_updates) if self.count > expected_updates => write!(
+                    line,
+                    ", {} over the expected {}{}",
+                    self.unit
+                        .count(fmt, self.count - expected_updates, &self.items),
+                    approximate,
+                    self.unit.number(fmt, expected_updates)
+                )
+                .unwrap(),
+                Some(0) => line.push_str(", expected 0"),
+                _ => {}
+            }
+        }
+        if fields.contains(Field::Throughput) {
+            write!(line, " ({}", self.unit.rate(fmt, throughput, &self.items)).unwrap();
+            // The rate the smoothed estimate follows
+            if let Some(rate) = self
+                .last_interval
+                .filter(|_| self.eta.estimator() != EtaEstimator::GlobalAverage)
+                .and_then(eta::instant_rate)
+            {
+                write!(line, ", now {}", self.unit.rate(fmt, rate, &self.items)).unwrap();
+            }
+            if self.work_units > 0.0 {
+                write!(line, ", {} work units/s", fmt.real(work_unit_throughput)).unwrap();
+            }
+            self.append_batch_throughput(&mut line, elapsed);
+            line.push(')');
+        }
+        if fields.contains(Field::Ratios) {
+            self.append_ratios(&mut line);
+        }
+        if fields.contains(Field::Retries) {
+            self.append_retries(&mut line, true);
+        }
+        if fields.contains(Field::ExtraFields) && !self.extra_fields.is_empty() {
+            let values = self.extra_field_values();
+            if !values.is_empty() {
+                write!(line, " [{}]", values.join(", ")).unwrap();
+            }
+        }
+        if fields.contains(Field::Suppressed) {
+            if self.suppressed_notes > 0 {
+                write!(line, " (+{} notes suppressed)", self.suppressed_notes).unwrap();
+                self.suppressed_notes = 0;
+            }
+            if self.rate_limited_reports > 0 {
+                write!(
+                    line,
+                    " (+{} reports rate limited)",
+                    self.rate_limited_reports
+                )
+                .unwrap();
+                self.rate_limited_reports = 0;
+            }
+            if let Some(errors) = self
+                .sink
+                .as_ref()
+                .map(SharedSink::errors)
+                .filter(|&e| e > 0)
+            {
+                write!(line, " (sink errors: {})", errors).unwrap();
+            }
+        }
+        if fields.contains(Field::Phases) {
+            if let Some(phase) = self.phases.running() {
+                write!(line, ", phase: {}", phase).unwrap();
+            }
+        }
+        if fields.contains(Field::Metadata) {
+            self.append_metadata(&mut line);
+        }
+        Some(trim_separators(line))
+    }
+
+    /// Reports the current state right away, without counting an update,
+    /// e.g. at the end of a file, regardless of the frequency and of
+    /// `with_min_report_spacing`. The next periodic report is due one
+    /// frequency later. Same as `Progress::report_now`.
+    ///
+    /// Checkpoints count in `output_bytes`, but are written even once the
+    /// budget of `with_output_budget` is exhausted.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_output_budget(10)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.update(1u32);
+    /// pl.checkpoint();
+    /// pl.checkpoint();
+    /// let lines = lines.lines();
+    /// assert!(lines[0].starts_with("Half of the output budget of 10 bytes used"));
+    /// // Both checkpoints are written, and counted
+    /// assert_eq!(lines.len(), 3);
+    /// let written: usize = lines[1..].iter().map(String::len).sum();
+    /// assert_eq!(pl.output_bytes(), written as u64);
+    /// pl.finish_silently();
+    /// ```
+    pub fn checkpoint(&mut self) {
+        self.report_labelled(None);
+    }
+
+    /// Same as `checkpoint`, starting the report with `msg`, to tell it
+    /// apart from the periodic ones.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("rows")
+    ///     .with_memory_reporting(false)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// for file in 0..2 {
+    ///     pl.update(300u32);
+    ///     pl.checkpoint_with_message(&format!("end of file {}", file));
+    /// }
+    /// pl.finish_silently();
+    /// let output = lines.contents();
+    /// let lines: Vec<&str> = output.lines().collect();
+    /// assert_eq!(lines.len(), 2);
+    /// assert!(lines[0].starts_with("end of file 0: "));
+    /// assert!(lines[1].starts_with("end of file 1: "));
+    /// assert!(lines[1].contains(" 600 rows "));
+    /// ```
+    pub fn checkpoint_with_message(&mut self, msg: &str) {
+        self.report_labelled(Some(sanitize(msg).into_owned()));
+    }
+
+    /// Reports right away, starting the line with `label`, if any
+    pub(crate) fn report_labelled(&mut self, label: Option<String>) {
+        let outermost = self.exclusive.enter();
+        let now = clock::now();
+        self.last_logged = now;
+        if self.background_reports.is_some() {
+            self.send_background_report(now, label);
+        } else if !self.managed_by_display {
+            if let Some(line) = self.report_line(now) {
+                let line = match label {
+                    Some(label) => format!("{}: {}", label, line),
+                    None => line,
+                };
+                self.charge_output(line.len(), false);
+                self.publish(
+                    self.level,
+                    ProgressEvent::Report {
+                        snapshot: self.snapshot_at(now),
+                        line: Some(line),
+                    },
+                );
+            }
+        }
+        self.exclusive.exit(outermost);
+    }
+
+    /// Logs the given message, unless another message has been logged with
+    /// this method less than the configured frequency ago. Suppressed
+    /// messages are counted, and their number is reported in the next
+    /// periodic report. Useful for occasional diagnostics from within
+    /// the loop being tracked, without flooding the logs.
+    pub fn note<S: AsRef<str>>(&mut self, msg: S) {
+        let now = clock::now();
+        let due = self
+            .last_note
+            .map(|last_note| now - last_note >= self.frequency)
+            .unwrap_or(true);
+        if due {
+            let msg = sanitize(msg.as_ref());
+            if self.is_enabled() && self.charge_output(msg.len(), true) {
+                let line = msg.into_owned();
+                self.publish(self.level, ProgressEvent::Note { line });
+            }
+            self.last_note.replace(now);
         } else {
-            info!(
-                "[mem: {} kB, swap: {} kB] {:.2?} {} {} ({:.2} {}/s)",
-                used_kb,
-                used_swap_kb,
-                elapsed,
-                PrettyNumber::from(self.count),
-                self.items,
-                PrettyNumber::from(throughput),
-                self.items
+            self.suppressed_notes += 1;
+        }
+    }
+
+    /// Counts the open file descriptors, if enabled with `with_open_fds`,
+    /// updating the peak and warning when the count first exceeds the
+    /// threshold, if any
+    fn sample_open_fds(&mut self) -> Option<u64> {
+        if !self.open_fds_enabled {
+            return None;
+        }
+        let fds = fds::count_open_fds()?;
+        self.peak_open_fds = Some(self.peak_open_fds.unwrap_or(0).max(fds));
+        if let Some(threshold) = self.open_fds_warning {
+            if fds > threshold && !self.open_fds_warned {
+                self.warned_in_interval = true;
+                self.publish(
+                    Level::Warn,
+                    ProgressEvent::Warning {
+                        kind: WarningKind::OpenFileDescriptors,
+                        line: format!(
+                            "{} open file descriptors, more than the expected {}: leaking descriptors?",
+                            fds, threshold
+                        ),
+                    },
+                );
+            }
+            // Warn again if the count goes back below the threshold and then exceeds it
+            self.open_fds_warned = fds > threshold;
+        }
+        Some(fds)
+    }
+
+    /// The largest number of open file descriptors observed at reports, if
+    /// enabled with `with_open_fds` and supported by the platform
+    pub fn peak_open_fds(&self) -> Option<u64> {
+        self.peak_open_fds
+    }
+
+    /// Calls the update hook with `cnt`, dropping it if it panics
+    #[inline(never)]
+    fn run_update_hook(&mut self, cnt: u64) {
+        if let Some(hook) = self.update_hook.as_mut() {
+            if panic::catch_unwind(AssertUnwindSafe(|| hook(cnt))).is_err() {
+                self.update_hook = None;
+                self.hook_panicked("update");
+            }
+        }
+    }
+
+    /// Calls the interval hook with `cnt`, dropping it if it panics
+    fn run_interval_hook(&mut self, cnt: u64) {
+        if let Some(hook) = self.interval_hook.as_mut() {
+            if panic::catch_unwind(AssertUnwindSafe(|| hook(cnt))).is_err() {
+                self.interval_hook = None;
+                self.hook_panicked("interval");
+            }
+        }
+    }
+
+    /// Calls the `on_log` callback with the throughput, the count and the
+    /// time to completion, dropping it if it panics
+    fn run_on_log(&mut self, throughput: f64, prediction: Option<f64>) {
+        if let Some(on_log) = self.on_log.as_ref() {
+            let count = self.count;
+            if panic::catch_unwind(AssertUnwindSafe(|| on_log(throughput, count, prediction)))
+                .is_err()
+            {
+                self.on_log = None;
+                self.hook_panicked("on_log");
+            }
+        }
+    }
+
+    #[cold]
+    fn hook_panicked(&mut self, hook: &str) {
+        self.warned_in_interval = true;
+        self.publish(
+            Level::Warn,
+            ProgressEvent::Warning {
+                kind: WarningKind::HookPanicked,
+                line: format!(
+                    "The {} hook of {} panicked, and is no longer called",
+                    hook, self.items
+                ),
+            },
+        );
+    }
+
+    /// Renders the extra fields as `name: value`. Functions panicking are
+    /// dropped, with a warning, so that a faulty field cannot break every
+    /// report.
+    fn extra_field_values(&mut self) -> Vec<String> {
+        let mut values = Vec::with_capacity(self.extra_fields.len());
+        let mut panicked = Vec::new();
+        for (i, (name, value_fn)) in self.extra_fields.iter().enumerate() {
+            match panic::catch_unwind(AssertUnwindSafe(value_fn)) {
+                Ok(value) => values.push(format!("{}: {}", name, sanitize(&value))),
+                Err(_) => panicked.push(i),
+            }
+        }
+        for i in panicked.into_iter().rev() {
+            let (name, _) = self.extra_fields.remove(i);
+            self.warned_in_interval = true;
+            self.publish(
+                Level::Warn,
+                ProgressEvent::Warning {
+                    kind: WarningKind::ExtraFieldPanicked,
+                    line: format!(
+                        "The extra field {} of {} panicked, and is no longer reported",
+                        name, self.items
+                    ),
+                },
+            );
+        }
+        values
+    }
+
+    /// Emits a message to the sink if any, or through the `log` crate,
+    /// after the prefix, if any. If the sink panics, it is abandoned in
+    /// favor of the `log` crate.
+    fn output(&self, level: Level, args: std::fmt::Arguments) {
+        match self.prefix.as_ref() {
+            Some(prefix) => self.write_output(level, format_args!("{}{}", prefix, args)),
+            None => self.write_output(level, args),
+        }
+    }
+
+    fn write_output(&self, level: Level, args: std::fmt::Arguments) {
+        if let Some(bar) = self.bar.as_ref() {
+            bar.end_line();
+        }
+        match self.sink.as_ref() {
+            Some(sink) if !self.sink_panicked.load(Ordering::Relaxed) => {
+                let written = sink.write_line(args);
+                if let SinkWrite::Failed { error, first } = &written {
+                    sink_failed(
+                        self.sink_error_policy,
+                        &self.target,
+                        &self.items,
+                        sink,
+                        error,
+                        *first,
+                    );
+                }
+                if let SinkWrite::Panicked = written {
+                    self.sink_panicked.store(true, Ordering::Relaxed);
+                    emit(
+                        &self.target,
+                        Level::Warn,
+                        format_args!(
+                            "The sink of the reports of {} panicked, reporting through the log crate from now on",
+                            self.items
+                        ),
+                    );
+                    emit(&self.target, level, args);
+                }
+            }
+            _ => emit(&self.target, level, args),
+        }
+    }
+
+    /// Whether reports should be emitted, according to the global switch
+    /// and, without a sink or reporters, to the level filter of the `log`
+    /// crate
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        self.is_enabled_at(self.level)
+    }
+
+    /// Same as `is_enabled`, for a message at `level`
+    #[inline]
+    fn is_enabled_at(&self, level: Level) -> bool {
+        (self.ignore_global_switch || is_globally_enabled())
+            && (self.sink.is_some() && !self.sink_panicked.load(Ordering::Relaxed)
+                || !self.reporters.is_empty()
+                || emit::enabled(&self.target, level))
+    }
+
+    /// Hands the event built by `event` to the reporters, if any, see
+    /// `with_reporter`
+    pub(crate) fn dispatch<F: FnOnce(&Self) -> ProgressEvent>(&mut self, event: F) {
+        if self.reporters.is_empty() {
+            return;
+        }
+        let event = event(self);
+        self.handle_event(&event);
+    }
+
+    /// Hands the event to the reporters, then writes its line, if any
+    pub(crate) fn publish(&mut self, level: Level, event: ProgressEvent) {
+        if !self.reporters.is_empty() {
+            self.handle_event(&event);
+        }
+        if let Some(line) = event.line() {
+            self.output(level, format_args!("{}", line));
+        }
+    }
+
+    /// Hands the event to each reporter. Reporters panicking are dropped,
+    /// with a warning, unless the policy of sink errors is to fail, in
+    /// which case the panic propagates.
+    fn handle_event(&mut self, event: &ProgressEvent) {
+        let mut panicked = Vec::new();
+        for (i, reporter) in self.reporters.iter_mut().enumerate() {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| reporter.handle(event))) {
+                if self.sink_error_policy == SinkErrorPolicy::Fail {
+                    panic::resume_unwind(payload);
+                }
+                panicked.push(i);
+            }
+        }
+        for i in panicked.into_iter().rev() {
+            self.reporters.remove(i);
+            self.warned_in_interval = true;
+            self.publish(
+                Level::Warn,
+                ProgressEvent::Warning {
+                    kind: WarningKind::ReporterPanicked,
+                    line: format!(
+                        "A reporter of {} panicked, and no longer receives events",
+                        self.items
+                    ),
+                },
             );
         }
     }
 
+    fn append_batches(&self, line: &mut String) {
+        if let Some(batches) = self.batches {
+            write!(line, " in {} batches", self.number_format.int(batches)).unwrap();
+        }
+    }
+
+    fn append_batch_throughput(&self, line: &mut String, elapsed: Duration) {
+        if let Some(batches) = self.batches {
+            let throughput = batches as f64 / elapsed.as_secs_f64();
+            write!(line, ", {} batches/s", self.number_format.real(throughput)).unwrap();
+        }
+    }
+
+    fn append_ratios(&self, line: &mut String) {
+        for ratio in self.ratios.iter() {
+            write!(line, ", {}", ratio.render()).unwrap();
+        }
+    }
+
+    /// Adds the ratio between two counters to the reports and to the
+    /// completion statement, as `name 3.41:1`, or `name n/a` while the
+    /// denominator is zero. Any pair of counters can be linked, e.g. the
+    /// input and output bytes of a compressor:
+    ///
+    /// ```
+    /// use progress_logger::{ProgressLogger, ProgressReader, ProgressWriter};
+    /// use std::io::{Cursor, Read, Write};
+    ///
+    /// let input = vec![0u8; 10000];
+    /// let mut reader = ProgressReader::new(Cursor::new(input), ProgressLogger::builder().start());
+    /// let mut writer = ProgressWriter::new(Vec::new(), ProgressLogger::builder().start());
+    /// let (read, written) = (reader.counter(), writer.counter());
+    /// writer.logger_mut().link_ratio("ratio", read, written);
+    /// let mut buf = [0u8; 1000];
+    /// while reader.read(&mut buf)? > 0 {
+    ///     // A very effective compressor
+    ///     writer.write_all(&buf[..1])?;
+    /// }
+    /// assert_eq!((reader.counter().get(), writer.counter().get()), (10000, 10));
+    /// // Logs `Done in ... (... updates/s), ratio 1000.00:1`
+    /// writer.stop()?;
+    /// reader.stop();
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn link_ratio<S: Into<String>>(
+        &mut self,
+        name: S,
+        numerator: Counter,
+        denominator: Counter,
+    ) {
+        self.ratios.push(counter::Ratio {
+            name: sanitize(&name.into()).into_owned(),
+            numerator,
+            denominator,
+        });
+    }
+
+    /// Completes an inner iteration of the grid set with `with_grid`.
+    ///
+    /// # Panics
+    ///
+    /// If the logger has no grid.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let (epochs, batches) = (3, 100);
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_grid(epochs, batches)
+    ///     .with_grid_names("epoch", "batch")
+    ///     .start();
+    /// for _ in 0..epochs {
+    ///     // Reports `epoch 2/3, batch 42/100 (overall 47.3%)`, with the
+    ///     // time left based on the overall rate
+    ///     for _ in 0..batches {
+    ///         pl.inner_step();
+    ///     }
+    ///     pl.outer_step();
+    /// }
+    /// assert_eq!(pl.snapshot().count, 300);
+    /// // Logs `Done in ... 300 updates (...), epoch durations: ...`
+    /// pl.stop();
+    /// ```
+    pub fn inner_step(&mut self) {
+        let grid = self.grid.as_mut().expect("inner_step requires with_grid");
+        grid.inner += 1;
+        self.update(1u32);
+    }
+
+    /// Completes an outer iteration of the grid set with `with_grid`,
+    /// recording its duration. Inner iterations not completed with
+    /// `inner_step` are counted as done, so that the count stays at
+    /// `outer * inner_total`.
+    ///
+    /// # Panics
+    ///
+    /// If the logger has no grid.
+    pub fn outer_step(&mut self) {
+        let grid = self.grid.as_mut().expect("outer_step requires with_grid");
+        let skipped = grid.outer_step(clock::now());
+        self.update(skipped);
+    }
+
+    /// Counts a retry of a failed operation, shown in the reports as
+    /// `retries: N`, along with the current backoff, if set with
+    /// `set_backoff`. See `with_retry_warning` to warn when retries pile up.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder().with_retry_warning(100).start();
+    /// let mut backoff = Duration::from_secs(1);
+    /// for attempt in 0..4 {
+    ///     if attempt < 3 {
+    ///         // A transient failure: wait for `backoff` and try again
+    ///         pl.record_retry();
+    ///         pl.set_backoff(backoff);
+    ///         backoff *= 2;
+    ///     } else {
+    ///         pl.update(1u32);
+    ///         pl.set_backoff(Duration::from_secs(0));
+    ///     }
+    /// }
+    /// assert_eq!(pl.retries(), 3);
+    /// assert_eq!(pl.max_backoff(), Some(Duration::from_secs(4)));
+    /// // Logs `Done in ... (... updates/s), 3 retries (max backoff 4.00 s)`
+    /// pl.stop();
+    /// ```
+    pub fn record_retry(&mut self) {
+        self.retries += 1;
+    }
+
+    /// Sets the backoff currently applied between retries, shown in the
+    /// reports next to the retry count. The completion statement shows the
+    /// largest backoff set.
+    pub fn set_backoff(&mut self, backoff: Duration) {
+        self.backoff = Some(backoff);
+        self.max_backoff = Some(self.max_backoff.map_or(backoff, |max| max.max(backoff)));
+    }
+
+    /// The number of retries counted with `record_retry`
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    /// The largest backoff set with `set_backoff`, if any
+    pub fn max_backoff(&self) -> Option<Duration> {
+        self.max_backoff
+    }
+
+    /// Warns if the retries counted since the last report exceed the
+    /// threshold set with `with_retry_warning`
+    fn check_retry_rate(&mut self) {
+        let interval_retries = self.retries - self.last_logged_retries;
+        self.last_logged_retries = self.retries;
+        if let Some(threshold) = self.retry_warning {
+            if interval_retries > threshold {
+                self.warned_in_interval = true;
+                self.publish(
+                    Level::Warn,
+                    ProgressEvent::Warning {
+                        kind: WarningKind::Retries,
+                        line: format!(
+                            "{} retries of {} since the last report, more than {}",
+                            interval_retries, self.items, threshold
+                        ),
+                    },
+                );
+            }
+        }
+    }
+
+    fn append_retries(&self, line: &mut String, current_backoff: bool) {
+        if self.retries == 0 {
+            return;
+        }
+        let fmt = self.number_format;
+        let backoff = if current_backoff {
+            write!(line, ", retries: {}", fmt.int(self.retries)).unwrap();
+            self.backoff.map(|backoff| ("current", backoff))
+        } else {
+            write!(line, ", {} retries", fmt.int(self.retries)).unwrap();
+            self.max_backoff.map(|backoff| ("max", backoff))
+        };
+        if let Some((which, backoff)) = backoff {
+            write!(
+                line,
+                " ({} backoff {} s)",
+                which,
+                fmt.secs(backoff.as_secs_f64())
+            )
+            .unwrap();
+        }
+    }
+
+    fn append_metadata(&self, line: &mut String) {
+        if self.metadata_in_text && !self.metadata.is_empty() {
+            write!(line, " [{}]", self.metadata.to_logfmt()).unwrap();
+        }
+    }
+
+    /// The samples collected at each report, if the logger has been built
+    /// with `with_collect_samples(true)`, or an empty slice otherwise. Long
+    /// runs are downsampled to at most 4096 samples, spanning the whole run.
+    ///
+    /// ```
+    /// use progress_logger::{ProgressLogger, ThroughputSlope};
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_collect_samples(true)
+    ///     .start();
+    /// for _ in 0..10 {
+    ///     std::thread::sleep(Duration::from_millis(2));
+    ///     pl.update(1u32);
+    /// }
+    /// assert_eq!(pl.samples().len(), 10);
+    /// let trend = pl.samples().compute_slope();
+    /// # assert!(trend.is_finite());
+    /// pl.stop();
+    /// ```
+    pub fn samples(&self) -> &[ProgressSample] {
+        self.samples
+            .as_ref()
+            .map_or(&[], samples::SampleLog::samples)
+    }
+
+    /// The count as a function of the active elapsed time, downsampled to
+    /// at most 257 points covering the whole run, from the start to the
+    /// last clock check, or to the end once finished. Unlike `samples`, it
+    /// is always kept, and does not need any report.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder().start();
+    /// for _ in 0..1000 {
+    ///     pl.update(1u32);
+    /// }
+    /// let report = pl.stop();
+    /// let curve = &report.throughput_curve;
+    /// assert_eq!(curve[0], (Duration::from_secs(0), 0));
+    /// assert_eq!(curve[curve.len() - 1].1, 1000);
+    /// assert!(curve[curve.len() - 1].0 <= report.elapsed);
+    /// ```
+    pub fn throughput_curve(&self) -> &[(Duration, u64)] {
+        self.curve.points()
+    }
+
+    /// The timeline of the run, if enabled with `with_heartbeat_string`: one
+    /// character per report interval, `.` for normal intervals, `s` for
+    /// intervals without progress or in which the updates stalled for at
+    /// least the frequency, see `stall_episodes`, and `!` for intervals in
+    /// which a warning was logged. The reports of `with_stall_reports` are
+    /// intervals too, and a stall until `finish` ends the string with an
+    /// `s`. For long runs, each character summarizes several
+    /// consecutive intervals, reporting the most severe condition among them.
+    pub fn heartbeat(&self) -> Option<String> {
+        self.heartbeat.as_ref().map(Heartbeat::render)
+    }
+
+    /// An estimate of the memory held by the logger, in bytes: its own
+    /// size plus the heap memory of the features enabled. The state that
+    /// grows with the length of the run is capped, so that the estimate
+    /// stays bounded however long the run:
+    ///
+    /// - samples, see `with_collect_samples`: at most 4096, about 360 KiB;
+    /// - heartbeat string, see `with_heartbeat_string`: at most 512 bytes;
+    /// - throughput window, see `EtaEstimator::Window`: at most 1024
+    ///   counts, 24 KiB;
+    /// - phases, see `start_phase`: the last 256, about 16 KiB plus their
+    ///   names;
+    /// - outer iteration durations, see `with_grid`: the last 256, 4 KiB.
+    ///
+    /// The rest is proportional to the configuration, e.g. the milestones,
+    /// the metadata, the extra fields and the ratios, and does not grow
+    /// while running. The memory held by the closures and reporters
+    /// themselves is not known, and only their boxes are counted.
+    ///
+    /// ```
+    /// use progress_logger::{Progress, ProgressLogger};
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_collect_samples(true)
+    ///     .with_heartbeat_string(true)
+    ///     .start();
+    /// for _ in 0..10_000 {
+    ///     pl.start_phase("step");
+    ///     pl.report_now();
+    /// }
+    /// assert!(pl.estimated_memory_footprint() < 512 * 1024);
+    /// assert!(pl.stop().memory_footprint < 512 * 1024);
+    /// ```
+    pub fn estimated_memory_footprint(&self) -> usize {
+        let boxes = |n: usize| n * std::mem::size_of::<Box<dyn Reporter>>();
+        std::mem::size_of::<Self>()
+            + self.items_name.capacity()
+            + self.prefix.as_ref().map_or(0, String::capacity)
+            + self.config.items.capacity()
+            + (self.milestones.capacity() + self.config.milestones.capacity())
+                * std::mem::size_of::<u64>()
+            + self
+                .samples
+                .as_ref()
+                .map_or(0, samples::SampleLog::heap_size)
+            + self.heartbeat.as_ref().map_or(0, Heartbeat::heap_size)
+            + self.curve.heap_size()
+            + self.eta.heap_size()
+            + self.phases.heap_size()
+            + self.grid.as_ref().map_or(0, grid::Grid::heap_size)
+            + self.metadata.heap_size()
+            + self.extra_fields.capacity() * std::mem::size_of::<ExtraField>()
+            + self
+                .extra_fields
+                .iter()
+                .map(|(name, _)| name.capacity())
+                .sum::<usize>()
+            + self.ratios.capacity() * std::mem::size_of::<counter::Ratio>()
+            + self
+                .ratios
+                .iter()
+                .map(|ratio| ratio.name.capacity())
+                .sum::<usize>()
+            + boxes(self.reporters.capacity())
+    }
+
+    /// Pauses the timer, e.g. while waiting for user input or for a
+    /// resource unrelated to the computation. The time spent paused is
+    /// excluded from the throughput and the time to completion, see
+    /// `active_elapsed`. Updates while paused are counted, but don't trigger
+    /// periodic or milestone reports. Does nothing if already paused.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.pause();
+    /// pl.pause();
+    /// for _ in 0..10 {
+    ///     std::thread::sleep(Duration::from_millis(2));
+    ///     pl.update(1u32);
+    /// }
+    /// assert!(lines.contents().is_empty());
+    /// pl.resume();
+    /// pl.resume();
+    /// assert_eq!(pl.snapshot().count, 10);
+    /// assert!(pl.active_elapsed() < Duration::from_millis(20));
+    /// ```
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(clock::now());
+            if let Some(watchdog) = self.watchdog.as_ref() {
+                watchdog.set_idle(true);
+            }
+        }
+    }
+
+    /// Resumes the timer stopped by `pause`. Does nothing if not paused.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_duration += clock::now() - paused_at;
+            if let Some(watchdog) = self.watchdog.as_ref() {
+                watchdog.set_idle(false);
+                self.feed_watchdog(clock::now());
+            }
+        }
+    }
+
+    /// Starts over, as if the logger had just been built: the count, the
+    /// timer, the statistics, the milestones, the samples, the phases and
+    /// the position in the grid are reset, while the configuration, the
+    /// sink and the reporters are kept, e.g. to time the repetitions of a
+    /// benchmark. The frequency stays as adjusted so far, e.g. by
+    /// `with_target_report_count`, and the output budget as used so far,
+    /// see `with_output_budget`. Nothing is logged. A finished logger
+    /// can be restarted, with synchronous reports if they were emitted
+    /// from a background thread, see `with_async_reports`.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("queries")
+    ///     .with_expected_updates(100u32)
+    ///     .start();
+    /// for _ in 0..3 {
+    ///     pl.restart();
+    ///     for _ in 0..100 {
+    ///         pl.update(1u32);
+    ///     }
+    ///     pl.finish();
+    ///     assert_eq!(pl.count(), 100);
+    /// }
+    /// pl.restart_with_expected(1000);
+    /// assert_eq!(pl.count(), 0);
+    /// assert_eq!(pl.expected_updates(), Some(1000));
+    /// ```
+    pub fn restart(&mut self) {
+        let now = clock::now();
+        self.start = now;
+        self.started_at = SystemTime::now();
+        self.count = 0;
+        self.last_logged = now;
+        self.last_logged_count = 0;
+        self.last_check = now;
+        self.active_at_check = Duration::from_secs(0);
+        self.last_report_at = None;
+        self.last_interval = None;
+        self.paused_at = None;
+        self.paused_duration = Duration::from_secs(0);
+        self.finished = false;
+        self.post_finish_updates = 0;
+        if self.heartbeat.is_some() {
+            self.heartbeat = Some(Heartbeat::new());
+        }
+        self.warned_in_interval = false;
+        self.rate_limited_reports = 0;
+        self.work_units = 0.0;
+        self.last_logged_work_units = 0.0;
+        self.work_unit_throughput = None;
+        self.key_fraction = None;
+        self.ettc = None;
+        self.eta = eta::Eta::new(self.eta.estimator());
+        if self.samples.is_some() {
+            self.samples = Some(samples::SampleLog::new());
+        }
+        self.curve = ThroughputCurve::new();
+        self.curve.observe(Duration::from_secs(0), 0);
+        self.stall_episodes = 0;
+        self.stalled_duration = Duration::from_secs(0);
+        self.stalled_in_interval = false;
+        self.clock_skew_events = 0;
+        self.last_clock_skew_warning = None;
+        self.last_note = None;
+        self.suppressed_notes = 0;
+        self.lock_wait = Duration::from_secs(0);
+        self.retries = 0;
+        self.last_logged_retries = 0;
+        self.backoff = None;
+        self.max_backoff = None;
+        self.open_fds_warned = false;
+        self.peak_open_fds = None;
+        self.peak_memory_kb = None;
+        self.batches = self.batches.map(|_| 0);
+        self.phases = Default::default();
+        if let Some(grid) = self.grid.as_mut() {
+            grid.restart(now);
+        }
+        self.milestones = self.config.milestones.iter().rev().copied().collect();
+        self.next_milestone = self.milestones.last().copied().unwrap_or(u64::MAX);
+        self.check_every = 1;
+        self.next_check_at = 1;
+        self.count_at_check = 0;
+        self.next_event = self.next_milestone.min(1);
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.set_idle(false);
+            self.feed_watchdog(now);
+        }
+    }
+
+    /// Same as `restart`, expecting `expected_updates` updates from now
+    /// on. Milestones are not moved to the new expectation.
+    pub fn restart_with_expected(&mut self, expected_updates: u64) {
+        self.restart();
+        self.expected_updates = Some(expected_updates);
+    }
+
+    /// The wall-clock time elapsed since the start, see `active_elapsed`
+    /// to exclude the pauses.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_expected_updates(100u32)
+    ///     .start();
+    /// std::thread::sleep(Duration::from_millis(10));
+    /// pl.update(25u32);
+    /// assert_eq!(pl.count(), 25);
+    /// assert_eq!(pl.expected_updates(), Some(100));
+    /// assert!(pl.elapsed() >= Duration::from_millis(10));
+    /// // Available before the first report
+    /// assert!(pl.throughput().unwrap() > 0.0);
+    /// ```
+    pub fn elapsed(&self) -> Duration {
+        clock::now() - self.start
+    }
+
+    /// The time elapsed since the start, excluding the time spent paused.
+    /// All the rates and estimates of the logger are based on this time.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder().start();
+    /// pl.update(10u32);
+    /// pl.pause();
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// pl.resume();
+    /// assert!(pl.active_elapsed() < Duration::from_millis(50));
+    /// // Logs `Done in 50.12ms wall (0.08ms active). 10 updates ...`
+    /// pl.stop();
+    /// ```
+    pub fn active_elapsed(&self) -> Duration {
+        self.active_elapsed_at(clock::now())
+    }
+
+    /// The wall-clock time at which the logger started. It is recorded
+    /// once, so adjustments of the system clock during the run do not alter
+    /// it, nor the elapsed times, which come from a monotonic clock.
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    fn active_elapsed_at(&self, now: Instant) -> Duration {
+        let paused = self.paused_duration
+            + self.paused_at.map_or(Duration::from_secs(0), |paused_at| {
+                now.saturating_duration_since(paused_at)
+            });
+        now.saturating_duration_since(self.start)
+            .saturating_sub(paused)
+    }
+
+    /// The number of times the counter did not advance for at least the
+    /// frequency, pauses excluded. Stalls are seen at the clock checks of
+    /// the updates, and at `finish` for a stall still going on, such as
+    /// the ones reported by `with_stall_reports`: while the rate of
+    /// updates is high, a stall between two clock checks is only seen if
+    /// they end up at least the frequency apart.
+    ///
+    /// ```
+    /// use progress_logger::test_util::advance;
+    /// use progress_logger::ProgressLogger;
+    /// use std::time::Duration;
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(50))
+    ///     .with_memory_reporting(false)
+    ///     .start();
+    /// for _ in 0..5 {
+    ///     advance(Duration::from_millis(10));
+    ///     pl.update(1u32);
+    /// }
+    /// advance(Duration::from_millis(500));
+    /// pl.update(1u32);
+    /// let stalled = pl.stalled_duration();
+    /// assert_eq!(pl.stall_episodes(), 1);
+    /// assert!(stalled >= Duration::from_millis(500));
+    /// assert!(stalled < Duration::from_millis(550));
+    /// pl.finish_silently();
+    /// ```
+    pub fn stall_episodes(&self) -> u64 {
+        self.stall_episodes
+    }
+
+    /// The cumulative duration of the stalls counted by `stall_episodes`
+    pub fn stalled_duration(&self) -> Duration {
+        self.stalled_duration
+    }
+
+    /// Records a stall of `duration`, seen at a clock check or at the end
+    #[cold]
+    fn record_stall(&mut self, duration: Duration) {
+        self.stall_episodes += 1;
+        self.stalled_duration += duration;
+        self.stalled_in_interval = true;
+    }
+
+    /// Adds the intervals reported by the thread of `with_stall_reports`
+    /// while the updates stalled to the heartbeat string
+    fn push_watchdog_stalls(&mut self) {
+        let stall_reports = self
+            .watchdog
+            .as_ref()
+            .map_or(0, watchdog::Watchdog::take_stall_reports);
+        if let Some(heartbeat) = self.heartbeat.as_mut() {
+            for _ in 0..stall_reports {
+                heartbeat.push(heartbeat::STALLED);
+            }
+        }
+    }
+
+    /// The configuration of the logger, as resolved by `start`
+    ///
+    /// ```
+    /// use progress_logger::{Level, ProgressLogger};
+    /// use std::time::Duration;
+    ///
+    /// let pl = ProgressLogger::builder()
+    ///     .with_items_name("edges")
+    ///     .with_expected_updates(1000u32)
+    ///     .with_milestones(&[0.5])
+    ///     .start();
+    /// let config = pl.config();
+    /// assert_eq!(config.frequency, Duration::from_secs(10));
+    /// assert_eq!(config.milestones, vec![500]);
+    /// assert!(config.to_json().contains(",\"items\":\"edges\",\"frequency_secs\":10,"));
+    /// assert_eq!(config.completion_level, Level::Info);
+    /// assert!(config.to_json().contains(",\"unit\":\"Count\",\"eta_estimator\":\"GlobalAverage\","));
+    /// ```
+    pub fn config(&self) -> &ResolvedConfig {
+        &self.config
+    }
+
+    /// Describes the configuration of the logger in a few lines of text,
+    /// see `ResolvedConfig::describe`
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink, LOG_TARGET, VERSION};
+    ///
+    /// let pl = ProgressLogger::builder()
+    ///     .with_sink(SharedSink::new(MemorySink::new()))
+    ///     .start();
+    /// assert_eq!(
+    ///     pl.describe(),
+    ///     format!(
+    ///         "items: updates\n\
+    ///          reports: every 10.00s, at least 100.00ms apart\n\
+    ///          level: Info, completion at Info, target: {}\n\
+    ///          numbers: grouped, Plain\n\
+    ///          unit: Count\n\
+    ///          time left: GlobalAverage\n\
+    ///          display: Log\n\
+    ///          memory: on, with CPU\n\
+    ///          sink: sink\n\
+    ///          updates after finish: Ignore\n\
+    ///          version: {}",
+    ///         LOG_TARGET, VERSION
+    ///     )
+    /// );
+    /// ```
+    pub fn describe(&self) -> String {
+        self.config.describe()
+    }
+
+    /// The name of the items being counted, as configured. In reports, control
+    /// characters such as newlines are escaped.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let pl = ProgressLogger::builder()
+    ///     .with_items_name("rows\nINFO fake line")
+    ///     .start();
+    /// // Reports show `rows\nINFO fake line` on a single line
+    /// assert_eq!(pl.items_name(), "rows\nINFO fake line");
+    /// ```
+    pub fn items_name(&self) -> &str {
+        &self.items_name
+    }
+
+    /// The metadata attached with `with_metadata`, in insertion order
+    pub fn metadata(&self) -> &[(String, String)] {
+        self.metadata.pairs()
+    }
+
     /// Get the estimated time to completion, if such prediction is available
     pub fn time_to_completion(&self) -> Option<Duration> {
-        self.ettc.map(Duration::from_secs_f64)
+        self.ettc
+            .map(|secs| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
     }
 
+    /// The throughput since the start, in updates per second, computed
+    /// now rather than at the last report. `None` if no time has elapsed.
     pub fn throughput(&self) -> Option<f64> {
-        self.throughput
+        Some(self.count as f64 / self.active_elapsed().as_secs_f64())
+            .filter(|throughput| throughput.is_finite())
+    }
+
+    /// The number of updates so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Borrows the logger as a handle updated through `&self`, to pass
+    /// down recursive calls, see `ProgressHandle`
+    pub fn handle(&mut self) -> ProgressHandle<'_> {
+        ProgressHandle::new(self)
+    }
+
+    /// The expected number of updates, if any, see `with_expected_updates`
+    /// and `track_batches`
+    pub fn expected_updates(&self) -> Option<u64> {
+        self.expected_updates
+    }
+
+    /// The throughput in work units per second, as of the last report, if
+    /// `update_weighted` has been used
+    pub fn work_unit_throughput(&self) -> Option<f64> {
+        self.work_unit_throughput
+    }
+
+    /// The current state of the logger. The time to completion is the one
+    /// estimated at the last report.
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        self.snapshot_at(clock::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> ProgressSnapshot {
+        let elapsed = self.active_elapsed_at(now);
+        ProgressSnapshot {
+            items: self.items_name.clone(),
+            count: self.count,
+            expected_updates: self.expected_updates,
+            elapsed,
+            started_at: self.started_at,
+            throughput: self.count as f64 / elapsed.as_secs_f64(),
+            time_to_completion: self.time_to_completion(),
+            interval: self.last_interval.map(|(interval, _)| interval),
+            interval_count_delta: self.last_interval.map(|(_, delta)| delta),
+            lock_wait: self.lock_wait,
+            sink_errors: self.sink.as_ref().map_or(0, SharedSink::errors),
+            version: VERSION,
+        }
+    }
+
+    /// A handle to wait for the reports of this logger from async code,
+    /// see `ProgressNotifier`. The completion statement counts as a report.
+    #[cfg(feature = "async")]
+    pub fn notifier(&mut self) -> ProgressNotifier {
+        self.publisher
+            .get_or_insert_with(notifier::Publisher::new)
+            .notifier()
+    }
+
+    /// The average number of work units per item, if `update_weighted` has
+    /// been used and at least one item has been counted
+    pub fn work_units_per_item(&self) -> Option<f64> {
+        if self.count > 0 && self.work_units > 0.0 {
+            Some(self.work_units / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    /// The total number of items, estimated from the expected work units set
+    /// with `with_expected_work_units` and the average work units per item
+    /// observed so far. `None` until some work units have been counted.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// // Counting records, knowing only the size of the file in bytes
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_items_name("records")
+    ///     .with_expected_work_units(1_000_000.0)
+    ///     .start();
+    /// assert_eq!(pl.estimated_total_items(), None);
+    /// for _ in 0..100 {
+    ///     let record_bytes = 250;
+    ///     pl.update_weighted(1, record_bytes as f64);
+    /// }
+    /// assert_eq!(pl.estimated_total_items(), Some(4000));
+    /// pl.stop();
+    /// ```
+    pub fn estimated_total_items(&self) -> Option<u64> {
+        let expected = self.expected_work_units?;
+        self.work_units_per_item()
+            .map(|per_item| (expected / per_item).round() as u64)
+    }
+
+    /// The time elapsed since the last report, checking for clock skew if
+    /// the detection is enabled.
+    #[inline]
+    fn since_last_logged(&mut self, now: Instant) -> Duration {
+        if self.clock_skew_threshold.is_none() {
+            return now - self.last_logged;
+        }
+        match now.checked_duration_since(self.last_logged) {
+            Some(elapsed) => elapsed,
+            None => {
+                self.clock_skew_detected(now);
+                Duration::from_secs(0)
+            }
+        }
     }
 
-    /// Try to report progress only once every million updates
+    #[cold]
+    fn clock_skew_detected(&mut self, now: Instant) {
+        self.clock_skew_events += 1;
+        let threshold = self
+            .clock_skew_threshold
+            .expect("clock skew detection should be enabled");
+        match self.last_clock_skew_warning {
+            None => {
+                self.publish(
+                    Level::Warn,
+                    ProgressEvent::Warning {
+                        kind: WarningKind::ClockSkew,
+                        line: String::from(
+                            "Clock skew detected; throughput calculations may be momentarily inaccurate.",
+                        ),
+                    },
+                );
+                self.warned_in_interval = true;
+                self.last_clock_skew_warning.replace(now);
+            }
+            Some(last_warning) => {
+                // Skews closer than the threshold to the last warning are only counted
+                if now.checked_duration_since(last_warning).unwrap_or_default() >= threshold {
+                    self.publish(
+                        Level::Warn,
+                        ProgressEvent::Warning {
+                            kind: WarningKind::ClockSkew,
+                            line: format!(
+                                "Clock skew detected {} times so far; throughput calculations may be momentarily inaccurate.",
+                                self.clock_skew_events
+                            ),
+                        },
+                    );
+                    self.warned_in_interval = true;
+                    self.last_clock_skew_warning.replace(now);
+                }
+            }
+        }
+    }
+
+    /// The number of bytes of the messages emitted so far, checkpoints
+    /// included, excluding the completion statement, see
+    /// `with_output_budget`
+    pub fn output_bytes(&self) -> u64 {
+        self.output_bytes
+    }
+
+    /// The number of times the clock was observed going backwards, if
+    /// detection was enabled with `with_clock_skew_detection`
+    pub fn clock_skew_events(&self) -> u32 {
+        self.clock_skew_events
+    }
+
+    /// Same as `update`, which now adapts the frequency of its clock checks
+    /// to the rate of updates.
+    #[deprecated(note = "`update` tunes its clock checks to the rate of updates, use it instead")]
     #[inline]
     pub fn update_light<N: Into<u64>>(&mut self, cnt: N) {
-        self.count += cnt.into();
-        if self.count % 1_000_000 == 0 {
-            let now = Instant::now();
-            if (now - self.last_logged) > self.frequency {
-                self.log();
-                self.last_logged = now;
+        self.update(cnt);
+    }
+
+    /// Applies the policy for updates arriving after `finish`, returning
+    /// whether to count the update
+    #[cold]
+    fn update_after_finish(&mut self) -> bool {
+        self.post_finish_updates += 1;
+        match self.after_finish {
+            AfterFinish::Ignore => {
+                if self.post_finish_updates == 1 {
+                    self.publish(
+                        Level::Warn,
+                        ProgressEvent::Warning {
+                            kind: WarningKind::UpdateAfterFinish,
+                            line: format!(
+                                "Ignoring updates of {} arriving after the completion statement",
+                                self.items
+                            ),
+                        },
+                    );
+                }
+                false
             }
+            AfterFinish::CountSilently => true,
+            AfterFinish::Panic => panic!("update of {} after the completion statement", self.items),
+        }
+    }
+
+    /// The number of updates received after `finish`, see `with_after_finish`
+    pub fn post_finish_updates(&self) -> u64 {
+        self.post_finish_updates
+    }
+
+    /// Reports progress, and moves on to the next milestone not yet reached
+    #[cold]
+    fn milestone_reached(&mut self) {
+        let mut reached = None;
+        while self.milestones.last().is_some_and(|&m| self.count >= m) {
+            reached = self.milestones.pop();
+        }
+        self.next_milestone = self.milestones.last().copied().unwrap_or(u64::MAX);
+        if self.paused_at.is_none() {
+            self.last_logged = clock::now();
+            if let Some(count) = reached {
+                self.dispatch(|_| ProgressEvent::Milestone { count });
+            }
+            self.log();
         }
     }
 
     /// Update the internal counter and report progress if the time
-    /// since the last report is greater than the configured duration
+    /// since the last report is greater than the configured duration.
+    ///
+    /// Reading the clock is much more expensive than counting, so the clock
+    /// is not read at every call: the number of updates between two reads
+    /// adapts to the rate of updates, aiming at a read every 1/32 to 1/16
+    /// of the frequency, and never more than 4096 updates apart. Very
+    /// frequent updates thus cost little more than an increment, and:
+    ///
+    /// - while the rate of updates holds, reports are late by at most 1/16
+    ///   of the frequency;
+    /// - when the rate drops by a factor `k`, the next report can be late
+    ///   by up to `k / 16` frequencies, e.g. one frequency for a loop
+    ///   slowing down 16 times, and by no more than 4096 updates at the new
+    ///   rate; the reads then fit the new rate, and the following reports
+    ///   are late by at most 1/16 of the frequency again.
+    ///
+    /// No update, no report: see `with_stall_reports` for loops that stall,
+    /// and `with_strict_cadence` for reports on time whatever the updates,
+    /// e.g. for loops alternating hot phases and slow ones.
+    ///
+    /// A panic of the `log` backend while reporting propagates to the
+    /// caller, but leaves the logger consistent: if the panic is caught,
+    /// the next report is still due after the configured frequency.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::panic::{self, AssertUnwindSafe};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// struct Broken;
+    /// impl log::Log for Broken {
+    ///     fn enabled(&self, _: &log::Metadata) -> bool {
+    ///         true
+    ///     }
+    ///     fn log(&self, _: &log::Record) {
+    ///         panic!("backend down");
+    ///     }
+    ///     fn flush(&self) {}
+    /// }
+    /// static LOGGER: Broken = Broken;
+    /// log::set_logger(&LOGGER).unwrap();
+    /// log::set_max_level(log::LevelFilter::Info);
+    ///
+    /// # panic::set_hook(Box::new(|_| {}));
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(10))
+    ///     .with_min_report_spacing(Duration::from_secs(0))
+    ///     .start();
+    /// let start = Instant::now();
+    /// let mut panics = 0;
+    /// while start.elapsed() < Duration::from_millis(100) {
+    ///     if panic::catch_unwind(AssertUnwindSafe(|| pl.update(1u32))).is_err() {
+    ///         panics += 1;
+    ///     }
+    /// }
+    /// // One report every 10ms, not one at every clock check
+    /// assert!(panics <= 11);
+    /// assert!(pl.snapshot().interval.unwrap() >= Duration::from_millis(10));
+    /// ```
     #[inline]
     pub fn update<N: Into<u64>>(&mut self, cnt: N) {
-        let cnt: u64 = cnt.into();
+        self.update_checking_time(cnt.into());
+    }
+
+    /// Like `update`, returning whether the update read the clock to check
+    /// whether a report was due. Lets callers piggyback their own periodic
+    /// checks on the logger's clock checks.
+    #[inline]
+    pub(crate) fn update_checking_time(&mut self, cnt: u64) -> bool {
+        let outermost = self.exclusive.enter();
+        if self.update_hook.is_some() {
+            self.run_update_hook(cnt);
+        }
+        let count = self.count + cnt;
+        let checked = if count < self.next_event {
+            self.count = count;
+            false
+        } else {
+            self.update_at_event(cnt)
+        };
+        self.exclusive.exit(outermost);
+        checked
+    }
+
+    /// Counts an update reaching `next_event`: the next clock check or
+    /// milestone, or any update after `finish`. Returns whether it read the
+    /// clock.
+    #[cold]
+    fn update_at_event(&mut self, cnt: u64) -> bool {
+        if self.finished {
+            if self.update_after_finish() {
+                self.count += cnt;
+            }
+            return false;
+        }
         self.count += cnt;
-        let now = Instant::now();
-        if (now - self.last_logged) > self.frequency {
-            self.log();
+        if self.count >= self.next_milestone {
+            self.milestone_reached();
+        }
+        let checked = self.count >= self.next_check_at;
+        if checked {
+            self.check_time();
+        }
+        self.next_event = self.next_milestone.min(self.next_check_at);
+        checked
+    }
+
+    /// Reports progress if the time since the last report is greater than
+    /// the configured frequency, and tunes the number of updates between
+    /// clock checks, so that the clock is checked about 16 times per report
+    /// interval.
+    fn check_time(&mut self) {
+        let now = clock::now();
+        let since_check = now.saturating_duration_since(self.last_check);
+        let active = self.active_elapsed_at(now);
+        let stalled_for = active.saturating_sub(self.active_at_check);
+        if stalled_for >= self.frequency {
+            self.record_stall(stalled_for);
+        }
+        let target = self.frequency / CLOCK_CHECKS_PER_INTERVAL;
+        if since_check < target / 2 && self.check_every < MAX_UPDATES_BETWEEN_CHECKS {
+            self.check_every *= 2;
+        } else if since_check > target * 2 {
+            // The rate dropped: start over from the rate since the last check
+            let updates = (self.count - self.count_at_check) as f64;
+            let fitting = updates * target.as_secs_f64() / since_check.as_secs_f64();
+            self.check_every = (fitting as u64).clamp(1, self.check_every);
+        } else if since_check > target && self.check_every > 1 {
+            self.check_every /= 2;
+        }
+        self.next_check_at = self.count.saturating_add(self.check_every);
+        self.count_at_check = self.count;
+        self.last_check = now;
+        self.active_at_check = active;
+        self.feed_watchdog(now);
+        self.curve.observe(active, self.count);
+        if self.paused_at.is_none() && self.since_last_logged(now) > self.frequency {
+            if let Some(reported_at) = self
+                .watchdog
+                .as_ref()
+                .and_then(|watchdog| watchdog.claim_report(now, self.last_logged))
+            {
+                // The thread of `with_strict_cadence` reported meanwhile
+                self.last_logged = reported_at;
+                return;
+            }
+            // Before any user code runs: a panicking sink or extra field
+            // must not make every later update report
             self.last_logged = now;
+            self.log();
         }
     }
 
-    /// Stops and drops the progress logger, logging the completion statement
-    pub fn stop(self) {
-        let elapsed = Instant::now() - self.start;
-        let throughput = self.count as f64 / elapsed.as_secs_f64();
-        info!(
-            "Done in {:.2?}. {} {} ({:.2} {}/s)",
-            elapsed,
-            PrettyNumber::from(self.count),
-            self.items,
-            PrettyNumber::from(throughput),
-            self.items
+    /// Like `update`, additionally accumulating `weight` work units. Useful when
+    /// the items have different complexities (e.g. the degree of graph nodes):
+    /// the time to completion is still estimated on the number of items, but
+    /// the reports also include the throughput in work units per second.
+    #[inline]
+    pub fn update_weighted(&mut self, cnt: u64, weight: f64) {
+        if !self.finished || self.after_finish == AfterFinish::CountSilently {
+            self.work_units += weight;
+        }
+        self.update(cnt);
+    }
+
+    /// Updates the internal counter with the sum of the given counts,
+    /// checking whether to report only once. Equivalent to calling `update`
+    /// with each of the counts, but cheaper for large batches.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let sizes = vec![512u64, 1024, 4096];
+    /// let mut pl = ProgressLogger::builder().with_items_name("bytes").start();
+    /// pl.update_batch(sizes.iter().copied());
+    /// pl.stop();
+    /// ```
+    #[inline]
+    pub fn update_batch<I: IntoIterator<Item = u64>>(&mut self, counts: I) {
+        let total: u64 = counts.into_iter().sum();
+        self.update(total);
+    }
+
+    /// Updates the counter with `n_items` items amounting to `total_weight`
+    /// work units, checking whether to report only once. Equivalent to a
+    /// sequence of `update_weighted` calls for the single items.
+    #[inline]
+    pub fn update_n_items(&mut self, n_items: u64, total_weight: u64) {
+        self.update_weighted(n_items, total_weight as f64);
+    }
+
+    /// Counts a single update, and records that the computation reached
+    /// `current` in the range of keys between `start` and `end`. Useful for scans of data
+    /// sorted by a key whose range is known, even if the number of items is
+    /// not: the fraction of the range covered so far drives the time to
+    /// completion estimate, in place of the expected number of updates.
+    ///
+    /// Keys outside of the range, or smaller than the previous one, are
+    /// clamped; in debug builds they trigger an assertion failure.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    ///
+    /// let mut pl = ProgressLogger::builder().with_items_name("events").start();
+    /// let timestamps = vec![0u64, 3600, 7200, 43200, 86399];
+    /// for ts in timestamps {
+    ///     pl.update_position_in_range(ts, 0, 86400);
+    /// }
+    /// pl.stop();
+    /// ```
+    #[inline]
+    pub fn update_position_in_range(&mut self, current: u64, start: u64, end: u64) {
+        debug_assert!(
+            start <= current && current <= end,
+            "key {} outside of the range [{}, {}]",
+            current,
+            start,
+            end
+        );
+        let fraction = if end > start {
+            (current.clamp(start, end) - start) as f64 / (end - start) as f64
+        } else {
+            1.0
+        };
+        let previous = self.key_fraction.unwrap_or(0.0);
+        debug_assert!(
+            fraction >= previous,
+            "keys should be non-decreasing, got {} after a larger one",
+            current
         );
+        self.key_fraction = Some(fraction.max(previous));
+        self.update(1u32);
     }
-}
 
-/// Builds a new progress logger. All the configurations are optional,
-/// To obtain a builder, use `ProgressLogger::builder()`.
-pub struct ProgressLoggerBuilder {
-    expected_updates: Option<u64>,
-    items: Option<String>,
-    frequency: Option<Duration>,
-}
+    /// Drops the progress logger, logging that the computation was cancelled
+    /// in place of the completion statement
+    pub(crate) fn stop_cancelled(mut self) {
+        self.finish_early("Cancelled");
+    }
 
-impl ProgressLoggerBuilder {
-    /// Configure the expected number of updates.
-    pub fn with_expected_updates<N: Into<u64>>(mut self, updates: N) -> Self {
-        self.expected_updates = Some(updates.into());
-        self
-    }
-    /// Set the name of the items being counted.
-    pub fn with_items_name<S: Into<String>>(mut self, name: S) -> Self {
-        self.items = Some(name.into());
-        self
-    }
-    /// Set the frequency of reports on the console.
-    pub fn with_frequency(mut self, freq: Duration) -> Self {
-        self.frequency = Some(freq);
-        self
-    }
-    /// Builds the `ProgressLogger`, starting the internal timer.
-    pub fn start(self) -> ProgressLogger {
-        let now = Instant::now();
-        ProgressLogger {
-            start: now,
-            count: 0,
-            expected_updates: self.expected_updates,
-            items: self.items.unwrap_or_else(|| "updates".to_owned()),
-            last_logged: now,
-            ettc: None,
-            throughput: None,
-            frequency: self.frequency.unwrap_or_else(|| Duration::from_secs(10)),
-            system: System::default(),
+    /// Logs that the computation ended early, `how`, in place of the
+    /// completion statement, as a `ProgressEvent::Cancelled`. Does nothing
+    /// if already finished.
+    fn finish_early(&mut self, how: &str) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.next_event = 0;
+        self.curve.observe(self.active_elapsed(), self.count);
+        if let Some(mut background_reports) = self.background_reports.take() {
+            background_reports.flush();
+        }
+        if !self.is_enabled_at(self.completion_level) {
+            return;
+        }
+        let elapsed = self.active_elapsed();
+        let mut line = format!(
+            "{} after {} in {}",
+            how,
+            self.unit.count(self.number_format, self.count, &self.items),
+            self.duration_format.pretty(elapsed)
+        );
+        if let Some(expected_updates) = self.expected_updates.filter(|&e| e > 0) {
+            write!(
+                line,
+                " ({:.1}% of {})",
+                self.count as f64 / expected_updates as f64 * 100.0,
+                self.unit.number(self.number_format, expected_updates)
+            )
+            .unwrap();
         }
+        self.append_metadata(&mut line);
+        let report = self.report();
+        self.publish(
+            self.completion_level,
+            ProgressEvent::Cancelled { report, line },
+        );
     }
-}
 
-struct PrettyNumber {
-    rendered: String,
-}
+    /// Starts a named phase of the computation, ending the running one, if
+    /// any. The memory in use is sampled at the boundaries of the phases,
+    /// whatever the sampling interval, and the completion statement
+    /// attributes the memory growth to each of them, as `memory by phase:
+    /// load +6.2 GiB, index +1.1 GiB, query -0.1 GiB`. Phases are named
+    /// but not measured if memory reporting is disabled, see
+    /// `with_memory_reporting`.
+    ///
+    /// ```
+    /// use progress_logger::ProgressLogger;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// // Scripted memory usage, in kB, at the successive samples
+    /// const USED_KB: [u64; 4] = [1 << 20, 7 << 20, 8 << 20, 7 << 20];
+    /// static SAMPLES: AtomicUsize = AtomicUsize::new(0);
+    /// progress_logger::set_memory_probe(Some(|| {
+    ///     (USED_KB[SAMPLES.fetch_add(1, Ordering::Relaxed).min(3)], 0)
+    /// }));
+    ///
+    /// let mut pl = ProgressLogger::builder().start();
+    /// pl.start_phase("load");
+    /// pl.start_phase("index");
+    /// pl.start_phase("query");
+    /// pl.end_phase();
+    /// assert_eq!(
+    ///     pl.phase_memory_deltas(),
+    ///     vec![
+    ///         ("load".to_owned(), 6 << 20),
+    ///         ("index".to_owned(), 1 << 20),
+    ///         ("query".to_owned(), -(1 << 20)),
+    ///     ]
+    /// );
+    /// // Logs `Done in ..., memory by phase: load +6.0 GiB, index +1.0 GiB, query -1.0 GiB`
+    /// pl.stop();
+    /// ```
+    pub fn start_phase<S: Into<String>>(&mut self, name: S) {
+        let used_kb = self.used_kb_now();
+        let name = sanitize(&name.into()).into_owned();
+        if let Some(ended) = self.phases.running().map(str::to_owned) {
+            self.dispatch(|_| ProgressEvent::PhaseEnded { name: ended });
+        }
+        self.dispatch(|_| ProgressEvent::PhaseStarted { name: name.clone() });
+        self.phases.start(name, used_kb);
+    }
 
-impl std::fmt::Display for PrettyNumber {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.rendered)
+    /// Ends the running phase, if any, see `start_phase`
+    pub fn end_phase(&mut self) {
+        if let Some(name) = self.phases.running().map(str::to_owned) {
+            let used_kb = self.used_kb_now();
+            self.phases.end(used_kb);
+            self.dispatch(|_| ProgressEvent::PhaseEnded { name });
+        }
     }
-}
 
-impl std::fmt::Debug for PrettyNumber {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.rendered)
+    /// The memory usage for the reports, if enabled, see
+    /// `with_memory_reporting`
+    fn memory_sample(&mut self, now: Instant) -> Option<(memory::MemorySample, bool)> {
+        if !self.memory_reporting {
+            return None;
+        }
+        let sample = memory::sample(now)?;
+        self.record_peak_memory(sample.0.used_kb);
+        Some(sample)
     }
-}
 
-impl From<u64> for PrettyNumber {
-    fn from(n: u64) -> PrettyNumber {
-        let s = format!("{}", n);
-        let tmp: Vec<char> = s.chars().rev().collect();
-        let mut chunks: Vec<&[char]> = tmp.chunks(3).collect();
-
-        let mut rendered = String::new();
-        let mut ul = chunks.len() % 2 == 1;
-        while let Some(chunk) = chunks.pop() {
-            let mut chunk = Vec::from(chunk);
-            if ul {
-                rendered.push_str("\x1B[0m");
-            } else {
-                rendered.push_str("\x1B[4m");
-            }
-            ul = !ul;
-            while let Some(c) = chunk.pop() {
-                rendered.push(c);
+    /// The memory in use for the boundaries of the phases, whatever the
+    /// sampling interval
+    fn used_kb_now(&mut self) -> Option<u64> {
+        if !self.memory_reporting {
+            return None;
+        }
+        let used_kb = memory::refresh(clock::now())?.used_kb;
+        self.record_peak_memory(used_kb);
+        Some(used_kb)
+    }
+
+    fn record_peak_memory(&mut self, used_kb: u64) {
+        self.peak_memory_kb = Some(self.peak_memory_kb.unwrap_or(0).max(used_kb));
+    }
+
+    /// The memory growth of each completed phase, in kB, negative if the
+    /// phase released memory, see `start_phase`
+    pub fn phase_memory_deltas(&self) -> Vec<(String, i64)> {
+        self.phases.deltas()
+    }
+
+    /// Whether a periodic report is due: the next update checking the
+    /// clock will report, unless a sink or the global switch silences it.
+    /// Useful to align occasional expensive work, such as flushing a
+    /// buffer, with the reports, so that both pauses coincide. Calling it
+    /// changes nothing, and costs a read of the clock.
+    ///
+    /// Updates check the clock at a rate adapted to theirs, see `update`,
+    /// so the report may come up to 1/16 of the frequency after this
+    /// starts returning `true`, while the rate of updates holds. Milestones are not accounted for, as they
+    /// depend on the size of the next update.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    /// use std::time::Duration;
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_frequency(Duration::from_millis(20))
+    ///     .with_min_report_spacing(Duration::from_millis(0))
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// assert!(!pl.should_report());
+    /// std::thread::sleep(Duration::from_millis(25));
+    /// assert!(pl.should_report());
+    /// assert!(pl.should_report());
+    /// // Flush the memtable here, then
+    /// pl.update(1u32);
+    /// assert_eq!(lines.lines().len(), 1);
+    /// assert!(!pl.should_report());
+    ///
+    /// // Paused loggers don't report
+    /// pl.pause();
+    /// std::thread::sleep(Duration::from_millis(25));
+    /// assert!(!pl.should_report());
+    /// pl.update(1u32);
+    /// assert_eq!(lines.lines().len(), 1);
+    /// ```
+    pub fn should_report(&self) -> bool {
+        if self.finished || self.managed_by_display || self.paused_at.is_some() {
+            return false;
+        }
+        let now = clock::now();
+        let too_early = self.last_report_at.is_some_and(|last_report_at| {
+            now.saturating_duration_since(last_report_at) < self.min_report_spacing
+        });
+        !too_early && now.saturating_duration_since(self.last_logged) > self.frequency
+    }
+
+    /// The current frequency of reports, which can differ from the
+    /// configured one, see `with_target_report_count` and
+    /// `with_output_budget`
+    pub fn frequency(&self) -> Duration {
+        self.frequency
+    }
+
+    /// The time the workers of `parallel_chunks` or the handles of a
+    /// `ConcurrentProgressLogger` spent waiting for the logger, summed over
+    /// all the threads. Counting within a chunk or between clock checks
+    /// never takes the lock.
+    pub fn lock_wait(&self) -> Duration {
+        self.lock_wait
+    }
+
+    /// Accounts for the time a worker waited for the logger, warning the
+    /// first time the total exceeds the fraction of the wall time set with
+    /// `with_lock_wait_warning`
+    pub(crate) fn add_lock_wait(&mut self, waited: Duration) {
+        self.lock_wait += waited;
+        if let Some(fraction) = self.lock_wait_warning {
+            let wall_elapsed = clock::now() - self.start;
+            if self.lock_wait.as_secs_f64() > fraction * wall_elapsed.as_secs_f64() {
+                self.lock_wait_warning = None;
+                self.warned_in_interval = true;
+                self.publish(
+                    Level::Warn,
+                    ProgressEvent::Warning {
+                        kind: WarningKind::LockWait,
+                        line: format!(
+                            "Workers waited {:.2?} for the logger of {} in {:.2?}: use larger chunks, or background reports",
+                            self.lock_wait, self.items, wall_elapsed
+                        ),
+                    },
+                );
             }
         }
-        if ul {
-            rendered.push_str("\x1B[0m");
+    }
+
+    /// Stops and drops the progress logger, logging the completion
+    /// statement, and returns the outcome of the run, see `ProgressReport`
+    pub fn stop(mut self) -> ProgressReport {
+        self.finish();
+        self.report()
+    }
+
+    /// Stops and drops the progress logger like `stop`, but without the
+    /// completion statement nor the desktop notification, e.g. in tests, or
+    /// where the `log` crate is not set up. Reports already queued by
+    /// `with_async_reports` are still emitted.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_memory_reporting(false)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// pl.update(42u32);
+    /// let report = pl.finish_silently();
+    /// assert_eq!(report.total_items, 42);
+    /// assert_eq!(report.peak_memory_kb, None);
+    /// assert!(lines.contents().is_empty());
+    /// ```
+    pub fn finish_silently(mut self) -> ProgressReport {
+        self.finish_with(false);
+        self.report()
+    }
+
+    pub(crate) fn report(&self) -> ProgressReport {
+        let elapsed = self.active_elapsed();
+        ProgressReport {
+            total_items: self.count,
+            elapsed,
+            throughput_per_sec: self.count as f64 / elapsed.as_secs_f64(),
+            peak_memory_kb: self.peak_memory_kb,
+            output_latency: self.output_latency.average(),
+            memory_footprint: self.estimated_memory_footprint(),
+            throughput_curve: self.curve.points().to_vec(),
+            stall_episodes: self.stall_episodes,
+            stalled_duration: self.stalled_duration,
+            config: self.config.clone(),
         }
+    }
 
-        PrettyNumber { rendered }
+    /// Logs the completion statement, without dropping the logger, which
+    /// can still be queried. Updates arriving later are handled according
+    /// to the policy set with `with_after_finish`. Calling `finish` again,
+    /// or `stop` after it, does nothing.
+    ///
+    /// ```
+    /// use progress_logger::{AfterFinish, ProgressLogger};
+    ///
+    /// let mut pl = ProgressLogger::builder()
+    ///     .with_after_finish(AfterFinish::CountSilently)
+    ///     .start();
+    /// pl.update(10u32);
+    /// pl.finish();
+    /// // A straggler, counted but not reported
+    /// pl.update(1u32);
+    /// assert_eq!(pl.snapshot().count, 11);
+    /// assert_eq!(pl.post_finish_updates(), 1);
+    /// // No second completion statement
+    /// pl.stop();
+    /// ```
+    pub fn finish(&mut self) {
+        self.finish_with(true);
+    }
+
+    /// Finishes, with the completion statement and notification if
+    /// `announce`
+    fn finish_with(&mut self, announce: bool) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        // Make all updates go through `update_at_event`
+        self.next_event = 0;
+        let active = self.active_elapsed();
+        let stalled_for = active.saturating_sub(self.active_at_check);
+        if stalled_for >= self.frequency {
+            // No update since: the stall lasts until the end
+            self.record_stall(stalled_for);
+        }
+        if self.heartbeat.is_some() {
+            self.push_watchdog_stalls();
+            let stalled = self.stalled_in_interval;
+            if let Some(heartbeat) = self.heartbeat.as_mut().filter(|_| stalled) {
+                // The interval since the last report, cut short
+                heartbeat.push(heartbeat::STALLED);
+            }
+        }
+        self.curve.observe(active, self.count);
+        if let Some(bar) = self.bar.as_ref() {
+            bar.end_line();
+        }
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.set_idle(true);
+        }
+        #[cfg(feature = "async")]
+        {
+            if let Some(publisher) = self.publisher.as_ref() {
+                publisher.publish(self.snapshot());
+            }
+        }
+        if self.phases.is_running() {
+            self.end_phase();
+        }
+        if let Some(mut background_reports) = self.background_reports.take() {
+            // Emit the pending reports before the completion statement
+            background_reports.flush();
+        }
+        if self.on_log.is_some() {
+            let throughput = self.count as f64 / self.active_elapsed().as_secs_f64();
+            self.run_on_log(throughput, None);
+        }
+        if self.interval_hook.is_some() {
+            self.run_interval_hook(self.count - self.last_logged_count);
+        }
+        if !announce {
+            self.dispatch(|pl| ProgressEvent::Finished {
+                report: pl.report(),
+                line: None,
+            });
+            return;
+        }
+        #[cfg(feature = "desktop-notification")]
+        {
+            if self.desktop_notification {
+                notification::notify_completion(&self.items, self.count, clock::now() - self.start);
+            }
+        }
+        if !self.is_enabled_at(self.completion_level) {
+            return;
+        }
+        let line = self.completion_line(clock::now());
+        let report = self.report();
+        #[cfg(feature = "tracing")]
+        let _fields = emit::report_fields(
+            &self.items,
+            report.total_items,
+            report.throughput_per_sec,
+            None,
+        );
+        self.publish(
+            self.completion_level,
+            ProgressEvent::Finished {
+                report,
+                line: Some(line),
+            },
+        );
     }
-}
 
-impl From<f64> for PrettyNumber {
-    fn from(x: f64) -> PrettyNumber {
-        assert!(x >= 0.0, "only positive number are supported for now");
-        let s = format!("{:.2}", x);
-        let mut parts = s.split(".");
-        let s = parts.next().expect("missing integer part");
-        let decimal = parts.next();
-        let tmp: Vec<char> = s.chars().rev().collect();
-        let mut chunks: Vec<&[char]> = tmp.chunks(3).collect();
-
-        let mut rendered = String::new();
-        let mut ul = chunks.len() % 2 == 1;
-        while let Some(chunk) = chunks.pop() {
-            let mut chunk = Vec::from(chunk);
-            if ul {
-                rendered.push_str("\x1B[0m");
+    /// Renders the completion statement
+    fn completion_line(&self, now: Instant) -> String {
+        let wall_elapsed = now - self.start;
+        let elapsed = self.active_elapsed_at(now);
+        let throughput = self.count as f64 / elapsed.as_secs_f64();
+        let fmt = self.number_format;
+        let fields = self.final_fields;
+        let mut line = if self.start_time_in_text {
+            format!("Started {}, done", pretty::utc_minutes(self.started_at))
+        } else {
+            String::from("Done")
+        };
+        if self.count == 0 && self.expected_updates == Some(0) {
+            if fields.contains(Field::Elapsed) {
+                write!(line, " in {}", self.duration_format.pretty(wall_elapsed)).unwrap();
+            }
+            line.push_str(", nothing to do.");
+            if fields.contains(Field::Metadata) {
+                self.append_metadata(&mut line);
+            }
+            return line;
+        }
+        if fields.contains(Field::Elapsed) {
+            if elapsed < wall_elapsed {
+                write!(
+                    line,
+                    " in {} wall ({} active)",
+                    self.duration_format.pretty(wall_elapsed),
+                    self.duration_format.pretty(elapsed)
+                )
+                .unwrap();
             } else {
-                rendered.push_str("\x1B[4m");
+                write!(line, " in {}", self.duration_format.pretty(elapsed)).unwrap();
+            }
+        }
+        line.push('.');
+        if fields.contains(Field::Count) {
+            let count = self.unit.count(fmt, self.count, &self.items);
+            write!(line, " {}", count).unwrap();
+            self.append_batches(&mut line);
+        }
+        if fields.contains(Field::WorkUnits) && self.work_units > 0.0 {
+            write!(line, " ({} work units)", fmt.real(self.work_units)).unwrap();
+        }
+        if fields.contains(Field::Throughput) {
+            write!(line, " ({}", self.unit.rate(fmt, throughput, &self.items)).unwrap();
+            if self.work_units > 0.0 {
+                let work_unit_throughput = self.work_units / elapsed.as_secs_f64();
+                write!(line, ", {} work units/s", fmt.real(work_unit_throughput)).unwrap();
+            }
+            self.append_batch_throughput(&mut line, elapsed);
+            line.push(')');
+        }
+        if fields.contains(Field::Ratios) {
+            self.append_ratios(&mut line);
+        }
+        if fields.contains(Field::Retries) {
+            self.append_retries(&mut line, false);
+        }
+        if let Some(grid) = self.grid.as_ref().filter(|_| fields.contains(Field::Grid)) {
+            grid.render_durations(&mut line);
+        }
+        if fields.contains(Field::Phases) {
+            self.phases.render(&mut line);
+        }
+        if fields.contains(Field::LockWait) && self.lock_wait > Duration::from_secs(0) {
+            write!(line, ", waited {:.2?} for the logger", self.lock_wait).unwrap();
+        }
+        if let Some(warning_fraction) = self
+            .stall_warning_fraction
+            .filter(|_| fields.contains(Field::Stalls))
+        {
+            let stalled_fraction = self.stalled_duration.as_secs_f64() / wall_elapsed.as_secs_f64();
+            if self.stall_episodes > 0 && stalled_fraction >= warning_fraction {
+                write!(
+                    line,
+                    ", stalled {:.0}% of wall time across {} episodes",
+                    stalled_fraction * 100.0,
+                    self.stall_episodes
+                )
+                .unwrap();
             }
-            ul = !ul;
-            while let Some(c) = chunk.pop() {
-                rendered.push(c);
+        }
+        if let Some(heartbeat) = self
+            .heartbeat
+            .as_ref()
+            .filter(|_| fields.contains(Field::Heartbeat))
+        {
+            if heartbeat.stride() > 1 {
+                write!(
+                    line,
+                    ", heartbeat (1 char every {} intervals): {}",
+                    heartbeat.stride(),
+                    heartbeat.render()
+                )
+                .unwrap();
+            } else {
+                write!(line, ", heartbeat: {}", heartbeat.render()).unwrap();
             }
         }
-        if ul {
-            rendered.push_str("\x1B[0m");
+        if let Some(sink) = self
+            .sink
+            .as_ref()
+            .filter(|sink| fields.contains(Field::Suppressed) && sink.errors() > 0)
+        {
+            write!(
+                line,
+                ", {} sink errors (first: {})",
+                sink.errors(),
+                sink.first_error().unwrap_or_default()
+            )
+            .unwrap();
         }
-        if let Some(decimal) = decimal {
-            rendered.push('.');
-            rendered.push_str(decimal);
+        if fields.contains(Field::Metadata) {
+            self.append_metadata(&mut line);
         }
+        line
+    }
+}
+
+/// Dropping a logger that was not stopped, e.g. on an early return, logs
+/// the completion statement, or, if the thread is panicking, that the
+/// computation was interrupted. Unlike with `stop`, panics of the sink or
+/// of the `log` backend, e.g. with `SinkErrorPolicy::Fail`, are swallowed.
+///
+/// ```
+/// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+///
+/// fn parse(lines: &MemorySink, input: &[&str]) -> Result<(), std::num::ParseIntError> {
+///     let mut pl = ProgressLogger::builder()
+///         .with_items_name("numbers")
+///         .with_memory_reporting(false)
+///         .with_sink(SharedSink::new(lines.clone()))
+///         .start();
+///     for s in input {
+///         s.parse::<u32>()?;
+///         pl.update(1u32);
+///     }
+///     pl.stop();
+///     Ok(())
+/// }
+///
+/// let lines = MemorySink::new();
+/// assert!(parse(&lines, &["1", "2", "x"]).is_err());
+/// # std::panic::set_hook(Box::new(|_| {}));
+/// let interrupted = std::panic::catch_unwind(|| {
+///     let _ = parse(&lines, &["1", "2", "3", "4", "5"]);
+///     let mut pl = ProgressLogger::builder()
+///         .with_memory_reporting(false)
+///         .with_sink(SharedSink::new(lines.clone()))
+///         .start();
+///     pl.update(7u32);
+///     panic!("out of disk");
+/// });
+/// assert!(interrupted.is_err());
+/// let output = lines.contents();
+/// let output: Vec<&str> = output.lines().collect();
+/// assert_eq!(output.len(), 3);
+/// assert!(output[0].starts_with("Done in "));
+/// assert!(output[0].contains(" 2 numbers "));
+/// assert!(output[1].contains(" 5 numbers "));
+/// assert!(output[2].starts_with("Interrupted after 7 updates in "));
+/// ```
+#[cfg(feature = "std")]
+impl Drop for ProgressLogger {
+    fn drop(&mut self) {
+        let interrupted = std::thread::panicking();
+        // Panicking in drop would abort the process if already panicking
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            if interrupted {
+                self.finish_early("Interrupted");
+            } else {
+                self.finish();
+            }
+        }));
+    }
+}
 
-        PrettyNumber { rendered }
+/// Removes the separators left at the start of a line whose first fields
+/// have been deselected
+#[cfg(feature = "std")]
+fn trim_separators(line: String) -> String {
+    match line.trim_start_matches([' ', ',']) {
+        trimmed if trimmed.len() == line.len() => line,
+        trimmed => trimmed.to_owned(),
     }
 }
@@ -0,0 +1,203 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+#[cfg(feature = "memory")]
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// The memory usage of the machine, in kB, and the CPU utilization of the
+/// process
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MemorySample {
+    pub(crate) used_kb: u64,
+    pub(crate) swap_kb: u64,
+    /// the utilization of the processors by the current process since the
+    /// previous refresh, 100% per busy core, `None` for the first refresh or
+    /// with a probe
+    pub(crate) cpu_percent: Option<f32>,
+    pub(crate) taken_at: Instant,
+}
+
+struct Cache {
+    #[cfg(feature = "memory")]
+    system: System,
+    /// the current process, `None` if it can't be determined
+    #[cfg(feature = "memory")]
+    pid: Option<Pid>,
+    /// whether the process has been refreshed once, the utilization being
+    /// measured between two refreshes
+    #[cfg(feature = "memory")]
+    cpu_primed: bool,
+    sample: Option<MemorySample>,
+    probe: Option<fn() -> (u64, u64)>,
+}
+
+/// The sample shared by all the loggers of the process, so that adding
+/// loggers doesn't multiply the cost of sampling
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+static SAMPLING_INTERVAL_NANOS: AtomicU64 = AtomicU64::new(1_000_000_000);
+static REFRESHES: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the minimum time between two refreshes of the memory usage shown in
+/// the reports, which is sampled once for all the loggers of the process.
+/// Reports within this interval from the last refresh reuse its sample, and
+/// show its age, e.g. `[mem: 1 024 kB, swap: 0 kB (350.12ms old)]`.
+/// Defaults to one second. Each semver-incompatible version of the crate
+/// linked in a program has its own sample and interval.
+///
+/// ```
+/// use progress_logger::{Progress, ProgressLogger};
+/// use std::time::Duration;
+///
+/// progress_logger::set_memory_sampling_interval(Duration::from_secs(3600));
+/// let before = progress_logger::memory_refreshes();
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         std::thread::spawn(|| {
+///             let mut pl = ProgressLogger::builder().start();
+///             for _ in 0..100 {
+///                 pl.update(1u32);
+///                 pl.report_now();
+///             }
+///             pl.stop();
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// // 400 reports, and at most one refresh
+/// assert!(progress_logger::memory_refreshes() - before <= 1);
+/// ```
+pub fn set_memory_sampling_interval(interval: Duration) {
+    let nanos = interval.as_nanos().min(u64::MAX as u128) as u64;
+    SAMPLING_INTERVAL_NANOS.store(nanos, Ordering::Relaxed);
+}
+
+/// Replaces the measurement of the memory usage with `probe`, returning
+/// the used memory and swap in kB, e.g. to script the values in tests.
+/// `None` goes back to measuring the memory of the machine. The next report
+/// refreshes the sample, whatever the sampling interval.
+pub fn set_memory_probe(probe: Option<fn() -> (u64, u64)>) {
+    let mut cache = lock();
+    let cache = cache.get_or_insert_with(Cache::new);
+    cache.probe = probe;
+    cache.sample = None;
+}
+
+/// The number of times the memory usage has been refreshed by the loggers
+/// of the process, see `set_memory_sampling_interval`
+pub fn memory_refreshes() -> u64 {
+    REFRESHES.load(Ordering::Relaxed)
+}
+
+/// The memory usage, refreshed if the shared sample is older than the
+/// sampling interval. Also returns whether the sample comes from the cache.
+/// `None` without the `memory` feature, unless a probe is set.
+pub(crate) fn sample(now: Instant) -> Option<(MemorySample, bool)> {
+    let interval = Duration::from_nanos(SAMPLING_INTERVAL_NANOS.load(Ordering::Relaxed));
+    let mut cache = lock();
+    let cache = cache.get_or_insert_with(Cache::new);
+    if let Some(sample) = cache.sample {
+        if now.saturating_duration_since(sample.taken_at) < interval {
+            return Some((sample, true));
+        }
+    }
+    cache.refresh(now).map(|sample| (sample, false))
+}
+
+/// The memory usage, refreshed whatever the age of the shared sample
+pub(crate) fn refresh(now: Instant) -> Option<MemorySample> {
+    lock().get_or_insert_with(Cache::new).refresh(now)
+}
+
+fn lock() -> MutexGuard<'static, Option<Cache>> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Cache {
+    fn new() -> Self {
+        Cache {
+            #[cfg(feature = "memory")]
+            system: System::new(),
+            #[cfg(feature = "memory")]
+            pid: sysinfo::get_current_pid().ok(),
+            #[cfg(feature = "memory")]
+            cpu_primed: false,
+            sample: None,
+            probe: None,
+        }
+    }
+
+    fn refresh(&mut self, now: Instant) -> Option<MemorySample> {
+        let (used_kb, swap_kb, cpu_percent) = match self.probe {
+            Some(probe) => {
+                let (used_kb, swap_kb) = probe();
+                (used_kb, swap_kb, None)
+            }
+            #[cfg(feature = "memory")]
+            None => {
+                self.system.refresh_memory();
+                let mut cpu_percent = None;
+                if let Some(pid) = self.pid {
+                    // Refreshing the process also refreshes the processor
+                    // times its utilization is measured against, once they
+                    // are known
+                    if !self.cpu_primed {
+                        self.system.refresh_cpu();
+                    }
+                    // `false` the first time, the process being added then
+                    let updated = self.system.refresh_process(pid);
+                    if updated && self.cpu_primed {
+                        cpu_percent = self
+                            .system
+                            .get_process(pid)
+                            .map(|process| process.cpu_usage())
+                            .filter(|usage| usage.is_finite());
+                    }
+                    self.cpu_primed = self.system.get_process(pid).is_some();
+                }
+                (
+                    self.system.get_used_memory(),
+                    self.system.get_used_swap(),
+                    cpu_percent,
+                )
+            }
+            #[cfg(not(feature = "memory"))]
+            None => return None,
+        };
+        REFRESHES.fetch_add(1, Ordering::Relaxed);
+        let sample = MemorySample {
+            used_kb,
+            swap_kb,
+            cpu_percent,
+            taken_at: now,
+        };
+        self.sample = Some(sample);
+        Some(sample)
+    }
+}
+
+/// Renders the memory usage, and the CPU utilization of the process if
+/// `cpu` and measured, with the age of cached samples
+pub(crate) fn render(
+    fmt: crate::pretty::NumberFormat,
+    now: Instant,
+    (sample, cached): (MemorySample, bool),
+    cpu: bool,
+) -> String {
+    let mut rendered = format!(
+        "mem: {} kB, swap: {} kB",
+        fmt.int(sample.used_kb),
+        fmt.int(sample.swap_kb)
+    );
+    if let Some(cpu_percent) = sample.cpu_percent.filter(|_| cpu) {
+        rendered.push_str(&format!(", cpu: {:.1}%", cpu_percent));
+    }
+    if cached {
+        rendered.push_str(&format!(
+            " ({:.2?} old)",
+            now.saturating_duration_since(sample.taken_at)
+        ));
+    }
+    rendered
+}
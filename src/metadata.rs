@@ -0,0 +1,61 @@
+/// Static key-value pairs attached to every report of a logger.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Metadata {
+    pairs: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Adds a pair, panicking if the key is not valid, i.e. if it is empty,
+    /// contains whitespace, `=`, or `"`.
+    pub(crate) fn push(&mut self, key: String, value: String) {
+        assert!(
+            !key.is_empty()
+                && !key
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || c == '=' || c == '"'),
+            "invalid metadata key {:?}: keys must be non-empty and cannot contain whitespace, '=' or '\"'",
+            key
+        );
+        self.pairs.push((key, value));
+    }
+
+    pub(crate) fn pairs(&self) -> &[(String, String)] {
+        &self.pairs
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.pairs.capacity() * std::mem::size_of::<(String, String)>()
+            + self
+                .pairs
+                .iter()
+                .map(|(key, value)| key.capacity() + value.capacity())
+                .sum::<usize>()
+    }
+
+    /// Renders the pairs as space separated `key=value` items. Values are
+    /// quoted if they contain whitespace, `=` or quotes.
+    pub(crate) fn to_logfmt(&self) -> String {
+        let mut out = String::new();
+        for (k, v) in self.pairs.iter() {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(k);
+            out.push('=');
+            if v.is_empty()
+                || v.chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || c == '=' || c == '"')
+            {
+                out.push_str(&format!("{:?}", v));
+            } else {
+                out.push_str(v);
+            }
+        }
+        out
+    }
+}
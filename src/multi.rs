@@ -0,0 +1,266 @@
+use crate::clock;
+use crate::{ProgressLogger, SharedSink};
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write};
+
+/// Displays the progress of several loggers on an interactive terminal, one
+/// per line, rewriting all the lines in place at each refresh so that the
+/// reports of concurrent loggers don't interleave.
+///
+/// Loggers added to the display don't emit periodic reports on their own:
+/// the status lines are rendered on `refresh`, followed by a line with the
+/// total count of the loggers. If standard error is not a terminal,
+/// `refresh` falls back to logging the status lines as usual, and with
+/// `with_sink`, it writes them to the sink instead.
+///
+/// ```
+/// use progress_logger::{DigitGrouping, MemorySink, MultiLoggerDisplay, ProgressLogger, SharedSink};
+///
+/// let lines = MemorySink::new();
+/// let mut display = MultiLoggerDisplay::new().with_sink(SharedSink::new(lines.clone()));
+/// let start = |name: &str| {
+///     ProgressLogger::builder()
+///         .with_items_name(name)
+///         .with_digit_grouping(DigitGrouping::Plain)
+///         .with_memory_reporting(false)
+///         .start()
+/// };
+/// let reads = display.add(start("reads"));
+/// let writes = display.add(start("writes"));
+/// for i in 0..10000 {
+///     display.logger(reads).update(1u32);
+///     if i % 2 == 0 {
+///         display.logger(writes).update(1u32);
+///     }
+/// }
+/// display.refresh();
+/// let lines = lines.lines();
+/// assert_eq!(lines.len(), 3);
+/// assert!(lines[0].contains(" 10000 reads"), "{}", lines[0]);
+/// assert!(lines[1].contains(" 5000 writes"), "{}", lines[1]);
+/// assert_eq!(lines[2], "total: 15000 across 2 loggers");
+/// display.stop();
+/// ```
+pub struct MultiLoggerDisplay {
+    loggers: Vec<ProgressLogger>,
+    /// the slots in the order of display
+    order: Vec<usize>,
+    ordering: DisplayOrdering,
+    /// the number of lines written by the last refresh
+    lines_drawn: usize,
+    interactive: bool,
+    items_per_line: usize,
+    sink: Option<SharedSink>,
+}
+
+/// The order of the loggers in a `MultiLoggerDisplay`, see `with_ordering`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayOrdering {
+    /// In the order of their slots
+    Registration,
+    /// In the alphabetical order of their items name, then of their slots
+    Name,
+}
+
+impl Default for MultiLoggerDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiLoggerDisplay {
+    /// An empty display, showing one logger per line, which rewrites its
+    /// lines in place if standard error is a terminal
+    pub fn new() -> Self {
+        Self {
+            loggers: Vec::new(),
+            order: Vec::new(),
+            ordering: DisplayOrdering::Registration,
+            lines_drawn: 0,
+            interactive: std::io::stderr().is_terminal(),
+            items_per_line: 1,
+            sink: None,
+        }
+    }
+
+    /// Show the status of `n` loggers on each line, separated by ` | `, in
+    /// the order of display.
+    ///
+    /// ```
+    /// use progress_logger::{MemorySink, MultiLoggerDisplay, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut display = MultiLoggerDisplay::new()
+    ///     .with_items_per_log_line(2)
+    ///     .with_sink(SharedSink::new(lines.clone()));
+    /// for name in ["reads", "writes", "seeks"] {
+    ///     let slot = display.add(ProgressLogger::builder().with_items_name(name).start());
+    ///     display.logger(slot).update(1u32);
+    /// }
+    /// display.refresh();
+    /// let lines = lines.lines();
+    /// assert_eq!(lines.len(), 3);
+    /// assert!(lines[0].contains(" reads") && lines[0].contains(" | "));
+    /// assert!(lines[0].contains(" writes"));
+    /// assert!(lines[1].contains(" seeks") && !lines[1].contains(" | "));
+    /// display.stop();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn with_items_per_log_line(mut self, n: usize) -> Self {
+        assert!(n > 0, "the number of loggers per line should be positive");
+        self.items_per_line = n;
+        self
+    }
+
+    /// The order of the loggers on the display, by default the order in
+    /// which they were added. It is the same whichever thread refreshes
+    /// the display.
+    ///
+    /// ```
+    /// use progress_logger::{DisplayOrdering, MemorySink, MultiLoggerDisplay, ProgressLogger, SharedSink};
+    ///
+    /// let lines = MemorySink::new();
+    /// let mut display = MultiLoggerDisplay::new()
+    ///     .with_ordering(DisplayOrdering::Name)
+    ///     .with_sink(SharedSink::new(lines.clone()));
+    /// for name in ["writes", "reads"] {
+    ///     display.add(ProgressLogger::builder().with_items_name(name).start());
+    /// }
+    /// display.refresh();
+    /// let lines = lines.lines();
+    /// assert!(lines[0].contains(" reads"), "{}", lines[0]);
+    /// assert!(lines[1].contains(" writes"), "{}", lines[1]);
+    /// display.stop();
+    /// ```
+    pub fn with_ordering(mut self, ordering: DisplayOrdering) -> Self {
+        self.ordering = ordering;
+        self.sort();
+        self
+    }
+
+    /// Write the status lines of each refresh to the sink, all at once, in
+    /// place of the terminal or the outputs of the loggers
+    pub fn with_sink(mut self, sink: SharedSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Registers a logger, returning the index of its slot
+    pub fn add(&mut self, mut pl: ProgressLogger) -> usize {
+        pl.managed_by_display = true;
+        self.loggers.push(pl);
+        self.order.push(self.loggers.len() - 1);
+        self.sort();
+        self.loggers.len() - 1
+    }
+
+    fn sort(&mut self) {
+        match self.ordering {
+            DisplayOrdering::Registration => self.order.sort_unstable(),
+            DisplayOrdering::Name => {
+                let loggers = &self.loggers;
+                self.order.sort_by(|&a, &b| {
+                    loggers[a]
+                        .items_name()
+                        .cmp(loggers[b].items_name())
+                        .then(a.cmp(&b))
+                });
+            }
+        }
+    }
+
+    /// The logger in the given slot, to be updated
+    ///
+    /// # Panics
+    ///
+    /// If there is no such slot.
+    pub fn logger(&mut self, slot: usize) -> &mut ProgressLogger {
+        &mut self.loggers[slot]
+    }
+
+    /// Renders the status of all the loggers, replacing the lines written
+    /// by the previous refresh.
+    pub fn refresh(&mut self) {
+        if self.loggers.is_empty() {
+            return;
+        }
+        let now = clock::now();
+        let draws = self.sink.is_some() || self.interactive;
+        // Take all the counts before rendering anything, so that the total
+        // is the sum of the counts on display
+        let counts: Vec<u64> = self
+            .order
+            .iter()
+            .map(|&slot| self.loggers[slot].count)
+            .collect();
+        let mut statuses = Vec::with_capacity(self.order.len());
+        for &slot in self.order.iter() {
+            statuses.push(self.loggers[slot].display_line(now, draws).unwrap_or_default());
+        }
+        let mut lines: Vec<String> = statuses
+            .chunks(self.items_per_line)
+            .map(|chunk| {
+                let statuses: Vec<&str> = chunk
+                    .iter()
+                    .map(|status| status.trim())
+                    .filter(|status| !status.is_empty())
+                    .collect();
+                statuses.join(" | ")
+            })
+            .collect();
+        let fmt = self.loggers[self.order[0]].number_format;
+        lines.push(format!(
+            "total: {} across {} loggers",
+            fmt.int(counts.iter().sum()),
+            counts.len()
+        ));
+        if let Some(sink) = self.sink.as_ref() {
+            // Failing to write the status is not worth interrupting the
+            // computation either
+            let _ = sink.write_line(format_args!("{}", lines.join("\n")));
+            return;
+        }
+        if !self.interactive {
+            // Each line goes to the output of the first logger on it, and
+            // the total to that of the first logger
+            let firsts = self
+                .order
+                .iter()
+                .step_by(self.items_per_line)
+                .chain(self.order.first())
+                .map(|&slot| &self.loggers[slot]);
+            for (pl, line) in firsts.zip(lines.iter()) {
+                if !line.is_empty() {
+                    pl.output(pl.level, format_args!("{}", line));
+                }
+            }
+            return;
+        }
+        // Write everything in a single call, so that all the lines are
+        // updated together
+        let mut buf = String::new();
+        if self.lines_drawn > 0 {
+            write!(buf, "\x1B[{}A", self.lines_drawn).unwrap();
+        }
+        for line in lines.iter() {
+            write!(buf, "\r\x1B[2K{}\n", line).unwrap();
+        }
+        let stderr = std::io::stderr();
+        let mut stderr = stderr.lock();
+        // Failing to draw the status is not worth interrupting the computation
+        let _ = stderr.write_all(buf.as_bytes());
+        let _ = stderr.flush();
+        self.lines_drawn = lines.len();
+    }
+
+    /// Stops all the loggers, logging their completion statements
+    pub fn stop(self) {
+        for mut pl in self.loggers {
+            pl.managed_by_display = false;
+            pl.stop();
+        }
+    }
+}
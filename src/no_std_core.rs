@@ -0,0 +1,345 @@
+//! A reduced progress logger for `#![no_std]` environments, enabled with the
+//! `no_std_core` feature, or its alias `no-std`. Without the `std` feature,
+//! which is on by default, the crate is `#![no_std]` and this module is all
+//! it provides; with it, the module sits next to the full logger. It
+//! provides counting, throughput and time to completion estimates, without
+//! allocating and without floating point arithmetic: the time is read from a
+//! user supplied `Clock`, and the reports are handed to a user supplied
+//! `Reporter`.
+//!
+//! Plain functions and closures can be used for both: a `FnMut() -> u64`
+//! returning nanoseconds is a clock, and a `FnMut(&str)` is a reporter
+//! receiving the reports rendered as text.
+//!
+//! ```
+//! use progress_logger::no_std_core::ProgressLogger;
+//!
+//! fn clock() -> u64 {
+//!     // nanoseconds from a monotonic hardware timer
+//! #   0
+//! }
+//!
+//! fn print(line: &str) {
+//!     // write the line to a serial port
+//! }
+//!
+//! let mut pl = ProgressLogger::builder(clock, print)
+//!     .with_expected_updates(10000u32)
+//!     .start();
+//! for _ in 0..10000 {
+//!     pl.update(1u32);
+//! }
+//! pl.stop();
+//! ```
+//!
+//! Implementing the traits allows for stateful clocks, and for reporters
+//! formatting the numbers themselves, e.g. with `defmt`:
+//!
+//! ```
+//! use progress_logger::no_std_core::{Clock, ProgressLogger, Reporter, Status};
+//! use core::time::Duration;
+//!
+//! /// A clock advancing by one millisecond at each reading
+//! struct FakeClock(u64);
+//!
+//! impl Clock for FakeClock {
+//!     fn now(&mut self) -> u64 {
+//!         self.0 += 1_000_000;
+//!         self.0
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! struct Last(Option<Status>, bool);
+//!
+//! impl Reporter for &mut Last {
+//!     fn report(&mut self, status: &Status) {
+//!         self.0 = Some(*status);
+//!     }
+//!
+//!     fn done(&mut self, status: &Status) {
+//!         self.0 = Some(*status);
+//!         self.1 = true;
+//!     }
+//! }
+//!
+//! let mut last = Last::default();
+//! let mut pl = ProgressLogger::builder(FakeClock(0), &mut last)
+//!     .with_expected_updates(1000u32)
+//!     .with_frequency(Duration::from_millis(99))
+//!     .start();
+//! for _ in 0..500 {
+//!     pl.update(1u32);
+//! }
+//! // One update per millisecond, reported every 100 updates
+//! assert_eq!(pl.throughput(), Some(1000));
+//! assert_eq!(pl.time_to_completion(), Some(Duration::from_millis(500)));
+//! pl.stop();
+//! let status = last.0.unwrap();
+//! assert!(last.1);
+//! assert_eq!(status.count, 500);
+//! ```
+
+use core::fmt::Write;
+use core::time::Duration;
+
+/// The maximum length of a report line rendered by the `FnMut(&str)` reporter,
+/// longer lines are truncated
+const LINE_CAPACITY: usize = 256;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A monotonic clock.
+pub trait Clock {
+    /// The time in nanoseconds from an arbitrary, fixed, epoch
+    fn now(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    #[inline]
+    fn now(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// The state of a logger at a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub items: &'static str,
+    pub count: u64,
+    pub expected_updates: Option<u64>,
+    pub elapsed: Duration,
+    /// the throughput since the start, in updates per second
+    pub throughput: u64,
+    pub time_to_completion: Option<Duration>,
+}
+
+/// Receives the reports of a logger.
+pub trait Reporter {
+    /// Called with each periodic report
+    fn report(&mut self, status: &Status);
+
+    /// Called when the logger is stopped
+    fn done(&mut self, status: &Status);
+}
+
+/// Renders the reports as text, and passes them to the function
+impl<F: FnMut(&str)> Reporter for F {
+    fn report(&mut self, status: &Status) {
+        let mut line: heapless::String<LINE_CAPACITY> = heapless::String::new();
+        // Errors only signal that the line is full: we print it truncated
+        let _ = write!(
+            line,
+            "{:.2?} {} {}",
+            status.elapsed, status.count, status.items
+        );
+        if let Some(ttc) = status.time_to_completion {
+            let _ = write!(line, ", {:.2?} left", ttc);
+        }
+        let _ = write!(line, " ({} {}/s)", status.throughput, status.items);
+        self(&line);
+    }
+
+    fn done(&mut self, status: &Status) {
+        let mut line: heapless::String<LINE_CAPACITY> = heapless::String::new();
+        let _ = write!(
+            line,
+            "Done in {:.2?}. {} {} ({} {}/s)",
+            status.elapsed, status.count, status.items, status.throughput, status.items
+        );
+        self(&line);
+    }
+}
+
+/// A tool to report the progress of computations, see the module documentation.
+pub struct ProgressLogger<C = fn() -> u64, R = fn(&str)> {
+    clock: C,
+    reporter: R,
+    /// the start time, in nanoseconds
+    start: u64,
+    count: u64,
+    expected_updates: Option<u64>,
+    items: &'static str,
+    /// the time of the last report, in nanoseconds
+    last_logged: u64,
+    ettc: Option<Duration>,
+    throughput: Option<u64>,
+    /// the frequency of reports, in nanoseconds
+    frequency: u64,
+    /// the count at which `update_light` reads the clock next
+    next_check: u64,
+    /// the number of updates between two clock readings of `update_light`
+    granularity: u64,
+}
+
+impl<C: Clock, R: Reporter> ProgressLogger<C, R> {
+    /// Creates a builder to configure a new progress logger, reading the
+    /// time from `clock` and passing the reports to `reporter`.
+    pub fn builder(clock: C, reporter: R) -> ProgressLoggerBuilder<C, R> {
+        ProgressLoggerBuilder {
+            clock,
+            reporter,
+            expected_updates: None,
+            items: "updates",
+            frequency: None,
+            granularity: 1_000_000,
+        }
+    }
+
+    fn status(&self, now: u64) -> Status {
+        let elapsed_ns = now.saturating_sub(self.start);
+        let throughput = if elapsed_ns > 0 {
+            (self.count as u128 * NANOS_PER_SEC / elapsed_ns as u128) as u64
+        } else {
+            0
+        };
+        // Remaining updates at the average time per update so far
+        let time_to_completion = match self.expected_updates {
+            Some(expected_updates) if self.count > 0 && expected_updates > 0 => {
+                let remaining = expected_updates.saturating_sub(self.count) as u128;
+                let nanos = remaining * elapsed_ns as u128 / self.count as u128;
+                Some(Duration::from_nanos(nanos.min(u64::MAX as u128) as u64))
+            }
+            _ => None,
+        };
+        Status {
+            items: self.items,
+            count: self.count,
+            expected_updates: self.expected_updates,
+            elapsed: Duration::from_nanos(elapsed_ns),
+            throughput,
+            time_to_completion,
+        }
+    }
+
+    fn log(&mut self, now: u64) {
+        let status = self.status(now);
+        self.throughput.replace(status.throughput);
+        self.ettc = status.time_to_completion;
+        self.reporter.report(&status);
+    }
+
+    /// Get the estimated time to completion, if such prediction is available
+    pub fn time_to_completion(&self) -> Option<Duration> {
+        self.ettc
+    }
+
+    /// The throughput as of the last report, in updates per second
+    pub fn throughput(&self) -> Option<u64> {
+        self.throughput
+    }
+
+    /// Try to report progress only once every million updates, or as set
+    /// with `with_light_update_granularity`, whatever the increments.
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use progress_logger::no_std_core::ProgressLogger;
+    ///
+    /// let mut reports = 0;
+    /// let mut clock = 0;
+    /// let mut pl = ProgressLogger::builder(
+    ///     || {
+    ///         // One second per clock reading
+    ///         clock += 1_000_000_000;
+    ///         clock
+    ///     },
+    ///     |_: &str| reports += 1,
+    /// )
+    /// .with_frequency(Duration::from_millis(500))
+    /// .start();
+    /// // Crosses one and two millions without landing on them
+    /// for _ in 0..900_000 {
+    ///     pl.update_light(3u32);
+    /// }
+    /// drop(pl);
+    /// assert_eq!(reports, 2);
+    /// ```
+    #[inline]
+    pub fn update_light<N: Into<u64>>(&mut self, cnt: N) {
+        self.count += cnt.into();
+        if self.count >= self.next_check {
+            self.next_check = self.count.saturating_add(self.granularity);
+            let now = self.clock.now();
+            if now.saturating_sub(self.last_logged) > self.frequency {
+                self.log(now);
+                self.last_logged = now;
+            }
+        }
+    }
+
+    /// Update the internal counter and report progress if the time
+    /// since the last report is greater than the configured duration
+    #[inline]
+    pub fn update<N: Into<u64>>(&mut self, cnt: N) {
+        self.count += cnt.into();
+        let now = self.clock.now();
+        if now.saturating_sub(self.last_logged) > self.frequency {
+            self.log(now);
+            self.last_logged = now;
+        }
+    }
+
+    /// Stops and drops the progress logger, reporting the completion statement
+    pub fn stop(mut self) {
+        let now = self.clock.now();
+        let status = self.status(now);
+        self.reporter.done(&status);
+    }
+}
+
+/// Builds a new progress logger. All the configurations are optional,
+/// To obtain a builder, use `ProgressLogger::builder(clock, reporter)`.
+pub struct ProgressLoggerBuilder<C = fn() -> u64, R = fn(&str)> {
+    clock: C,
+    reporter: R,
+    expected_updates: Option<u64>,
+    items: &'static str,
+    frequency: Option<Duration>,
+    granularity: u64,
+}
+
+impl<C: Clock, R: Reporter> ProgressLoggerBuilder<C, R> {
+    /// Configure the expected number of updates.
+    pub fn with_expected_updates<N: Into<u64>>(mut self, updates: N) -> Self {
+        self.expected_updates = Some(updates.into());
+        self
+    }
+    /// Set the name of the items being counted.
+    pub fn with_items_name(mut self, name: &'static str) -> Self {
+        self.items = name;
+        self
+    }
+    /// Set the frequency of reports.
+    pub fn with_frequency(mut self, freq: Duration) -> Self {
+        self.frequency = Some(freq);
+        self
+    }
+    /// Set the number of updates between two clock readings of
+    /// `update_light`, a million by default. Zero is taken as one.
+    pub fn with_light_update_granularity(mut self, updates: u64) -> Self {
+        self.granularity = updates.max(1);
+        self
+    }
+    /// Builds the `ProgressLogger`, starting the internal timer.
+    pub fn start(mut self) -> ProgressLogger<C, R> {
+        let now = self.clock.now();
+        ProgressLogger {
+            clock: self.clock,
+            reporter: self.reporter,
+            start: now,
+            count: 0,
+            expected_updates: self.expected_updates,
+            items: self.items,
+            last_logged: now,
+            ettc: None,
+            throughput: None,
+            frequency: self
+                .frequency
+                .unwrap_or_else(|| Duration::from_secs(10))
+                .as_nanos() as u64,
+            next_check: self.granularity,
+            granularity: self.granularity,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::emit::emit;
+use crate::LOG_TARGET;
+use crate::Level;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Set after the first failure, to avoid repeating the same message when
+/// there is no notification service (e.g. in headless sessions)
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Shows a desktop notification announcing the completion of a computation
+pub(crate) fn notify_completion(items: &str, count: u64, elapsed: Duration) {
+    if FAILED.load(Ordering::Relaxed) {
+        return;
+    }
+    let shown = notify_rust::Notification::new()
+        .summary(&format!("Done processing {}", items))
+        .body(&format!("{} {} in {:.2?}", count, items, elapsed))
+        .show();
+    if let Err(e) = shown {
+        if !FAILED.swap(true, Ordering::Relaxed) {
+            emit(
+                LOG_TARGET,
+                Level::Debug,
+                format_args!("could not show desktop notification: {}", e),
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::ProgressSnapshot;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+struct State {
+    /// the snapshot taken at the last report
+    latest: Option<ProgressSnapshot>,
+    /// the number of reports so far
+    reports: u64,
+    /// whether the logger has been stopped or dropped
+    finished: bool,
+    waiters: Vec<Waker>,
+}
+
+type Shared = Arc<Mutex<State>>;
+
+fn lock(state: &Shared) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lets async code wait for the reports of a logger running elsewhere,
+/// obtained with `ProgressLogger::notifier`. Notifiers can be cloned, and
+/// any number of tasks can wait on them at the same time.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+/// use std::future::Future;
+/// use std::sync::Arc;
+/// use std::task::{Context, Poll, Wake};
+/// use std::time::Duration;
+///
+/// // A minimal executor: use the one of your async runtime instead
+/// struct Unpark(std::thread::Thread);
+/// impl Wake for Unpark {
+///     fn wake(self: Arc<Self>) {
+///         self.0.unpark();
+///     }
+/// }
+/// fn block_on<F: Future>(future: F) -> F::Output {
+///     let mut future = Box::pin(future);
+///     let waker = Arc::new(Unpark(std::thread::current())).into();
+///     let mut cx = Context::from_waker(&waker);
+///     loop {
+///         match future.as_mut().poll(&mut cx) {
+///             Poll::Ready(output) => return output,
+///             Poll::Pending => std::thread::park(),
+///         }
+///     }
+/// }
+///
+/// let mut pl = ProgressLogger::builder()
+///     .with_expected_updates(100u32)
+///     .with_frequency(Duration::from_millis(1))
+///     .start();
+/// let notifier = pl.notifier();
+/// let worker = std::thread::spawn(move || {
+///     for _ in 0..100 {
+///         std::thread::sleep(Duration::from_millis(1));
+///         pl.update(1u32);
+///     }
+///     pl.stop();
+/// });
+/// let half = block_on(notifier.at_fraction(0.5)).unwrap();
+/// assert!(half.count >= 50);
+/// worker.join().unwrap();
+/// // The logger is gone: no more reports, while fractions already reached
+/// // are still available
+/// assert_eq!(block_on(notifier.next_report()), None);
+/// assert!(block_on(notifier.at_fraction(0.5)).is_some());
+/// ```
+#[derive(Clone)]
+pub struct ProgressNotifier {
+    state: Shared,
+}
+
+impl ProgressNotifier {
+    /// Resolves with the snapshot of the next report, or `None` if the
+    /// logger is stopped before reporting again.
+    pub fn next_report(&self) -> NextReport {
+        let after = lock(&self.state).reports;
+        NextReport {
+            state: Arc::clone(&self.state),
+            after,
+        }
+    }
+
+    /// Resolves with the snapshot of the first report at which the given
+    /// fraction of the expected updates is reached, immediately if it
+    /// already was. Resolves with `None` if the logger is stopped before
+    /// reaching it, or has no expected number of updates.
+    pub fn at_fraction(&self, fraction: f64) -> AtFraction {
+        AtFraction {
+            state: Arc::clone(&self.state),
+            fraction,
+        }
+    }
+}
+
+/// The future returned by `ProgressNotifier::next_report`
+pub struct NextReport {
+    state: Shared,
+    after: u64,
+}
+
+impl Future for NextReport {
+    type Output = Option<ProgressSnapshot>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.state);
+        if state.reports > self.after {
+            Poll::Ready(state.latest.clone())
+        } else if state.finished {
+            Poll::Ready(None)
+        } else {
+            state.waiters.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// The future returned by `ProgressNotifier::at_fraction`
+pub struct AtFraction {
+    state: Shared,
+    fraction: f64,
+}
+
+impl Future for AtFraction {
+    type Output = Option<ProgressSnapshot>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.state);
+        let reached = state.latest.as_ref().is_some_and(|snapshot| {
+            snapshot
+                .expected_updates
+                .is_some_and(|expected| snapshot.count as f64 >= self.fraction * expected as f64)
+        });
+        if reached {
+            Poll::Ready(state.latest.clone())
+        } else if state.finished {
+            Poll::Ready(None)
+        } else {
+            state.waiters.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// The side of the notifier held by the logger. Dropping it, along with
+/// the logger, wakes all the waiters.
+pub(crate) struct Publisher {
+    state: Shared,
+}
+
+impl Publisher {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                latest: None,
+                reports: 0,
+                finished: false,
+                waiters: Vec::new(),
+            })),
+        }
+    }
+
+    pub(crate) fn notifier(&self) -> ProgressNotifier {
+        ProgressNotifier {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    pub(crate) fn publish(&self, snapshot: ProgressSnapshot) {
+        let waiters = {
+            let mut state = lock(&self.state);
+            state.latest = Some(snapshot);
+            state.reports += 1;
+            std::mem::take(&mut state.waiters)
+        };
+        // Wake outside of the lock, as wakers may poll right away
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = lock(&self.state);
+            state.finished = true;
+            std::mem::take(&mut state.waiters)
+        };
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{Progress, ProgressLogger, ProgressSnapshot};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+/// Processes `data` in chunks of `chunk_size` elements on all the available
+/// cores, stopping the logger once all the chunks are done. Returns the
+/// snapshot of the logger right before stopping it.
+///
+/// The closure gets a chunk and a `ChunkHandle` to count the progress
+/// within it. Counts are buffered by the handle, and added to the shared
+/// logger only at the end of each chunk, so that workers don't contend
+/// on it for every item. The time workers wait for the logger anyway, e.g.
+/// while another one reports, is in the `lock_wait` of the snapshot and in
+/// the completion statement, see `with_lock_wait_warning`.
+///
+/// ```
+/// use progress_logger::{parallel_chunks, ProgressLogger};
+///
+/// let data: Vec<u64> = (0..100_000).collect();
+/// let pl = ProgressLogger::builder()
+///     .with_items_name("numbers")
+///     .with_expected_updates(data.len() as u64)
+///     .start();
+/// let snapshot = parallel_chunks(&data, 1000, pl, |chunk, handle| {
+///     for x in chunk {
+///         std::hint::black_box(x * x);
+///         handle.update(1);
+///     }
+/// });
+/// assert_eq!(snapshot.count, data.len() as u64);
+/// ```
+///
+/// Tiny chunks make workers contend for the logger:
+///
+/// ```
+/// use progress_logger::{parallel_chunks, ProgressLogger};
+/// use std::time::Duration;
+///
+/// let data: Vec<u64> = (0..100_000).collect();
+/// let pl = ProgressLogger::builder()
+///     .with_frequency(Duration::from_millis(1))
+///     .with_min_report_spacing(Duration::from_secs(0))
+///     .with_lock_wait_warning(None)
+///     .start();
+/// let snapshot = parallel_chunks(&data, 1, pl, |chunk, handle| {
+///     handle.update(chunk.len() as u64);
+/// });
+/// let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+/// // Nonzero with several cores, and bounded by the time of all workers
+/// assert!(snapshot.lock_wait <= snapshot.elapsed * threads as u32);
+/// ```
+///
+/// # Panics
+///
+/// If `chunk_size` is zero, or if the closure panics.
+pub fn parallel_chunks<T, F>(
+    data: &[T],
+    chunk_size: usize,
+    pl: ProgressLogger,
+    f: F,
+) -> ProgressSnapshot
+where
+    T: Sync,
+    F: Fn(&[T], &mut ChunkHandle) + Sync,
+{
+    assert!(chunk_size > 0, "chunk size should be positive");
+    let chunks: Vec<&[T]> = data.chunks(chunk_size).collect();
+    let next_chunk = AtomicUsize::new(0);
+    let pl = Mutex::new(pl);
+    let n_threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(chunks.len());
+    std::thread::scope(|scope| {
+        for _ in 0..n_threads {
+            scope.spawn(|| loop {
+                let i = next_chunk.fetch_add(1, Ordering::Relaxed);
+                let chunk = match chunks.get(i) {
+                    Some(chunk) => chunk,
+                    None => break,
+                };
+                let mut handle = ChunkHandle {
+                    pl: &pl,
+                    pending: 0,
+                };
+                f(chunk, &mut handle);
+                handle.flush();
+            });
+        }
+    });
+    let pl = pl.into_inner().unwrap_or_else(|e| e.into_inner());
+    let snapshot = pl.snapshot();
+    pl.stop();
+    snapshot
+}
+
+/// Counts the progress within a chunk processed by `parallel_chunks`.
+pub struct ChunkHandle<'a> {
+    pl: &'a Mutex<ProgressLogger>,
+    /// the updates not yet added to the shared logger
+    pending: u64,
+}
+
+impl ChunkHandle<'_> {
+    /// Adds `n` to the local counter
+    #[inline]
+    pub fn update(&mut self, n: u64) {
+        self.pending += n;
+    }
+
+    /// Adds the local counter to the shared logger, which may report
+    pub fn flush(&mut self) {
+        if self.pending > 0 {
+            self.lock().update(self.pending);
+            self.pending = 0;
+        }
+    }
+
+    /// Locks the shared logger, accounting for the time spent waiting
+    fn lock(&self) -> MutexGuard<'_, ProgressLogger> {
+        match self.pl.try_lock() {
+            Ok(pl) => pl,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                let start = Instant::now();
+                let mut pl = self.pl.lock().unwrap_or_else(|e| e.into_inner());
+                pl.add_lock_wait(start.elapsed().max(Duration::from_nanos(1)));
+                pl
+            }
+        }
+    }
+}
+
+impl Progress for ChunkHandle<'_> {
+    #[inline]
+    fn update(&mut self, n: u64) {
+        ChunkHandle::update(self, n);
+    }
+
+    fn report_now(&mut self) {
+        self.flush();
+        self.lock().report_now();
+    }
+}
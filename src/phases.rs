@@ -0,0 +1,100 @@
+use crate::pretty::signed_kib;
+use std::fmt::Write;
+
+/// The maximum number of phases kept: the oldest ones are dropped first
+pub(crate) const PHASE_CAPACITY: usize = 256;
+
+/// A named phase of a computation, with the memory used at its boundaries,
+/// if sampled, see `ProgressLogger::start_phase`
+struct Phase {
+    name: String,
+    start_kb: Option<u64>,
+    end_kb: Option<u64>,
+    running: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Phases {
+    phases: Vec<Phase>,
+    /// the number of completed phases dropped to stay within the capacity
+    dropped: usize,
+}
+
+impl Phases {
+    pub(crate) fn start(&mut self, name: String, used_kb: Option<u64>) {
+        self.end(used_kb);
+        if self.phases.len() >= PHASE_CAPACITY {
+            self.phases.remove(0);
+            self.dropped += 1;
+        }
+        self.phases.push(Phase {
+            name,
+            start_kb: used_kb,
+            end_kb: None,
+            running: true,
+        });
+    }
+
+    /// Ends the running phase, if any
+    pub(crate) fn end(&mut self, used_kb: Option<u64>) {
+        if let Some(phase) = self.phases.last_mut().filter(|phase| phase.running) {
+            phase.end_kb = used_kb;
+            phase.running = false;
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.running().is_some()
+    }
+
+    /// The name of the running phase, if any
+    pub(crate) fn running(&self) -> Option<&str> {
+        self.phases
+            .last()
+            .filter(|phase| phase.running)
+            .map(|phase| phase.name.as_str())
+    }
+
+    /// The memory growth of the completed phases, in kB, negative if
+    /// memory was released. Phases without memory samples are left out,
+    /// and so are the phases dropped to stay within the capacity.
+    pub(crate) fn deltas(&self) -> Vec<(String, i64)> {
+        self.phases
+            .iter()
+            .filter(|phase| !phase.running)
+            .filter_map(|phase| {
+                // In 128 bits, as the samples span the whole u64 range
+                let delta = phase.end_kb? as i128 - phase.start_kb? as i128;
+                let delta = delta.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+                Some((phase.name.clone(), delta))
+            })
+            .collect()
+    }
+
+    /// Renders the deltas as `memory by phase: load +6.2 GiB, index -1.0 MiB`
+    pub(crate) fn render(&self, line: &mut String) {
+        let deltas = self.deltas();
+        if deltas.is_empty() {
+            return;
+        }
+        line.push_str(", memory by phase");
+        if self.dropped > 0 {
+            write!(line, " ({} earlier omitted)", self.dropped).unwrap();
+        }
+        line.push(':');
+        for (i, (name, delta)) in deltas.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(line, "{} {} {}", separator, name, signed_kib(*delta)).unwrap();
+        }
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.phases.capacity() * std::mem::size_of::<Phase>()
+            + self
+                .phases
+                .iter()
+                .map(|phase| phase.name.capacity())
+                .sum::<usize>()
+    }
+}
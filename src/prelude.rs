@@ -0,0 +1,296 @@
+//! Extension traits to drive a `ProgressLogger` from iterators.
+//!
+//! ```
+//! use progress_logger::prelude::*;
+//! use progress_logger::ProgressLogger;
+//!
+//! let sizes = vec![1024u64, 2048, 512];
+//! let mut pl = ProgressLogger::builder().with_items_name("bytes").start();
+//! let total = sizes.into_iter().progress_sum(&mut pl);
+//! assert_eq!(total, 3584);
+//! pl.stop();
+//! ```
+
+use crate::ProgressLogger;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Sums the elements of an iterator while reporting progress.
+pub trait ProgressSum<T> {
+    /// Consumes the iterator, returning the sum of its elements.
+    ///
+    /// For iterators of `u64` each element is added to the logger's counter,
+    /// so that progress is reported in the same units being summed (e.g. bytes).
+    /// For iterators of `f64` each element counts as a single update.
+    ///
+    /// The logger is not stopped: see `progress_sum_and_stop` for that.
+    fn progress_sum(self, pl: &mut ProgressLogger) -> T;
+
+    /// Like `progress_sum`, but stops the logger once the iterator is consumed.
+    fn progress_sum_and_stop(self, pl: ProgressLogger) -> T;
+}
+
+impl<I: Iterator<Item = u64>> ProgressSum<u64> for I {
+    fn progress_sum(self, pl: &mut ProgressLogger) -> u64 {
+        let mut sum = 0;
+        for x in self {
+            pl.update(x);
+            sum += x;
+        }
+        sum
+    }
+
+    fn progress_sum_and_stop(self, mut pl: ProgressLogger) -> u64 {
+        let sum = self.progress_sum(&mut pl);
+        pl.stop();
+        sum
+    }
+}
+
+impl<I: Iterator<Item = f64>> ProgressSum<f64> for I {
+    fn progress_sum(self, pl: &mut ProgressLogger) -> f64 {
+        let mut sum = 0.0;
+        for x in self {
+            pl.update(1u32);
+            sum += x;
+        }
+        sum
+    }
+
+    fn progress_sum_and_stop(self, mut pl: ProgressLogger) -> f64 {
+        let sum = self.progress_sum(&mut pl);
+        pl.stop();
+        sum
+    }
+}
+
+/// Tracks the progress of an iterator, counting one update per element.
+///
+/// ```
+/// use progress_logger::prelude::*;
+/// use progress_logger::ProgressLogger;
+///
+/// // The expected number of updates comes from the length of the iterator
+/// let squares: Vec<u64> = (0..1000u64).progress().map(|x| x * x).collect();
+/// assert_eq!(squares.len(), 1000);
+///
+/// let pl = ProgressLogger::builder().with_items_name("lines").start();
+/// let mut lines = "a\nb\nc".lines().progress_with(pl);
+/// assert_eq!(lines.next(), Some("a"));
+/// assert_eq!(lines.logger().unwrap().snapshot().count, 1);
+/// // Stops the logger, which was not exhausted
+/// drop(lines);
+/// ```
+///
+/// The completion statement is logged once, whether the iterator is
+/// exhausted, dropped, or both:
+///
+/// ```
+/// use progress_logger::prelude::*;
+/// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+///
+/// let lines = MemorySink::new();
+/// let pl = ProgressLogger::builder()
+///     .with_sink(SharedSink::new(lines.clone()))
+///     .start();
+/// let mut iter = vec![1, 2, 3].into_iter().progress_with(pl);
+/// assert_eq!(iter.len(), 3);
+/// assert_eq!(iter.next_back(), Some(3));
+/// assert_eq!(iter.by_ref().sum::<i32>(), 3);
+/// drop(iter);
+/// let output = lines.contents();
+/// assert_eq!(output.matches("Done").count(), 1);
+/// ```
+pub trait ProgressIteratorExt: Iterator + Sized {
+    /// Counts the elements with a logger with the default configuration
+    fn progress(self) -> WithProgress<Self> {
+        self.progress_with(ProgressLogger::builder().start())
+    }
+
+    /// Counts the elements with `pl`, stopping it once the iterator is
+    /// exhausted, or when the adapter is dropped, whichever comes first.
+    /// If the length of the iterator is known, as for an
+    /// `ExactSizeIterator`, and `pl` has no expected number of updates, the
+    /// length becomes the expected number of updates.
+    fn progress_with(self, mut pl: ProgressLogger) -> WithProgress<Self> {
+        if let (lower, Some(upper)) = self.size_hint() {
+            if lower == upper && pl.expected_updates.is_none() {
+                pl.expected_updates = Some(lower as u64);
+            }
+        }
+        WithProgress {
+            iter: self,
+            pl: Some(pl),
+        }
+    }
+}
+
+impl<I: Iterator> ProgressIteratorExt for I {}
+
+/// The iterator returned by `progress` and `progress_with`.
+pub struct WithProgress<I> {
+    iter: I,
+    /// `None` once the logger is stopped
+    pl: Option<ProgressLogger>,
+}
+
+impl<I> WithProgress<I> {
+    /// The logger, until the iterator is exhausted
+    pub fn logger(&self) -> Option<&ProgressLogger> {
+        self.pl.as_ref()
+    }
+
+    /// Counts the item, or stops the logger at the end of the iteration
+    fn counted(&mut self, item: Option<I::Item>) -> Option<I::Item>
+    where
+        I: Iterator,
+    {
+        match item {
+            Some(item) => {
+                if let Some(pl) = self.pl.as_mut() {
+                    pl.update(1u32);
+                }
+                Some(item)
+            }
+            None => {
+                if let Some(pl) = self.pl.take() {
+                    pl.stop();
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for WithProgress<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.next();
+        self.counted(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for WithProgress<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<I::Item> {
+        let item = self.iter.next_back();
+        self.counted(item)
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for WithProgress<I> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<I> Drop for WithProgress<I> {
+    fn drop(&mut self) {
+        if let Some(pl) = self.pl.take() {
+            pl.stop();
+        }
+    }
+}
+
+/// Tracks the progress of an iterator, with a flag to stop it early.
+pub trait ProgressWithCancel: Iterator + Sized {
+    /// Counts one update per element, stopping the logger once the iterator
+    /// is exhausted. Setting `cancel`, e.g. from another thread or a signal
+    /// handler, ends the iteration early: the logger then reports how many
+    /// elements were processed before the cancellation, in place of the
+    /// completion statement.
+    ///
+    /// The flag is checked only when the logger reads the clock, about 16
+    /// times per report interval, so cancellation takes effect within a
+    /// fraction of the configured frequency and costs nothing on the other
+    /// elements.
+    ///
+    /// ```
+    /// use progress_logger::prelude::*;
+    /// use progress_logger::{DigitGrouping, MemorySink, ProgressLogger, SharedSink};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let lines = MemorySink::new();
+    /// let pl = ProgressLogger::builder()
+    ///     .with_items_name("items")
+    ///     .with_frequency(Duration::from_millis(1))
+    ///     .with_digit_grouping(DigitGrouping::Plain)
+    ///     .with_sink(SharedSink::new(lines.clone()))
+    ///     .start();
+    /// let canceller = {
+    ///     let cancel = Arc::clone(&cancel);
+    ///     std::thread::spawn(move || {
+    ///         std::thread::sleep(Duration::from_millis(20));
+    ///         cancel.store(true, Ordering::Relaxed);
+    ///     })
+    /// };
+    /// let processed = (0..)
+    ///     .progress_with_cancel(pl, cancel)
+    ///     .inspect(|_| std::thread::sleep(Duration::from_micros(100)))
+    ///     .count();
+    /// canceller.join().unwrap();
+    /// assert!(processed > 0);
+    /// let last = lines.lines().pop().unwrap();
+    /// let expected = format!("Cancelled after {} items", processed);
+    /// assert!(last.starts_with(&expected), "{}", last);
+    /// ```
+    fn progress_with_cancel(self, pl: ProgressLogger, cancel: Arc<AtomicBool>)
+        -> Cancellable<Self>;
+}
+
+impl<I: Iterator> ProgressWithCancel for I {
+    fn progress_with_cancel(
+        self,
+        pl: ProgressLogger,
+        cancel: Arc<AtomicBool>,
+    ) -> Cancellable<Self> {
+        Cancellable {
+            iter: self,
+            pl: Some(pl),
+            cancel,
+            checked_time: false,
+        }
+    }
+}
+
+/// The iterator returned by `progress_with_cancel`.
+pub struct Cancellable<I> {
+    iter: I,
+    /// `None` once the iteration is over, either way
+    pl: Option<ProgressLogger>,
+    cancel: Arc<AtomicBool>,
+    /// whether the last update read the clock, and thus the flag should
+    /// be checked before moving on
+    checked_time: bool,
+}
+
+impl<I: Iterator> Iterator for Cancellable<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let pl = self.pl.as_mut()?;
+        if self.checked_time && self.cancel.load(Ordering::Relaxed) {
+            self.pl.take().unwrap().stop_cancelled();
+            return None;
+        }
+        match self.iter.next() {
+            Some(item) => {
+                self.checked_time = pl.update_checking_time(1);
+                Some(item)
+            }
+            None => {
+                self.pl.take().unwrap().stop();
+                None
+            }
+        }
+    }
+}
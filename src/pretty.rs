@@ -0,0 +1,416 @@
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Escapes control characters (newlines, ANSI escapes, ...) in user supplied
+/// strings, so that they cannot break a report in several lines or alter the
+/// terminal. Format specifiers like `{}` need no escaping, since user strings
+/// are always passed as arguments to the formatting macros.
+pub(crate) fn sanitize(s: &str) -> Cow<'_, str> {
+    if s.chars().any(char::is_control) {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            if c.is_control() {
+                escaped.extend(c.escape_default());
+            } else {
+                escaped.push(c);
+            }
+        }
+        Cow::Owned(escaped)
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Renders a wall-clock time as `2024-05-12 03:15 UTC`, clamping times
+/// before the Unix epoch to it
+pub(crate) fn utc_minutes(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60
+    )
+}
+
+/// Renders a signed amount of kB with a binary prefix, e.g. `+6.2 GiB`,
+/// up to ZiB to cover the whole range
+pub(crate) fn signed_kib(kb: i64) -> String {
+    let mut value = kb as f64;
+    let mut unit = "KiB";
+    for next in ["MiB", "GiB", "TiB", "PiB", "EiB", "ZiB"] {
+        if value.abs() < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:+.1} {}", value, unit)
+}
+
+/// How the groups of three digits of full numbers are told apart in the
+/// reports, see `with_digit_grouping`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigitGrouping {
+    /// Alternate groups underlined with ANSI escape sequences, for
+    /// terminals
+    Underline,
+    /// `1,234,567.89`
+    Comma,
+    /// `1 234 567.89`
+    Space,
+    /// `1234567.89`, easy to parse
+    Plain,
+}
+
+/// How numbers are rendered in the reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NumberFormat {
+    /// Full digits, with separated groups of three digits
+    Grouped(DigitGrouping),
+    /// Three significant digits followed by an SI prefix, e.g. `3.85M`
+    SiPrefix,
+}
+
+impl NumberFormat {
+    pub(crate) fn int(self, n: u64) -> PrettyNumber {
+        match self {
+            NumberFormat::Grouped(grouping) => PrettyNumber::grouped(&n.to_string(), grouping),
+            NumberFormat::SiPrefix => PrettyNumber::si(n as f64, 0),
+        }
+    }
+
+    /// Renders `x`, or `n/a` if it is not finite, e.g. the throughput of
+    /// an interval of zero length
+    pub(crate) fn real(self, x: f64) -> PrettyNumber {
+        match self {
+            _ if !x.is_finite() => PrettyNumber::not_available(),
+            NumberFormat::Grouped(grouping) => PrettyNumber::grouped(&format!("{:.2}", x), grouping),
+            NumberFormat::SiPrefix => PrettyNumber::si(x, 2),
+        }
+    }
+
+    /// Renders a number of seconds, or `n/a` if it is not finite
+    pub(crate) fn secs(self, x: f64) -> PrettyNumber {
+        match self {
+            _ if !x.is_finite() => PrettyNumber::not_available(),
+            NumberFormat::Grouped(_) => PrettyNumber {
+                rendered: format!("{:.2}", x),
+            },
+            NumberFormat::SiPrefix => PrettyNumber::si(x, 2),
+        }
+    }
+}
+
+pub(crate) struct PrettyNumber {
+    rendered: String,
+}
+
+impl PrettyNumber {
+    fn not_available() -> PrettyNumber {
+        PrettyNumber {
+            rendered: "n/a".to_owned(),
+        }
+    }
+
+    /// Renders the number `s`, grouping the digits of its integer part,
+    /// after the sign, if any
+    fn grouped(s: &str, grouping: DigitGrouping) -> PrettyNumber {
+        if let Some(magnitude) = s.strip_prefix('-') {
+            let mut pretty = PrettyNumber::grouped(magnitude, grouping);
+            pretty.rendered.insert(0, '-');
+            return pretty;
+        }
+        let (integer, decimals) = match s.split_once('.') {
+            Some((integer, decimals)) => (integer, Some(decimals)),
+            None => (s, None),
+        };
+        let mut rendered = match grouping {
+            DigitGrouping::Underline => underline_groups(integer),
+            DigitGrouping::Comma => separate_groups(integer, ','),
+            DigitGrouping::Space => separate_groups(integer, ' '),
+            DigitGrouping::Plain => integer.to_owned(),
+        };
+        if let Some(decimals) = decimals {
+            rendered.push('.');
+            rendered.push_str(decimals);
+        }
+        PrettyNumber { rendered }
+    }
+
+    /// Renders `x` with three significant digits and an SI prefix. Numbers
+    /// below one thousand are rendered with `small_decimals` decimal digits,
+    /// and numbers that are not finite as `n/a`.
+    fn si(x: f64, small_decimals: usize) -> PrettyNumber {
+        const PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+        if !x.is_finite() {
+            return PrettyNumber::not_available();
+        }
+        if x < 0.0 {
+            let mut pretty = PrettyNumber::si(-x, small_decimals);
+            pretty.rendered.insert(0, '-');
+            return pretty;
+        }
+        let mut scaled = x;
+        let mut prefix = None;
+        for p in PREFIXES.iter() {
+            // Values that would round up to 1000 move to the next prefix,
+            // so that 999_999 becomes `1.00M` rather than `1000k`
+            if scaled < 999.5 {
+                break;
+            }
+            scaled /= 1000.0;
+            prefix = Some(p);
+        }
+        let rendered = match prefix {
+            None => format!("{:.*}", small_decimals, scaled),
+            Some(p) if scaled < 9.995 => format!("{:.2}{}", scaled, p),
+            Some(p) if scaled < 99.95 => format!("{:.1}{}", scaled, p),
+            Some(p) => format!("{:.0}{}", scaled, p),
+        };
+        PrettyNumber { rendered }
+    }
+}
+
+impl std::fmt::Display for PrettyNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+/// How the durations in the reports are rendered, see
+/// `with_duration_format`. Below a minute, all the formats render
+/// durations in seconds, or in smaller units below a second.
+///
+/// ```
+/// use progress_logger::DurationFormat;
+/// use std::time::Duration;
+///
+/// let secs = Duration::from_secs;
+/// let compact = |d| DurationFormat::Compact.render(d);
+/// assert_eq!(compact(Duration::from_micros(350_120)), "350.12ms");
+/// assert_eq!(compact(Duration::from_millis(59_999)), "59.99s");
+/// assert_eq!(compact(secs(60)), "1m0s");
+/// assert_eq!(compact(secs(3599)), "59m59s");
+/// assert_eq!(compact(secs(3600)), "1h0m0s");
+/// assert_eq!(compact(secs(86_399)), "23h59m59s");
+/// assert_eq!(compact(secs(86_400)), "1d0h0m");
+/// assert_eq!(compact(secs(93_212)), "1d1h53m");
+///
+/// let colon = |d| DurationFormat::Colon.render(d);
+/// assert_eq!(colon(Duration::from_millis(59_999)), "59.99s");
+/// assert_eq!(colon(secs(60)), "1:00");
+/// assert_eq!(colon(secs(3599)), "59:59");
+/// assert_eq!(colon(secs(3600)), "1:00:00");
+/// assert_eq!(colon(secs(93_212)), "25:53:32");
+///
+/// assert_eq!(DurationFormat::Seconds.render(secs(93_212)), "93212.00s");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DurationFormat {
+    /// `93212.41s`, and `93212.41 s left` for the time to completion
+    #[default]
+    Seconds,
+    /// The three largest units from the first nonzero one, e.g. `1d1h53m`
+    /// or `4m12s`
+    Compact,
+    /// Hours, minutes and seconds, e.g. `25:53:32` or `4:12`
+    Colon,
+}
+
+impl DurationFormat {
+    /// Renders `duration` in this format
+    pub fn render(self, duration: Duration) -> String {
+        self.pretty(duration).rendered
+    }
+
+    pub(crate) fn pretty(self, duration: Duration) -> PrettyDuration {
+        let secs = duration.as_secs();
+        let rendered = match self {
+            DurationFormat::Seconds => format!("{:.2?}", duration),
+            _ if duration < Duration::from_secs(1) => format!("{:.2?}", duration),
+            // Truncated rather than rounded, not to show `60.00s`
+            _ if secs < 60 => format!("{}.{:02}s", secs, duration.subsec_millis() / 10),
+            DurationFormat::Compact => {
+                let parts = [
+                    (secs / 86_400, 'd'),
+                    (secs % 86_400 / 3600, 'h'),
+                    (secs % 3600 / 60, 'm'),
+                    (secs % 60, 's'),
+                ];
+                let first = parts.iter().position(|&(value, _)| value > 0).unwrap_or(3);
+                parts[first..]
+                    .iter()
+                    .take(3)
+                    .map(|(value, unit)| format!("{}{}", value, unit))
+                    .collect()
+            }
+            DurationFormat::Colon if secs < 3600 => format!("{}:{:02}", secs / 60, secs % 60),
+            DurationFormat::Colon => {
+                format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+            }
+        };
+        PrettyDuration { rendered }
+    }
+
+    /// Renders a time to completion, in seconds, e.g. `102.16 s`, with
+    /// `fmt` in the `Seconds` format, or `1m42s`
+    pub(crate) fn time_left(self, fmt: NumberFormat, secs: f64) -> String {
+        match Duration::try_from_secs_f64(secs) {
+            Ok(duration) if self != DurationFormat::Seconds => self.pretty(duration).rendered,
+            _ => format!("{} s", fmt.secs(secs)),
+        }
+    }
+}
+
+/// A duration rendered in a `DurationFormat`
+pub(crate) struct PrettyDuration {
+    rendered: String,
+}
+
+impl std::fmt::Display for PrettyDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+impl std::fmt::Debug for PrettyNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+/// Underlines alternate groups of three digits of the given integer string
+fn underline_groups(s: &str) -> String {
+    let tmp: Vec<char> = s.chars().rev().collect();
+    let mut chunks: Vec<&[char]> = tmp.chunks(3).collect();
+
+    let mut rendered = String::new();
+    let mut ul = chunks.len() % 2 == 1;
+    while let Some(chunk) = chunks.pop() {
+        let mut chunk = Vec::from(chunk);
+        if ul {
+            rendered.push_str("\x1B[0m");
+        } else {
+            rendered.push_str("\x1B[4m");
+        }
+        ul = !ul;
+        while let Some(c) = chunk.pop() {
+            rendered.push(c);
+        }
+    }
+    if ul {
+        rendered.push_str("\x1B[0m");
+    }
+    rendered
+}
+
+/// Separates the groups of three digits of the given integer string
+fn separate_groups(s: &str, separator: char) -> String {
+    let mut rendered = String::with_capacity(s.len() + s.len() / 3);
+    for (i, c) in s.chars().enumerate() {
+        if i > 0 && (s.len() - i).is_multiple_of(3) {
+            rendered.push(separator);
+        }
+        rendered.push(c);
+    }
+    rendered
+}
+
+/// The unit of the counts, see `with_items_unit`. Byte units render counts
+/// and throughputs with the largest prefix keeping the value at least 1, in
+/// place of the name of the items, whatever the number format.
+///
+/// ```
+/// use progress_logger::{ProgressLogger, Unit};
+///
+/// let preview = ProgressLogger::builder()
+///     .with_expected_updates(1u64 << 30)
+///     .with_items_unit(Unit::Bytes)
+///     .preview()?;
+/// assert!(preview.contains(" 397.28 MB, 102.16 s left (6.62 MB/s)"));
+/// assert!(preview.ends_with(". 1.07 GB (6.62 MB/s)"));
+///
+/// let preview = ProgressLogger::builder()
+///     .with_expected_updates(1u64 << 30)
+///     .with_items_unit(Unit::BinaryBytes)
+///     .preview()?;
+/// assert!(preview.ends_with(". 1.00 GiB (6.31 MiB/s)"));
+/// # Ok::<(), progress_logger::ConfigError>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Unit {
+    /// Plain counts of the items, e.g. `1,234 rows`
+    #[default]
+    Count,
+    /// Bytes, with decimal prefixes, e.g. `1.23 MB`
+    Bytes,
+    /// Bytes, with binary prefixes, e.g. `1.17 MiB`
+    BinaryBytes,
+}
+
+impl Unit {
+    /// Renders a count of `items`, e.g. `1,234 rows` or `1.23 MB`
+    pub(crate) fn count(self, fmt: NumberFormat, n: u64, items: &str) -> String {
+        match self {
+            Unit::Count => format!("{} {}", fmt.int(n), items),
+            _ => self.number(fmt, n),
+        }
+    }
+
+    /// Renders a count without the name of the items, which byte units
+    /// include anyway
+    pub(crate) fn number(self, fmt: NumberFormat, n: u64) -> String {
+        match self.bytes(n as f64) {
+            Some((value, "B")) => format!("{:.0} B", value),
+            Some((value, prefix)) => format!("{:.2} {}", value, prefix),
+            None => fmt.int(n).to_string(),
+        }
+    }
+
+    /// Renders a throughput, e.g. `1,234.50 rows/s` or `1.23 MB/s`
+    pub(crate) fn rate(self, fmt: NumberFormat, x: f64, items: &str) -> String {
+        match self.bytes(x) {
+            Some(_) if !x.is_finite() => format!("{} B/s", NumberFormat::SiPrefix.real(x)),
+            Some((value, prefix)) => format!("{:.2} {}/s", value, prefix),
+            None => format!("{} {}/s", fmt.real(x), items),
+        }
+    }
+
+    /// Scales `x` bytes, `None` for plain counts
+    fn bytes(self, x: f64) -> Option<(f64, &'static str)> {
+        let (base, prefixes) = match self {
+            Unit::Count => return None,
+            Unit::Bytes => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+            Unit::BinaryBytes => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        };
+        let mut value = x;
+        let mut prefix = prefixes[0];
+        for next in &prefixes[1..] {
+            // Values that would round up to `base` move to the next prefix
+            if value < base - 0.005 {
+                break;
+            }
+            value /= base;
+            prefix = next;
+        }
+        Some((value, prefix))
+    }
+}
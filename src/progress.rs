@@ -0,0 +1,88 @@
+use crate::ProgressLogger;
+
+/// The operations needed to report progress, so that library functions can
+/// take an `impl Progress` and let callers choose between a real logger and
+/// `NoProgress`, rather than threading an `Option<&mut ProgressLogger>`.
+///
+/// It is implemented by `ProgressLogger`, by `NoProgress`, and by mutable
+/// references to implementors, so callers can keep using their logger after
+/// the call.
+///
+/// ```
+/// use progress_logger::{NoProgress, Progress, ProgressLogger};
+///
+/// fn sum_of_squares(data: &[u64], mut pl: impl Progress) -> u64 {
+///     let mut sum = 0;
+///     for x in data {
+///         sum += x * x;
+///         pl.update_light(1);
+///     }
+///     sum
+/// }
+///
+/// let data: Vec<u64> = (0..1000).collect();
+/// // Without progress reporting
+/// let quiet = sum_of_squares(&data, NoProgress);
+/// // With a logger, still available after the call
+/// let mut pl = ProgressLogger::builder().with_items_name("numbers").start();
+/// let reported = sum_of_squares(&data, &mut pl);
+/// pl.stop();
+/// assert_eq!(quiet, reported);
+/// ```
+pub trait Progress {
+    /// Adds `n` to the counter, see `ProgressLogger::update`
+    fn update(&mut self, n: u64);
+
+    /// Adds `n` to the counter, hinting that updates are very frequent and
+    /// should be cheap
+    #[inline]
+    fn update_light(&mut self, n: u64) {
+        self.update(n);
+    }
+
+    /// Reports progress right away, regardless of the configured frequency
+    fn report_now(&mut self);
+}
+
+/// A `Progress` implementation doing nothing, which compiles to no code.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    #[inline(always)]
+    fn update(&mut self, _n: u64) {}
+
+    #[inline(always)]
+    fn update_light(&mut self, _n: u64) {}
+
+    #[inline(always)]
+    fn report_now(&mut self) {}
+}
+
+impl Progress for ProgressLogger {
+    #[inline]
+    fn update(&mut self, n: u64) {
+        ProgressLogger::update(self, n);
+    }
+
+    fn report_now(&mut self) {
+        self.checkpoint();
+    }
+}
+
+impl<P: Progress + ?Sized> Progress for &mut P {
+    #[inline]
+    fn update(&mut self, n: u64) {
+        (**self).update(n);
+    }
+
+    #[inline]
+    fn update_light(&mut self, n: u64) {
+        (**self).update_light(n);
+    }
+
+    #[inline]
+    fn report_now(&mut self) {
+        (**self).report_now();
+    }
+}
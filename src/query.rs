@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Serves the last snapshot of a logger, as a JSON line, to every client
+/// connecting to a unix domain socket. The socket is served by a dedicated
+/// thread, and removed when the server is dropped.
+pub(crate) struct QueryServer {
+    path: PathBuf,
+    snapshot: Arc<Mutex<String>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl QueryServer {
+    pub(crate) fn bind(path: PathBuf, initial: String) -> std::io::Result<Self> {
+        let listener = UnixListener::bind(&path)?;
+        // Snapshots may contain sensitive metadata: only the owner can connect
+        if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+        let snapshot = Arc::new(Mutex::new(initial));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let snapshot = Arc::clone(&snapshot);
+            let shutdown = Arc::clone(&shutdown);
+            std::thread::Builder::new()
+                .name("progress-query".to_owned())
+                .spawn(move || serve(listener, snapshot, shutdown))?
+        };
+        Ok(Self {
+            path,
+            snapshot,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub(crate) fn set_snapshot(&self, snapshot: String) {
+        *self.snapshot.lock().unwrap_or_else(|e| e.into_inner()) = snapshot;
+    }
+}
+
+fn serve(listener: UnixListener, snapshot: Arc<Mutex<String>>, shutdown: Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::Acquire) {
+            break;
+        }
+        if let Ok(mut stream) = stream {
+            let line = snapshot.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            // A client going away early is not our concern
+            let _ = stream.write_all(line.as_bytes());
+            let _ = stream.write_all(b"\n");
+        }
+    }
+}
+
+impl Drop for QueryServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        // Wake up the thread blocked on `accept`. If that's not possible
+        // the thread is left behind, rather than blocking forever on join
+        if UnixStream::connect(&self.path).is_ok() {
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
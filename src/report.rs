@@ -0,0 +1,49 @@
+use crate::ResolvedConfig;
+use std::time::Duration;
+
+/// The outcome of a run, returned by `ProgressLogger::stop` and
+/// `ProgressLogger::finish_silently`, to check the throughput in tests or
+/// record it, without parsing the completion statement.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let mut pl = ProgressLogger::builder().with_items_name("rows").start();
+/// for _ in 0..1000 {
+///     pl.update(1u32);
+/// }
+/// let report = pl.stop();
+/// assert_eq!(report.total_items, 1000);
+/// assert!(report.throughput_per_sec > 0.0);
+/// assert_eq!(report.config.items, "rows");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressReport {
+    pub total_items: u64,
+    /// the time elapsed since the start, pauses excluded
+    pub elapsed: Duration,
+    /// the throughput over the whole run, in updates per second
+    pub throughput_per_sec: f64,
+    /// the largest memory usage of the machine among the samples of the
+    /// reports and phases, in kB, `None` if the memory was never sampled,
+    /// see `with_memory_reporting`
+    pub peak_memory_kb: Option<u64>,
+    /// the average time taken to write the last reports, see
+    /// `with_slow_output_threshold`
+    pub output_latency: Option<Duration>,
+    /// the memory held by the logger at the end of the run, in bytes, see
+    /// `ProgressLogger::estimated_memory_footprint`
+    pub memory_footprint: usize,
+    /// the count as a function of the elapsed time, pauses excluded,
+    /// downsampled to at most 257 points from the start to the end of the
+    /// run, see `ProgressLogger::throughput_curve`
+    pub throughput_curve: Vec<(Duration, u64)>,
+    /// the number of times the counter did not advance for at least the
+    /// frequency, see `ProgressLogger::stall_episodes`
+    pub stall_episodes: u64,
+    /// the cumulative duration of these stalls
+    pub stalled_duration: Duration,
+    /// the configuration of the logger, to archive along with the outcome,
+    /// see `ProgressLogger::config`
+    pub config: ResolvedConfig,
+}
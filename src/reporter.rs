@@ -0,0 +1,121 @@
+use crate::{ProgressReport, ProgressSnapshot, ResolvedConfig};
+
+/// What happens to a logger, in the order it happens, see `Reporter`.
+/// Events rendered by the logger carry their line, as written to the sink
+/// or the `log` crate, see `line`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// the logger started, with the given configuration
+    Started { config: ResolvedConfig },
+    /// a periodic report, with no line if rendered by the background
+    /// thread, see `with_async_reports`
+    Report {
+        snapshot: ProgressSnapshot,
+        line: Option<String>,
+    },
+    /// the count reached a milestone, see `with_milestones`; its report
+    /// follows
+    Milestone { count: u64 },
+    /// a phase started, see `start_phase`
+    PhaseStarted { name: String },
+    /// the running phase ended
+    PhaseEnded { name: String },
+    /// a message logged with `note`
+    Note { line: String },
+    /// an informational message of the logger, e.g. about the output budget
+    Notice { line: String },
+    /// a warning of the logger
+    Warning { kind: WarningKind, line: String },
+    /// the completion statement, with no line from `finish_silently`
+    Finished {
+        report: ProgressReport,
+        line: Option<String>,
+    },
+    /// the computation was cancelled, see `progress_with_cancel`
+    Cancelled {
+        report: ProgressReport,
+        line: String,
+    },
+}
+
+impl ProgressEvent {
+    /// The line written for the event, if any: collecting them gives the
+    /// text output of the logger, without the prefix of a `ChildLogger`
+    pub fn line(&self) -> Option<&str> {
+        match self {
+            ProgressEvent::Report { line, .. } | ProgressEvent::Finished { line, .. } => {
+                line.as_deref()
+            }
+            ProgressEvent::Note { line }
+            | ProgressEvent::Notice { line }
+            | ProgressEvent::Warning { line, .. }
+            | ProgressEvent::Cancelled { line, .. } => Some(line),
+            _ => None,
+        }
+    }
+}
+
+/// The cause of a `ProgressEvent::Warning`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// the monotonic clock went backwards, see `with_clock_skew_detection`
+    ClockSkew,
+    /// more retries than expected, see `with_retry_warning`
+    Retries,
+    /// more open file descriptors than expected, see `with_open_fds`
+    OpenFileDescriptors,
+    /// an extra field panicked, see `with_extra_field`
+    ExtraFieldPanicked,
+    /// a hook panicked, see `with_update_hook`, `with_interval_hook` and
+    /// `with_on_log`
+    HookPanicked,
+    /// a reporter panicked, see `with_reporter`
+    ReporterPanicked,
+    /// updates arrived after the completion statement, see
+    /// `with_after_finish`
+    UpdateAfterFinish,
+    /// the workers of `parallel_chunks` waited too long for the logger
+    LockWait,
+    /// the reports took too long to write, see `with_slow_output_threshold`
+    SlowOutput,
+    /// some entries of `walk_dir` could not be read
+    UnreadableEntries,
+}
+
+/// Receives the events of a logger, see `with_reporter`, to build outputs
+/// other than text, e.g. a dashboard. Implemented for closures.
+///
+/// ```
+/// use progress_logger::{ProgressEvent, ProgressLogger};
+/// use std::sync::mpsc;
+///
+/// let (tx, rx) = mpsc::channel();
+/// let mut pl = ProgressLogger::builder()
+///     .with_reporter(move |event: &ProgressEvent| {
+///         let _ = tx.send(event.clone());
+///     })
+///     .start();
+/// pl.update(10u32);
+/// pl.stop();
+/// let events: Vec<ProgressEvent> = rx.iter().collect();
+/// assert!(matches!(events[0], ProgressEvent::Started { .. }));
+/// match &events[1] {
+///     ProgressEvent::Finished { report, line } => {
+///         assert_eq!(report.total_items, 10);
+///         assert!(line.as_ref().unwrap().starts_with("Done"));
+///     }
+///     event => panic!("unexpected {:?}", event),
+/// }
+/// ```
+pub trait Reporter: Send {
+    /// Handles the event, on the thread updating the logger
+    fn handle(&mut self, event: &ProgressEvent);
+}
+
+impl<F: FnMut(&ProgressEvent) + Send> Reporter for F {
+    fn handle(&mut self, event: &ProgressEvent) {
+        self(event)
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+/// The state of a logger at the time of a report, collected when the logger
+/// is built with `with_collect_samples(true)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressSample {
+    pub timestamp: Instant,
+    pub count: u64,
+    /// the throughput since the start, in updates per second
+    pub throughput: f64,
+    /// the estimated time to completion, in seconds
+    pub ettc: Option<f64>,
+    pub memory_kb: u64,
+    /// the time since the previous report, `None` for the first one
+    pub interval: Option<Duration>,
+    /// the counter increment since the previous report, `None` for the first one
+    pub interval_count_delta: Option<u64>,
+}
+
+/// Trend analysis over a sequence of samples
+pub trait ThroughputSlope {
+    /// Fits a least squares line to the throughput of the samples as a function
+    /// of time, returning its slope in updates per second, per second. Positive
+    /// values mean that the computation is speeding up.
+    ///
+    /// Returns `NaN` if there are fewer than two samples with distinct timestamps.
+    fn compute_slope(&self) -> f64;
+}
+
+impl ThroughputSlope for [ProgressSample] {
+    fn compute_slope(&self) -> f64 {
+        let first = match self.first() {
+            Some(sample) => sample.timestamp,
+            None => return f64::NAN,
+        };
+        let n = self.len() as f64;
+        let xs = self
+            .iter()
+            .map(|sample| (sample.timestamp - first).as_secs_f64());
+        let mean_x = xs.clone().sum::<f64>() / n;
+        let mean_y = self.iter().map(|sample| sample.throughput).sum::<f64>() / n;
+        let mut covariance = 0.0;
+        let mut variance = 0.0;
+        for (x, sample) in xs.zip(self.iter()) {
+            covariance += (x - mean_x) * (sample.throughput - mean_y);
+            variance += (x - mean_x) * (x - mean_x);
+        }
+        if variance == 0.0 {
+            f64::NAN
+        } else {
+            covariance / variance
+        }
+    }
+}
+
+/// The maximum number of samples kept: longer runs are downsampled
+pub(crate) const SAMPLE_CAPACITY: usize = 4096;
+
+/// The samples of a run, see `ProgressLogger::samples`. When the capacity is
+/// reached, every other sample is dropped and only one report out of twice
+/// as many as before is sampled from then on, so that the samples span the
+/// whole run in constant space.
+#[derive(Debug)]
+pub(crate) struct SampleLog {
+    samples: Vec<ProgressSample>,
+    /// the number of reports between two samples
+    stride: u64,
+    /// the reports since the last sample
+    skipped: u64,
+}
+
+impl SampleLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            stride: 1,
+            skipped: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, sample: ProgressSample) {
+        if self.skipped + 1 < self.stride {
+            self.skipped += 1;
+            return;
+        }
+        self.skipped = 0;
+        self.samples.push(sample);
+        if self.samples.len() >= SAMPLE_CAPACITY {
+            let mut i = 0;
+            self.samples.retain(|_| {
+                i += 1;
+                i % 2 == 1
+            });
+            self.stride *= 2;
+        }
+    }
+
+    pub(crate) fn samples(&self) -> &[ProgressSample] {
+        &self.samples
+    }
+
+    /// The heap memory held, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        self.samples.capacity() * std::mem::size_of::<ProgressSample>()
+    }
+}
@@ -0,0 +1,257 @@
+use crate::emit::emit;
+use crate::Level;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A destination for the reports other than the `log` crate, such as a
+/// file, which can be shared by several loggers, see `with_sink`.
+///
+/// Each message is rendered in full, then written with a single `write_all`
+/// while holding the lock of the sink, so that the lines of loggers running
+/// on different threads never interleave.
+///
+/// ```
+/// use progress_logger::{ProgressLogger, SharedSink};
+/// use std::io::Write;
+/// use std::sync::{Arc, Mutex};
+/// use std::time::Duration;
+///
+/// /// An in-memory sink writing a byte at a time, to make interleaving likely
+/// #[derive(Clone, Default)]
+/// struct Bytes(Arc<Mutex<Vec<u8>>>);
+///
+/// impl Write for Bytes {
+///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+///         match buf.first() {
+///             Some(&b) => {
+///                 self.0.lock().unwrap().push(b);
+///                 Ok(1)
+///             }
+///             None => Ok(0),
+///         }
+///     }
+///
+///     fn flush(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let bytes = Bytes::default();
+/// let sink = SharedSink::new(bytes.clone());
+/// let workers: Vec<_> = ["reads", "writes"]
+///     .iter()
+///     .map(|name| {
+///         let mut pl = ProgressLogger::builder()
+///             .with_items_name(*name)
+///             .with_frequency(Duration::from_millis(0))
+///             .with_min_report_spacing(Duration::from_millis(0))
+///             .with_sink(sink.clone())
+///             .start();
+///         std::thread::spawn(move || {
+///             for _ in 0..200 {
+///                 pl.update(1u32);
+///             }
+///             pl.stop();
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// let output = String::from_utf8(bytes.0.lock().unwrap().clone()).unwrap();
+/// assert!(output.lines().count() >= 2);
+/// for line in output.lines() {
+///     let reads = line.contains(" reads");
+///     let writes = line.contains(" writes");
+///     assert!(reads != writes, "malformed line: {}", line);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedSink {
+    writer: Arc<Mutex<dyn Write + Send>>,
+    /// the name of the sink in error messages, e.g. the path of its file
+    name: Arc<str>,
+    errors: Arc<Errors>,
+}
+
+#[derive(Default)]
+struct Errors {
+    count: AtomicU64,
+    first: Mutex<Option<String>>,
+}
+
+/// What to do when a sink fails to write a message, e.g. on a full disk,
+/// see `with_sink_error_policy`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SinkErrorPolicy {
+    /// Log the first error at the `Error` level, count the later ones, and
+    /// keep running. Reports mention the count as `(sink errors: 14)`.
+    #[default]
+    Count,
+    /// Panic, interrupting the computation, rather than lose reports
+    Fail,
+}
+
+/// Applies `policy` to a failed write of the reports of `items` to `sink`,
+/// reporting the first failure with `target`
+pub(crate) fn sink_failed(
+    policy: SinkErrorPolicy,
+    target: &str,
+    items: &str,
+    sink: &SharedSink,
+    error: &io::Error,
+    first: bool,
+) {
+    if policy == SinkErrorPolicy::Fail {
+        panic!(
+            "failed to write the reports of {} to {}: {}",
+            items,
+            sink.name(),
+            error
+        );
+    }
+    if first {
+        emit(
+            target,
+            Level::Error,
+            format_args!(
+                "Failed to write the reports of {} to {}: {}; counting the later failures",
+                items,
+                sink.name(),
+                error
+            ),
+        );
+    }
+}
+
+/// The outcome of `SharedSink::write_line`
+pub(crate) enum SinkWrite {
+    Written,
+    /// the writer returned an error, the first one of the sink if `first`
+    Failed {
+        error: io::Error,
+        first: bool,
+    },
+    Panicked,
+}
+
+impl SharedSink {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::named("sink", writer)
+    }
+
+    /// Creates (or truncates) the file at `path`, naming the sink after it
+    /// in error messages
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)?;
+        Ok(Self::named(&path.display().to_string(), file))
+    }
+
+    fn named<W: Write + Send + 'static>(name: &str, writer: W) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(writer)),
+            name: name.into(),
+            errors: Arc::default(),
+        }
+    }
+
+    /// The name of the sink in error messages: the path of its file, or
+    /// `sink`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of messages the sink failed to write, by all the loggers
+    /// sharing it
+    pub fn errors(&self) -> u64 {
+        self.errors.count.load(Ordering::Relaxed)
+    }
+
+    /// The description of the first error of the sink, if any
+    pub fn first_error(&self) -> Option<String> {
+        self.errors
+            .first
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Writes the message followed by a newline, in a single call
+    pub(crate) fn write_line(&self, args: fmt::Arguments) -> SinkWrite {
+        let mut buf = args.to_string();
+        buf.push('\n');
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let written = panic::catch_unwind(AssertUnwindSafe(|| {
+            writer.write_all(buf.as_bytes())?;
+            writer.flush()
+        }));
+        match written {
+            Ok(Ok(())) => SinkWrite::Written,
+            Ok(Err(error)) => {
+                let first = self.errors.count.fetch_add(1, Ordering::Relaxed) == 0;
+                if first {
+                    let mut first_error =
+                        self.errors.first.lock().unwrap_or_else(|e| e.into_inner());
+                    *first_error = Some(error.to_string());
+                }
+                SinkWrite::Failed { error, first }
+            }
+            Err(_) => SinkWrite::Panicked,
+        }
+    }
+}
+
+/// An in-memory writer for `SharedSink`, whose clones share the output,
+/// e.g. to check the reports in tests
+///
+/// ```
+/// use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+///
+/// let lines = MemorySink::new();
+/// let mut pl = ProgressLogger::builder()
+///     .with_items_name("rows")
+///     .with_sink(SharedSink::new(lines.clone()))
+///     .start();
+/// pl.update(3u32);
+/// pl.stop();
+/// assert_eq!(lines.lines().len(), 1);
+/// assert!(lines.contents().starts_with("Done in "));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink(Arc<Mutex<Vec<u8>>>);
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The output so far, with invalid UTF-8 replaced
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap_or_else(|e| e.into_inner())).into_owned()
+    }
+
+    /// The lines of the output so far
+    pub fn lines(&self) -> Vec<String> {
+        self.contents().lines().map(str::to_owned).collect()
+    }
+}
+
+impl Write for MemorySink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::Write as _;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The state of a logger at a given point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressSnapshot {
+    pub items: String,
+    pub count: u64,
+    pub expected_updates: Option<u64>,
+    pub elapsed: Duration,
+    /// the wall-clock time at which the logger started
+    pub started_at: SystemTime,
+    /// the throughput since the start, in updates per second
+    pub throughput: f64,
+    pub time_to_completion: Option<Duration>,
+    /// the time between the last two reports, `None` before the second report
+    pub interval: Option<Duration>,
+    /// the counter increment between the last two reports, `None` before
+    /// the second report
+    pub interval_count_delta: Option<u64>,
+    /// the time the workers of `parallel_chunks` spent waiting for the
+    /// logger, summed over all the workers
+    pub lock_wait: Duration,
+    /// the messages the sink failed to write, see `SharedSink::errors`
+    pub sink_errors: u64,
+    /// the version of the crate that took the snapshot, see `VERSION`
+    pub version: &'static str,
+}
+
+impl ProgressSnapshot {
+    /// Renders the snapshot as a single line JSON object
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+        write!(out, "\"version\":{}", json_string(self.version)).unwrap();
+        write!(out, ",\"items\":{}", json_string(&self.items)).unwrap();
+        write!(out, ",\"count\":{}", self.count).unwrap();
+        match self.expected_updates {
+            Some(expected) => write!(out, ",\"expected_updates\":{}", expected).unwrap(),
+            None => out.push_str(",\"expected_updates\":null"),
+        }
+        write!(out, ",\"elapsed_secs\":{}", self.elapsed.as_secs_f64()).unwrap();
+        write!(
+            out,
+            ",\"started_at_unix_secs\":{}",
+            self.started_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64()
+        )
+        .unwrap();
+        write!(out, ",\"throughput\":{}", json_number(self.throughput)).unwrap();
+        write!(
+            out,
+            ",\"time_to_completion_secs\":{}",
+            self.time_to_completion
+                .map_or("null".to_owned(), |d| json_number(d.as_secs_f64()))
+        )
+        .unwrap();
+        write!(
+            out,
+            ",\"interval_secs\":{}",
+            self.interval
+                .map_or("null".to_owned(), |d| json_number(d.as_secs_f64()))
+        )
+        .unwrap();
+        match self.interval_count_delta {
+            Some(delta) => write!(out, ",\"interval_count_delta\":{}", delta).unwrap(),
+            None => out.push_str(",\"interval_count_delta\":null"),
+        }
+        write!(
+            out,
+            ",\"lock_wait_secs\":{}",
+            json_number(self.lock_wait.as_secs_f64())
+        )
+        .unwrap();
+        write!(out, ",\"sink_errors\":{}", self.sink_errors).unwrap();
+        out.push('}');
+        out
+    }
+}
+
+/// Renders a string as a JSON string literal, with the necessary escapes
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Renders a number, mapping the values that JSON cannot represent to `null`
+pub(crate) fn json_number(x: f64) -> String {
+    if x.is_finite() {
+        format!("{}", x)
+    } else {
+        "null".to_owned()
+    }
+}
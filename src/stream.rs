@@ -0,0 +1,40 @@
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::time::Duration;
+
+/// Receives a `(elapsed, count)` sample at every report, see
+/// `with_sample_channel`. Implemented for bounded standard channels, and
+/// for closures, which can forward the samples to any other channel:
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+/// use std::sync::mpsc;
+///
+/// // Stands for e.g. a `tokio::sync::mpsc::Sender`
+/// let (tx, rx) = mpsc::channel();
+/// let pl = ProgressLogger::builder()
+///     .with_sample_channel(move |elapsed, count| tx.send((elapsed, count)).is_ok())
+///     .start();
+/// # drop(rx);
+/// # pl.stop();
+/// ```
+pub trait SampleSender: Send {
+    /// Sends the sample without blocking, returning `false` once the
+    /// receiving side is gone, to stop sending samples
+    fn send_sample(&mut self, elapsed: Duration, count: u64) -> bool;
+}
+
+/// Drops the samples the consumer is not keeping up with
+impl SampleSender for SyncSender<(Duration, u64)> {
+    fn send_sample(&mut self, elapsed: Duration, count: u64) -> bool {
+        !matches!(
+            self.try_send((elapsed, count)),
+            Err(TrySendError::Disconnected(_))
+        )
+    }
+}
+
+impl<F: FnMut(Duration, u64) -> bool + Send> SampleSender for F {
+    fn send_sample(&mut self, elapsed: Duration, count: u64) -> bool {
+        self(elapsed, count)
+    }
+}
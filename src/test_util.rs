@@ -0,0 +1,119 @@
+//! Helpers for testing code that logs progress, with the `test-util`
+//! feature: a `log` backend capturing the messages of each thread, and a
+//! clock that tests advance instead of sleeping.
+//!
+//! ```
+//! use progress_logger::test_util::{advance, capture};
+//! use progress_logger::ProgressLogger;
+//! use std::time::Duration;
+//!
+//! # if cfg!(feature = "tracing") { return; }
+//! let records = capture(|| {
+//!     let mut pl = ProgressLogger::builder()
+//!         .with_items_name("rows")
+//!         .with_frequency(Duration::from_secs(10))
+//!         .with_memory_reporting(false)
+//!         .start();
+//!     advance(Duration::from_secs(11));
+//!     pl.update(1u32);
+//!     pl.stop();
+//! });
+//! assert_eq!(records.len(), 2);
+//! assert!(records[0].message.starts_with("11.0"));
+//! assert!(records[0].message.ends_with("s 1 rows (0.09 rows/s)"));
+//! assert!(records[1].message.starts_with("Done in 11.0"));
+//! ```
+
+use log::Level;
+use std::cell::RefCell;
+use std::sync::Once;
+use std::time::Duration;
+
+/// A message captured by `capture`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub level: Level,
+    pub target: String,
+    /// the message, without the escape sequences underlining the groups of
+    /// digits
+    pub message: String,
+}
+
+/// The `log` backend installed by `capture`, which keeps the messages of
+/// each thread apart, so that tests running in parallel don't see each
+/// other's messages
+struct Capture;
+
+thread_local! {
+    static RECORDS: RefCell<Vec<Record>> = const { RefCell::new(Vec::new()) };
+}
+
+impl log::Log for Capture {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let record = Record {
+            level: record.level(),
+            target: record.target().to_owned(),
+            message: strip_ansi(&record.args().to_string()),
+        };
+        RECORDS.with(|records| records.borrow_mut().push(record));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs `f`, returning the messages it logged on this thread, at all
+/// levels. The first call installs the capturing backend.
+///
+/// # Panics
+///
+/// If another `log` backend was installed before the first call.
+pub fn capture<F: FnOnce()>(f: F) -> Vec<Record> {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&Capture).expect("another log backend is installed");
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+    RECORDS.with(|records| records.borrow_mut().clear());
+    f();
+    RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+}
+
+/// Removes the escape sequences underlining the groups of digits, for the
+/// output of sinks
+pub fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Moves the clock of all the loggers of the process forward by
+/// `duration`: the next update sees the time passed, and reports if it is
+/// due. The threads of `with_stall_reports` and `with_strict_cadence`
+/// check the new time before `advance` returns, writing the reports due,
+/// if any. Other background threads, such as the one of
+/// `with_async_reports`, do not wait on the clock.
+pub fn advance(duration: Duration) {
+    crate::clock::advance(duration);
+}
+
+/// Moves the clock of all the loggers of the process back by `duration`,
+/// as a skewed clock would, see `with_clock_skew_detection`. The clock
+/// cannot go back further than where it started.
+pub fn rewind(duration: Duration) {
+    crate::clock::rewind(duration);
+}
@@ -0,0 +1,188 @@
+use crate::{sanitize, ProgressEvent, ProgressLogger, WarningKind};
+use crate::Level;
+use std::fs::{self, ReadDir};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// The files under a directory, depth first, without following symbolic
+/// links. Entries that cannot be read are skipped, and counted.
+struct Walker {
+    /// the directories being listed, from the root down
+    stack: Vec<ReadDir>,
+    errors: u64,
+    first_error: Option<io::Error>,
+}
+
+impl Walker {
+    fn new(root: &Path) -> Self {
+        let mut walker = Walker {
+            stack: Vec::new(),
+            errors: 0,
+            first_error: None,
+        };
+        match fs::read_dir(root) {
+            Ok(dir) => walker.stack.push(dir),
+            Err(e) => walker.error(e),
+        }
+        walker
+    }
+
+    fn error(&mut self, e: io::Error) {
+        self.errors += 1;
+        self.first_error.get_or_insert(e);
+    }
+}
+
+impl Iterator for Walker {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        while let Some(dir) = self.stack.last_mut() {
+            let entry = match dir.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => {
+                    self.error(e);
+                    continue;
+                }
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => match fs::read_dir(entry.path()) {
+                    Ok(dir) => self.stack.push(dir),
+                    Err(e) => self.error(e),
+                },
+                Ok(_) => return Some(entry.path()),
+                Err(e) => self.error(e),
+            }
+        }
+        None
+    }
+}
+
+/// The files under a directory, obtained with `ProgressLogger::walk_dir`
+/// once they have been counted. Iterating walks the directory again,
+/// counting each file as an update.
+///
+/// ```
+/// use progress_logger::ProgressLogger;
+///
+/// let root = std::env::temp_dir().join("progress-logger-walk-doc");
+/// std::fs::create_dir_all(root.join("a/b")).unwrap();
+/// for path in &["x", "a/y", "a/b/z"] {
+///     std::fs::write(root.join(path), b"data").unwrap();
+/// }
+///
+/// let mut pl = ProgressLogger::builder().with_items_name("files").start();
+/// // Logs `Discovered 3 files under /tmp/progress-logger-walk-doc in ...`
+/// let files = pl.walk_dir(&root);
+/// assert_eq!((files.discovered(), files.errors()), (3, 0));
+/// let bytes: u64 = files
+///     .map(|path| std::fs::metadata(path).unwrap().len())
+///     .sum();
+/// assert_eq!(bytes, 12);
+/// assert_eq!(pl.snapshot().count, 3);
+/// pl.stop();
+/// # std::fs::remove_dir_all(&root).unwrap();
+/// ```
+pub struct DirWalk<'a> {
+    pl: &'a mut ProgressLogger,
+    walker: Walker,
+    discovered: u64,
+    errors: u64,
+}
+
+impl DirWalk<'_> {
+    /// The number of files found by the first walk
+    pub fn discovered(&self) -> u64 {
+        self.discovered
+    }
+
+    /// The number of entries the first walk could not read, e.g. because
+    /// of missing permissions
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+}
+
+impl Iterator for DirWalk<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        let path = self.walker.next()?;
+        self.pl.update(1u32);
+        Some(path)
+    }
+}
+
+/// The number of files between two looks at the clock while discovering
+const DISCOVERY_CHECK_EVERY: u64 = 4096;
+
+impl ProgressLogger {
+    /// Counts the files under `root`, as a phase named `discover`, then
+    /// returns them, to be processed in a phase named `process` with the
+    /// expected number of updates increased by the files found, see
+    /// `start_phase`. Discovery is reported with notes, and concluded by a
+    /// line like `Discovered 1.20M files under /data in 3.20s`, which is a
+    /// warning if some entries could not be read, with the number of them
+    /// and the first error. Such entries are skipped, whatever the error.
+    ///
+    /// Symbolic links are not followed. Files created or deleted between
+    /// the two walks make the count differ from the expectation.
+    pub fn walk_dir<P: AsRef<Path>>(&mut self, root: P) -> DirWalk<'_> {
+        let root = root.as_ref();
+        self.start_phase("discover");
+        let start = Instant::now();
+        let mut walker = Walker::new(root);
+        let mut discovered = 0u64;
+        for _ in &mut walker {
+            discovered += 1;
+            if discovered.is_multiple_of(DISCOVERY_CHECK_EVERY) {
+                self.note(format!(
+                    "Discovered {} {} so far",
+                    self.number_format.int(discovered),
+                    self.items
+                ));
+            }
+        }
+        let mut line = format!(
+            "Discovered {} {} under {} in {:.2?}",
+            self.number_format.int(discovered),
+            self.items,
+            sanitize(&root.display().to_string()),
+            start.elapsed()
+        );
+        let (level, event) = match walker.first_error.as_ref() {
+            Some(e) => {
+                line.push_str(&format!(
+                    ", {} entries could not be read (first: {})",
+                    self.number_format.int(walker.errors),
+                    sanitize(&e.to_string())
+                ));
+                (
+                    Level::Warn,
+                    ProgressEvent::Warning {
+                        kind: WarningKind::UnreadableEntries,
+                        line,
+                    },
+                )
+            }
+            None => (self.level, ProgressEvent::Notice { line }),
+        };
+        let len = event.line().map_or(0, str::len);
+        if self.is_enabled() && self.charge_output(len, true) {
+            self.publish(level, event);
+        }
+        self.expected_updates = Some(self.count + discovered);
+        self.start_phase("process");
+        DirWalk {
+            pl: self,
+            walker: Walker::new(root),
+            discovered,
+            errors: walker.errors,
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::background::{PendingReport, Renderer};
+use crate::clock;
+use crate::is_globally_enabled;
+use std::fmt::Write as _;
+#[cfg(feature = "test-util")]
+use std::sync::Weak;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// What the logger last told the watchdog, at its clock checks and reports
+struct State {
+    /// the time of the last clock check
+    seen_at: Instant,
+    /// the active elapsed time and the count at `seen_at`
+    elapsed: Duration,
+    count: u64,
+    frequency: Duration,
+    /// the time of the last periodic report, by the thread, or by the
+    /// logger in strict mode
+    reported_at: Instant,
+    /// the reports of stalls since the logger last asked, see
+    /// `take_stall_reports`
+    stall_reports: u64,
+    /// paused or finished: nothing is expected
+    idle: bool,
+    stopped: bool,
+    /// the test clock moved since the last check, see `clock_advanced`
+    #[cfg(feature = "test-util")]
+    advanced: bool,
+    /// the time of the last check of the thread
+    #[cfg(feature = "test-util")]
+    checked_at: Instant,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+    /// notified after each check of the thread
+    #[cfg(feature = "test-util")]
+    checked: Condvar,
+}
+
+/// The watchdogs of the process, which `clock_advanced` waits for
+#[cfg(feature = "test-util")]
+static WATCHDOGS: Mutex<Vec<Weak<Shared>>> = Mutex::new(Vec::new());
+
+/// Lets the thread of every watchdog check the test clock just advanced,
+/// and waits for the checks, reports included, so that tests see the
+/// reports due at the new time as soon as `test_util::advance` returns
+#[cfg(feature = "test-util")]
+pub(crate) fn clock_advanced() {
+    let now = clock::now();
+    let watchdogs: Vec<Arc<Shared>> = {
+        let mut watchdogs = WATCHDOGS.lock().unwrap_or_else(|e| e.into_inner());
+        watchdogs.retain(|shared| shared.strong_count() > 0);
+        watchdogs.iter().filter_map(Weak::upgrade).collect()
+    };
+    for shared in watchdogs {
+        let mut state = shared.lock();
+        state.advanced = true;
+        shared.wake.notify_one();
+        while !state.stopped && state.checked_at < now {
+            state = shared.checked.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reports from a dedicated thread when the logger has not checked the
+/// clock for a whole frequency, see `with_stall_reports`, or, in strict
+/// mode, when a report is due and the logger has not reported, see
+/// `with_strict_cadence`. The logger only talks to the watchdog from its
+/// clock checks and reports, never from the counting path of `update`.
+pub(crate) struct Watchdog {
+    shared: Arc<Shared>,
+    strict: bool,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn(
+        renderer: Renderer,
+        frequency: Duration,
+        ignore_global_switch: bool,
+        strict: bool,
+    ) -> Self {
+        let now = clock::now();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                seen_at: now,
+                elapsed: Duration::from_secs(0),
+                count: 0,
+                frequency,
+                reported_at: now,
+                stall_reports: 0,
+                idle: false,
+                stopped: false,
+                #[cfg(feature = "test-util")]
+                advanced: false,
+                #[cfg(feature = "test-util")]
+                checked_at: now,
+            }),
+            wake: Condvar::new(),
+            #[cfg(feature = "test-util")]
+            checked: Condvar::new(),
+        });
+        #[cfg(feature = "test-util")]
+        WATCHDOGS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::downgrade(&shared));
+        let thread = {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name("progress-watchdog".to_owned())
+                .spawn(move || watch(&shared, renderer, ignore_global_switch, strict))
+                .expect("failed to spawn the thread of the stall reports")
+        };
+        Self {
+            shared,
+            strict,
+            thread: Some(thread),
+        }
+    }
+
+    /// Records a clock check of the logger
+    pub(crate) fn observe(&self, now: Instant, elapsed: Duration, count: u64, frequency: Duration) {
+        let mut state = self.shared.lock();
+        state.seen_at = now;
+        state.elapsed = elapsed;
+        state.count = count;
+        state.frequency = frequency;
+    }
+
+    /// Stops or resumes the reports, when the logger is paused or finished
+    pub(crate) fn set_idle(&self, idle: bool) {
+        self.shared.lock().idle = idle;
+    }
+
+    /// The number of reports of stalls since the last call, for the
+    /// heartbeat string of the logger
+    pub(crate) fn take_stall_reports(&self) -> u64 {
+        std::mem::take(&mut self.shared.lock().stall_reports)
+    }
+
+    /// Called by the logger about to report at `now`, having last reported
+    /// at `last_logged`. In strict mode, returns the time of the report of
+    /// the thread if it reported since, in which case the logger does not,
+    /// so that no report is emitted twice.
+    pub(crate) fn claim_report(&self, now: Instant, last_logged: Instant) -> Option<Instant> {
+        if !self.strict {
+            return None;
+        }
+        let mut state = self.shared.lock();
+        if state.reported_at > last_logged {
+            return Some(state.reported_at);
+        }
+        state.reported_at = now;
+        None
+    }
+}
+
+fn watch(shared: &Shared, mut renderer: Renderer, ignore_global_switch: bool, strict: bool) {
+    let mut state = shared.lock();
+    loop {
+        #[cfg(feature = "test-util")]
+        let advanced = std::mem::take(&mut state.advanced);
+        #[cfg(not(feature = "test-util"))]
+        let advanced = false;
+        if !advanced {
+            let timeout = if strict && !state.idle {
+                (state.reported_at + state.frequency).saturating_duration_since(clock::now())
+            } else {
+                state.frequency
+            };
+            state = shared
+                .wake
+                .wait_timeout(state, timeout)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        if state.stopped {
+            return;
+        }
+        let now = clock::now();
+        state = check(shared, state, now, &mut renderer, ignore_global_switch, strict);
+        #[cfg(feature = "test-util")]
+        {
+            state.checked_at = now;
+            shared.checked.notify_all();
+        }
+    }
+}
+
+/// Reports at `now` if due, returning the state locked again
+fn check<'a>(
+    shared: &'a Shared,
+    mut state: MutexGuard<'a, State>,
+    now: Instant,
+    renderer: &mut Renderer,
+    ignore_global_switch: bool,
+    strict: bool,
+) -> MutexGuard<'a, State> {
+    let stalled_for = now.saturating_duration_since(state.seen_at);
+    // At most one report per frequency, however often the thread wakes up
+    let due = now.saturating_duration_since(state.reported_at) >= state.frequency
+        && (strict || stalled_for >= state.frequency);
+    if state.idle || !due {
+        return state;
+    }
+    state.reported_at = now;
+    if !ignore_global_switch && !is_globally_enabled() {
+        return state;
+    }
+    let elapsed = state.elapsed + stalled_for;
+    let report = PendingReport {
+        elapsed,
+        count: state.count,
+        throughput: state.count as f64 / elapsed.as_secs_f64(),
+        prediction: None,
+        prediction_basis: None,
+        label: None,
+    };
+    let stalled = stalled_for >= state.frequency;
+    if stalled {
+        state.stall_reports += 1;
+    }
+    // Render and write without blocking the clock checks of the logger
+    drop(state);
+    let mut line = renderer.render(&report, 0);
+    if stalled {
+        write!(
+            line,
+            ", stalled for {}",
+            renderer.duration_format.pretty(stalled_for)
+        )
+        .unwrap();
+    }
+    renderer.output(&line);
+    shared.lock()
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shared.lock().stopped = true;
+        self.shared.wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            // A panic of the thread only loses reports
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! The timing of the reports for different patterns of updates, driven by
+//! the test clock, which the thread of strict cadence checks at every step.
+#![cfg(feature = "std")]
+
+use progress_logger::test_util::advance;
+use progress_logger::{ProgressLogger, SharedSink};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const FREQUENCY: Duration = Duration::from_secs(100);
+/// How far the clock moves at each step
+const STEP: Duration = Duration::from_secs(1);
+/// The duration of a run, in steps
+const STEPS: u64 = 1000;
+
+/// Records the step at which each line is written
+#[derive(Clone, Default)]
+struct Steps {
+    now: Arc<AtomicU64>,
+    written: Arc<Mutex<Vec<u64>>>,
+}
+
+impl std::io::Write for Steps {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let now = self.now.load(Ordering::SeqCst);
+        let lines = buf.iter().filter(|&&b| b == b'\n').count();
+        self.written
+            .lock()
+            .unwrap()
+            .extend(std::iter::repeat_n(now, lines));
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The intervals between the periodic reports of a logger updated by
+/// `pattern` at each step, in steps
+fn intervals(strict: bool, pattern: fn(u64, &mut ProgressLogger)) -> Vec<u64> {
+    let steps = Steps::default();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(FREQUENCY)
+        .with_min_report_spacing(Duration::from_millis(0))
+        .with_memory_reporting(false)
+        .with_strict_cadence(strict)
+        .with_sink(SharedSink::new(steps.clone()))
+        .start();
+    for step in 1..=STEPS {
+        steps.now.store(step, Ordering::SeqCst);
+        advance(STEP);
+        pattern(step, &mut pl);
+    }
+    // Leave the completion statement out
+    let written = steps.written.lock().unwrap().clone();
+    pl.finish_silently();
+    assert!(written.len() >= 4, "{} reports", written.len());
+    written.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// An update every 7s, so that reports are due between two updates
+fn sparse(step: u64, pl: &mut ProgressLogger) {
+    if step.is_multiple_of(7) {
+        pl.update(1u32);
+    }
+}
+
+/// Bursts of updates every 36s
+fn bursty(step: u64, pl: &mut ProgressLogger) {
+    if step.is_multiple_of(36) {
+        for _ in 0..100_000 {
+            pl.update(1u32);
+        }
+    }
+}
+
+/// A thousand updates per second
+fn dense(_: u64, pl: &mut ProgressLogger) {
+    for _ in 0..1000 {
+        pl.update(1u32);
+    }
+}
+
+#[test]
+fn sparse_updates_delay_reports_unless_strict() {
+    let intervals = intervals(false, sparse);
+    assert!(intervals.iter().all(|&i| i >= 100 && i.is_multiple_of(7)), "{:?}", intervals);
+    assert!(intervals.iter().any(|&i| i > 100), "{:?}", intervals);
+    let intervals = self::intervals(true, sparse);
+    assert!(intervals.iter().all(|&i| i == 100), "{:?}", intervals);
+}
+
+#[test]
+fn bursty_updates_delay_reports_unless_strict() {
+    let intervals = intervals(false, bursty);
+    assert!(intervals.iter().all(|&i| i == 108), "{:?}", intervals);
+    let intervals = self::intervals(true, bursty);
+    assert!(intervals.iter().all(|&i| i == 100), "{:?}", intervals);
+}
+
+#[test]
+fn dense_updates_keep_the_cadence_in_both_modes() {
+    // Without the thread, the clock checks are at most 4096 updates apart
+    let intervals = intervals(false, dense);
+    assert!(intervals.iter().all(|&i| (100..=105).contains(&i)), "{:?}", intervals);
+    let intervals = self::intervals(true, dense);
+    assert!(intervals.iter().all(|&i| i == 100), "{:?}", intervals);
+}
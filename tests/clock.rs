@@ -0,0 +1,204 @@
+//! The reports as the clock advances, driven by the test clock: tests
+//! sleeping on the real clock belong to another binary.
+#![cfg(all(feature = "std", not(feature = "tracing")))]
+
+use progress_logger::test_util::{advance, capture, rewind};
+use progress_logger::{DigitGrouping, MemorySink, ProgressLogger, SharedSink};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+const FREQUENCY: Duration = Duration::from_millis(160);
+
+/// Held by each test, as the test clock is shared by the whole process
+fn exclusive_clock() -> MutexGuard<'static, ()> {
+    static CLOCK: Mutex<()> = Mutex::new(());
+    CLOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Updates a logger at the rate of one update per gap of each phase, for
+/// the duration of the phase, returning the time of each report, in
+/// microseconds since the start
+fn report_times(phases: &[(Duration, Duration)]) -> Vec<u64> {
+    let now = Arc::new(AtomicU64::new(0));
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let mut pl = {
+        let now = Arc::clone(&now);
+        let reports = Arc::clone(&reports);
+        ProgressLogger::builder()
+            .with_frequency(FREQUENCY)
+            .with_min_report_spacing(Duration::from_millis(0))
+            .with_memory_reporting(false)
+            .with_on_log(move |_, _, _| {
+                reports.lock().unwrap().push(now.load(Ordering::SeqCst));
+            })
+            .start()
+    };
+    capture(|| {
+        for &(gap, length) in phases {
+            for _ in 0..(length.as_nanos() / gap.as_nanos()) {
+                advance(gap);
+                now.fetch_add(gap.as_micros() as u64, Ordering::SeqCst);
+                pl.update(1u32);
+            }
+        }
+    });
+    // Leave the call of `finish` out
+    let periodic = reports.lock().unwrap().clone();
+    pl.finish_silently();
+    periodic
+}
+
+/// The intervals between reports, in fractions of the frequency
+fn intervals(reports: &[u64]) -> Vec<f64> {
+    reports
+        .windows(2)
+        .map(|w| (w[1] - w[0]) as f64 / FREQUENCY.as_micros() as f64)
+        .collect()
+}
+
+#[test]
+fn reports_are_late_by_a_sixteenth_of_the_frequency_at_most() {
+    let _clock = exclusive_clock();
+    let reports = report_times(&[(Duration::from_micros(10), Duration::from_secs(2))]);
+    let intervals = intervals(&reports);
+    assert!(intervals.len() >= 10, "{:?}", intervals);
+    // Some slack for the real time passing along with the test clock
+    assert!(
+        intervals.iter().all(|&i| (0.95..=1.0 + 1.0 / 16.0 + 0.01).contains(&i)),
+        "{:?}",
+        intervals
+    );
+}
+
+#[test]
+fn a_rate_dropping_k_times_delays_one_report_by_k_sixteenths_at_most() {
+    let _clock = exclusive_clock();
+    const K: u32 = 32;
+    let gap = Duration::from_micros(10);
+    let reports = report_times(&[
+        (gap, Duration::from_secs(1)),
+        (gap * K, Duration::from_secs(2)),
+    ]);
+    let drop = Duration::from_secs(1).as_micros() as u64;
+    let late = |interval: f64| interval - 1.0;
+    let before: Vec<u64> = reports.iter().copied().filter(|&r| r <= drop).collect();
+    let after: Vec<u64> = reports.iter().copied().filter(|&r| r > drop).collect();
+    let across = intervals(&[*before.last().unwrap(), after[0]])[0];
+    assert!(late(across) <= K as f64 / 16.0 + 0.1, "{}", across);
+    // Then the clock checks fit the new rate
+    let intervals = intervals(&after);
+    assert!(intervals.len() >= 5, "{:?}", intervals);
+    assert!(
+        intervals.iter().all(|&i| late(i) <= 1.0 / 16.0 + 0.01),
+        "{:?}",
+        intervals
+    );
+}
+
+#[test]
+fn clock_skews_warn_once_then_at_most_once_per_threshold() {
+    let _clock = exclusive_clock();
+    let lines = MemorySink::new();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(FREQUENCY)
+        .with_min_report_spacing(Duration::from_millis(0))
+        .with_memory_reporting(false)
+        .with_clock_skew_detection(Duration::from_secs(1))
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    let warnings = |lines: &MemorySink| -> Vec<String> {
+        lines
+            .lines()
+            .into_iter()
+            .filter(|line| line.starts_with("Clock skew detected"))
+            .collect()
+    };
+    // Updates large enough to reach a clock check each
+    let update = |pl: &mut ProgressLogger| pl.update(1_000_000u32);
+    advance(FREQUENCY * 2);
+    update(&mut pl);
+    assert_eq!(pl.clock_skew_events(), 0);
+    // Back to before the last report
+    rewind(Duration::from_millis(5));
+    update(&mut pl);
+    assert_eq!(pl.clock_skew_events(), 1);
+    assert_eq!(
+        warnings(&lines),
+        ["Clock skew detected; throughput calculations may be momentarily inaccurate."]
+    );
+    // Closer than the threshold to the warning: only counted
+    advance(Duration::from_millis(1));
+    update(&mut pl);
+    rewind(Duration::from_millis(2));
+    update(&mut pl);
+    assert_eq!(pl.clock_skew_events(), 3);
+    assert_eq!(warnings(&lines).len(), 1);
+    // Past the threshold, a reminder with the count
+    advance(Duration::from_secs(2));
+    update(&mut pl);
+    rewind(Duration::from_millis(5));
+    update(&mut pl);
+    assert_eq!(pl.clock_skew_events(), 4);
+    let warnings = warnings(&lines);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[1].starts_with("Clock skew detected 4 times so far"), "{}", warnings[1]);
+    pl.finish_silently();
+}
+
+#[test]
+fn reports_show_the_work_unit_throughput_and_the_average_per_item() {
+    let _clock = exclusive_clock();
+    let lines = MemorySink::new();
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("nodes")
+        .with_frequency(Duration::from_secs(3600))
+        .with_memory_reporting(false)
+        .with_digit_grouping(DigitGrouping::Plain)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    advance(Duration::from_secs(10));
+    pl.update_weighted(100, 2500.0);
+    pl.checkpoint();
+    advance(Duration::from_secs(10));
+    pl.update_weighted(300, 1500.0);
+    pl.checkpoint();
+    let lines = lines.lines();
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    assert!(
+        lines[0].contains(
+            " 100 nodes (2500.00 work units, avg 25.00 per item, last interval 25.00 per item) \
+             (10.00 nodes/s, 250.00 work units/s)"
+        ),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[1].contains(
+            " 400 nodes (4000.00 work units, avg 10.00 per item, last interval 5.00 per item) \
+             (20.00 nodes/s, 200.00 work units/s)"
+        ),
+        "{}",
+        lines[1]
+    );
+    let throughput = pl.work_unit_throughput().unwrap();
+    assert!((throughput - 200.0).abs() < 0.1, "{}", throughput);
+    pl.finish_silently();
+}
+
+#[test]
+fn a_run_too_long_for_a_duration_saturates() {
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_expected_updates(u64::MAX)
+        .with_target_report_count(10)
+        .with_memory_reporting(false)
+        .with_sink(SharedSink::new(MemorySink::new()))
+        .start();
+    // 0.1 updates/s, for a run of about 5.8e12 years
+    advance(Duration::from_secs(11));
+    pl.update(1u32);
+    assert_eq!(pl.frequency(), Duration::MAX / 10);
+    assert_eq!(pl.time_to_completion(), Some(Duration::MAX));
+    pl.finish_silently();
+}
@@ -0,0 +1,7 @@
+//! The misuses of the API caught at compile time, with the errors they give.
+#![cfg(feature = "std")]
+
+#[test]
+fn ui() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,224 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{
+    AfterFinish, ConcurrentProgressLogger, MemorySink, ProgressLogger, SharedSink,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn completion_statements(lines: &MemorySink) -> Vec<String> {
+    lines
+        .lines()
+        .into_iter()
+        .filter(|line| line.starts_with("Done"))
+        .collect()
+}
+
+#[test]
+fn reports_the_aggregate_count_once_all_handles_are_dropped() {
+    let lines = MemorySink::new();
+    let pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(1))
+        .with_min_report_spacing(Duration::from_secs(0))
+        .with_compact_numbers(true)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start_concurrent();
+    let workers: Vec<_> = (0..8)
+        .map(|_| {
+            let pl = pl.clone();
+            std::thread::spawn(move || {
+                for _ in 0..250_000 {
+                    pl.update_light(1u32);
+                }
+            })
+        })
+        .collect();
+    drop(pl);
+    assert!(completion_statements(&lines).is_empty());
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let done = completion_statements(&lines);
+    assert_eq!(done.len(), 1);
+    assert!(done[0].contains(" 2.00M updates "), "{}", done[0]);
+}
+
+#[test]
+fn stop_logs_the_completion_statement_once() {
+    let lines = MemorySink::new();
+    let pl = ProgressLogger::builder()
+        .with_sink(SharedSink::new(lines.clone()))
+        .start_concurrent();
+    let other = pl.clone();
+    pl.update(10u32);
+    assert_eq!(pl.stop().total_items, 10);
+    drop(other);
+    assert_eq!(completion_statements(&lines).len(), 1);
+}
+
+/// A stopped logger and a handle left to update it after the completion
+/// statement, with the given policy
+fn stopped(policy: AfterFinish, lines: &MemorySink) -> ConcurrentProgressLogger {
+    let pl = ProgressLogger::builder()
+        .with_after_finish(policy)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start_concurrent();
+    let other = pl.clone();
+    pl.update(10u32);
+    pl.stop();
+    other
+}
+
+#[test]
+fn updates_after_stop_are_ignored_with_a_warning() {
+    let lines = MemorySink::new();
+    let other = stopped(AfterFinish::Ignore, &lines);
+    other.update(5u32);
+    other.update(5u32);
+    assert_eq!(other.count(), 10);
+    let warnings: Vec<String> = lines
+        .lines()
+        .into_iter()
+        .filter(|line| line.starts_with("Ignoring updates"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{:?}", lines.lines());
+    assert_eq!(other.stop().total_items, 10);
+}
+
+#[test]
+fn stopping_while_threads_update_warns_once() {
+    let lines = MemorySink::new();
+    let pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(1))
+        .with_min_report_spacing(Duration::from_secs(0))
+        .with_sink(SharedSink::new(lines.clone()))
+        .start_concurrent();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let workers: Vec<_> = (0..8)
+        .map(|_| {
+            let pl = pl.clone();
+            let stopped = Arc::clone(&stopped);
+            std::thread::spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    pl.update(1u32);
+                }
+                // Some more after the completion statement
+                for _ in 0..10_000 {
+                    pl.update(1u32);
+                }
+            })
+        })
+        .collect();
+    std::thread::sleep(Duration::from_millis(20));
+    let stopper = {
+        let pl = pl.clone();
+        let stopped = Arc::clone(&stopped);
+        std::thread::spawn(move || {
+            let report = pl.stop();
+            stopped.store(true, Ordering::Relaxed);
+            report
+        })
+    };
+    let report = stopper.join().unwrap();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert!(report.total_items > 0);
+    assert_eq!(completion_statements(&lines).len(), 1, "{:?}", lines.lines());
+    let warnings: Vec<String> = lines
+        .lines()
+        .into_iter()
+        .filter(|line| line.starts_with("Ignoring updates"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    // The last handle stops nothing more
+    assert_eq!(pl.stop().total_items, report.total_items);
+    assert_eq!(completion_statements(&lines).len(), 1);
+}
+
+#[test]
+fn updates_after_stop_can_be_counted_silently() {
+    let lines = MemorySink::new();
+    let other = stopped(AfterFinish::CountSilently, &lines);
+    let before = lines.lines().len();
+    other.update(5u32);
+    assert_eq!(other.count(), 15);
+    assert_eq!(lines.lines().len(), before);
+    assert_eq!(other.stop().total_items, 15);
+}
+
+#[test]
+fn updates_after_stop_can_panic() {
+    let lines = MemorySink::new();
+    let other = stopped(AfterFinish::Panic, &lines);
+    let panicked = std::panic::catch_unwind(|| other.update(5u32));
+    let message = panicked.unwrap_err();
+    let message = message.downcast_ref::<String>().unwrap();
+    assert!(message.contains("after the completion statement"), "{}", message);
+    assert_eq!(other.count(), 10);
+}
+
+#[test]
+fn a_logger_can_move_between_threads() {
+    // Debug builds check that no two threads update a logger at once, not
+    // that a single thread does
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(0))
+        .with_min_report_spacing(Duration::from_secs(0))
+        .with_sink(SharedSink::new(std::io::sink()))
+        .start();
+    for _ in 0..4 {
+        pl = std::thread::spawn(move || {
+            for _ in 0..1000 {
+                pl.update(1u32);
+            }
+            pl
+        })
+        .join()
+        .unwrap();
+        pl.update(1u32);
+    }
+    assert_eq!(pl.stop().total_items, 4004);
+}
+
+/// A sink taking its time to write, holding the logger meanwhile
+struct Slow;
+
+impl std::io::Write for Slow {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::thread::sleep(Duration::from_millis(2));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn waits_for_a_logger_held_by_a_slow_sink_are_measured() {
+    let pl = ProgressLogger::builder()
+        .with_lock_wait_warning(None)
+        .with_sink(SharedSink::new(Slow))
+        .start_concurrent();
+    let threads = 4;
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let pl = pl.clone();
+            std::thread::spawn(move || {
+                for _ in 0..20 {
+                    pl.update(1u32);
+                    pl.report_now();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let snapshot = pl.snapshot();
+    assert!(snapshot.lock_wait > Duration::from_secs(0));
+    assert!(snapshot.lock_wait <= snapshot.elapsed * threads, "{:?}", snapshot);
+    pl.stop();
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{
+    AfterFinish, DigitGrouping, EtaEstimator, Level, MemorySink, ProgressLogger, ResolvedConfig,
+    SharedSink, Unit,
+};
+use serde_json::Value;
+use std::time::Duration;
+
+fn level(name: &str) -> Level {
+    match name {
+        "Error" => Level::Error,
+        "Warn" => Level::Warn,
+        "Info" => Level::Info,
+        "Debug" => Level::Debug,
+        "Trace" => Level::Trace,
+        _ => panic!("unknown level {}", name),
+    }
+}
+
+fn secs(value: &Value) -> Duration {
+    Duration::from_secs_f64(value.as_f64().unwrap())
+}
+
+/// Rebuilds a configuration from its JSON rendering, with the enums
+/// compared through their names
+fn from_json(json: &str, like: &ResolvedConfig) -> ResolvedConfig {
+    let value: Value = serde_json::from_str(json).unwrap();
+    let object = value.as_object().unwrap();
+    let name = |key: &str| object[key].as_str().unwrap().to_owned();
+    let named = |key: &str, expected: String| {
+        assert_eq!(name(key), expected, "{}", key);
+    };
+    named("unit", format!("{:?}", like.unit));
+    named("eta_estimator", format!("{:?}", like.eta_estimator));
+    named("display", format!("{:?}", like.display));
+    named("after_finish", format!("{:?}", like.after_finish));
+    assert_eq!(
+        object["digit_grouping"].as_str(),
+        like.digit_grouping.map(|g| format!("{:?}", g)).as_deref()
+    );
+    ResolvedConfig {
+        items: name("items"),
+        frequency: secs(&object["frequency_secs"]),
+        level: level(&name("level")),
+        log_target: name("log_target"),
+        completion_level: level(&name("completion_level")),
+        expected_updates: object["expected_updates"].as_u64(),
+        expected_work_units: object["expected_work_units"].as_f64(),
+        milestones: object["milestones"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m.as_u64().unwrap())
+            .collect(),
+        min_report_spacing: secs(&object["min_report_spacing_secs"]),
+        output_budget: object["output_budget"].as_u64(),
+        stall_warning_fraction: object["stall_warning_fraction"].as_f64(),
+        clock_skew_threshold: object["clock_skew_threshold_secs"]
+            .as_f64()
+            .map(Duration::from_secs_f64),
+        compact_numbers: object["compact_numbers"].as_bool().unwrap(),
+        digit_grouping: like.digit_grouping,
+        unit: like.unit,
+        eta_estimator: like.eta_estimator,
+        display: like.display,
+        memory_reporting: object["memory_reporting"].as_bool().unwrap(),
+        cpu_reporting: object["cpu_reporting"].as_bool().unwrap(),
+        sink: object["sink"].as_str().map(str::to_owned),
+        after_finish: like.after_finish,
+        version: progress_logger::VERSION,
+    }
+}
+
+#[test]
+fn the_config_of_the_report_round_trips_through_json() {
+    let mut pl = ProgressLogger::builder()
+        .with_items_name("rows \"quoted\"")
+        .with_frequency(Duration::from_millis(250))
+        .with_log_level(Level::Debug)
+        .with_completion_level(Level::Warn)
+        .with_log_target("my_app::progress")
+        .with_expected_updates(1000u32)
+        .with_milestones(&[0.25, 0.5])
+        .with_output_budget(4096)
+        .with_stall_warning_fraction(0.5)
+        .with_clock_skew_detection(Duration::from_millis(5))
+        .with_digit_grouping(DigitGrouping::Comma)
+        .with_items_unit(Unit::BinaryBytes)
+        .with_eta_estimator(EtaEstimator::ExponentialMovingAverage { alpha: 0.25 })
+        .with_memory_reporting(false)
+        .with_after_finish(AfterFinish::CountSilently)
+        .with_sink(SharedSink::new(MemorySink::new()))
+        .start();
+    pl.update(10u32);
+    let report = pl.stop();
+    let config = &report.config;
+    assert_eq!(config.level, Level::Debug);
+    assert_eq!(config.completion_level, Level::Warn);
+    assert_eq!(config.milestones, vec![250, 500]);
+    assert_eq!(config.digit_grouping, Some(DigitGrouping::Comma));
+    assert_eq!(config.sink.as_deref(), Some("sink"));
+    assert_eq!(&from_json(&config.to_json(), config), config);
+}
+
+#[test]
+fn the_defaults_round_trip_through_json() {
+    let pl = ProgressLogger::builder().start();
+    let config = pl.config().clone();
+    assert_eq!(from_json(&config.to_json(), &config), config);
+    assert_eq!(pl.finish_silently().config, config);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "std")]
+
+use progress_logger::ThroughputCurve;
+use std::time::Duration;
+
+/// A run of `n` observations `step` apart, with counts growing unevenly
+fn history(n: u64, step: Duration) -> Vec<(Duration, u64)> {
+    let mut count = 0;
+    (0..n)
+        .map(|i| {
+            count += i % 7 + (i / 1000) % 3;
+            (step * i as u32, count)
+        })
+        .collect()
+}
+
+/// Checks that the curve of `raw` starts and ends with it, is made of its
+/// observations, and covers it evenly
+fn check(raw: &[(Duration, u64)]) {
+    let mut curve = ThroughputCurve::new();
+    for &(elapsed, count) in raw {
+        curve.observe(elapsed, count);
+    }
+    let points = curve.points();
+    assert!(points.len() <= 257, "{} points", points.len());
+    assert_eq!(points.first(), raw.first());
+    assert_eq!(points.last(), raw.last());
+    let mut rest = raw.iter();
+    for point in points {
+        assert!(rest.any(|raw| raw == point), "{:?} not observed", point);
+    }
+    let span = raw.last().unwrap().0 - raw[0].0;
+    if span > Duration::from_secs(1) && raw.len() > 1000 {
+        assert!(points.len() > 128, "{} points", points.len());
+        assert!(curve.resolution() <= span / 128, "{:?}", curve.resolution());
+        for pair in points.windows(2) {
+            assert!(pair[1].0 - pair[0].0 <= curve.resolution() * 2);
+        }
+    }
+}
+
+#[test]
+fn keeps_the_endpoints_of_runs_of_any_length() {
+    check(&history(1, Duration::from_millis(1)));
+    check(&history(2, Duration::from_nanos(1)));
+    check(&history(300, Duration::from_micros(3)));
+    check(&history(10_000, Duration::from_millis(7)));
+    check(&history(1_000_000, Duration::from_millis(250)));
+    // A year-long run checked every 10 seconds
+    check(&history(3_153_600, Duration::from_secs(10)));
+}
+
+#[test]
+fn ignores_observations_out_of_order() {
+    let mut curve = ThroughputCurve::new();
+    curve.observe(Duration::from_secs(2), 20);
+    curve.observe(Duration::from_secs(1), 10);
+    curve.observe(Duration::from_secs(3), 30);
+    assert_eq!(
+        curve.points(),
+        [(Duration::from_secs(2), 20), (Duration::from_secs(3), 30)]
+    );
+}
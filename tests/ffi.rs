@@ -0,0 +1,70 @@
+//! The C bindings: the header matches `src/ffi.rs`, and `ffi/test.c` runs
+//! against the static library.
+#![cfg(feature = "std")]
+
+use std::path::Path;
+#[cfg(all(feature = "ffi", unix))]
+use std::process::Command;
+
+/// The header generated by cbindgen from the current sources
+fn generated_header() -> String {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let config = cbindgen::Config::from_file(Path::new(root).join("cbindgen.toml")).unwrap();
+    let mut header = Vec::new();
+    cbindgen::Builder::new()
+        .with_src(Path::new(root).join("src/ffi.rs"))
+        .with_config(config)
+        .generate()
+        .expect("cbindgen failed")
+        .write(&mut header);
+    String::from_utf8(header).unwrap()
+}
+
+#[test]
+fn the_header_matches_the_bindings() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("ffi/progress_logger.h");
+    let generated = generated_header();
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+    let checked_in = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        checked_in == generated,
+        "ffi/progress_logger.h is out of date, rewrite it with `BLESS=1 cargo test --test ffi`:\n{}",
+        generated
+    );
+}
+
+#[cfg(all(feature = "ffi", unix))]
+#[test]
+fn the_c_program_runs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // A target directory of its own, not to wait for the one of the tests
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let built = Command::new(env!("CARGO"))
+        .args(["rustc", "--lib", "--no-default-features", "--features", "ffi"])
+        .args(["--crate-type", "staticlib", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target)
+        .status()
+        .unwrap();
+    assert!(built.success(), "building the static library failed");
+    let program = target.join("ffi-test");
+    let compiled = Command::new(std::env::var_os("CC").unwrap_or_else(|| "cc".into()))
+        .arg(root.join("ffi/test.c"))
+        .arg("-I")
+        .arg(root.join("ffi"))
+        .arg(target.join("debug/libprogress_logger.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(compiled.success(), "compiling ffi/test.c failed");
+    let output = Command::new(&program).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    let done: Vec<&str> = stderr.lines().filter(|line| line.contains("Done in")).collect();
+    assert_eq!(done.len(), 1, "{}", stderr);
+    assert!(done[0].contains(" 10000000 items "), "{}", done[0]);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{Progress, ProgressLogger, SharedSink};
+use std::time::Duration;
+
+/// The bound documented by `estimated_memory_footprint`, with room for the
+/// size of the logger itself and its configuration
+const BOUND: usize = 512 * 1024;
+
+/// A logger with all the state that grows with the length of the run
+fn logger() -> ProgressLogger {
+    ProgressLogger::builder()
+        .with_items_name("intervals")
+        .with_frequency(Duration::from_millis(0))
+        .with_min_report_spacing(Duration::from_millis(0))
+        .with_collect_samples(true)
+        .with_heartbeat_string(true)
+        .with_throughput_window(Duration::from_secs(3600))
+        .with_memory_reporting(false)
+        .with_grid(u64::MAX, 1)
+        .with_sink(SharedSink::new(std::io::sink()))
+        .start()
+}
+
+/// Runs `intervals` report intervals, each in its own phase and outer
+/// iteration of the grid
+fn run(pl: &mut ProgressLogger, intervals: u64) {
+    for _ in 0..intervals {
+        pl.start_phase("interval");
+        pl.inner_step();
+        pl.outer_step();
+        pl.report_now();
+    }
+}
+
+#[test]
+fn footprint_stays_bounded_on_long_runs() {
+    let mut pl = logger();
+    let empty = pl.estimated_memory_footprint();
+    run(&mut pl, 20_000);
+    let long = pl.estimated_memory_footprint();
+    run(&mut pl, 80_000);
+    let longer = pl.estimated_memory_footprint();
+    assert!(long > empty);
+    assert!(longer < BOUND, "{} bytes", longer);
+    // The capped state is full after the first run, and does not grow
+    assert_eq!(longer, long);
+    assert!(pl.samples().len() <= 4096);
+    assert!(pl.heartbeat().unwrap().len() <= 512);
+    assert!(pl.phase_memory_deltas().len() <= 256);
+    assert_eq!(pl.stop().memory_footprint, longer);
+}
+
+#[test]
+fn samples_span_the_whole_run() {
+    let mut pl = logger();
+    run(&mut pl, 20_000);
+    let samples = pl.samples();
+    assert!(samples.len() >= 2048, "{} samples", samples.len());
+    assert_eq!(samples[0].count, 1);
+    assert!(samples.last().unwrap().count > 19_000);
+    pl.stop();
+}
@@ -0,0 +1,76 @@
+//! The process-wide switch of `set_global_enabled`, toggled mid-run. The
+//! switch and the test clock are shared by the whole process: each test
+//! holds `exclusive`.
+#![cfg(feature = "std")]
+
+use progress_logger::test_util::advance;
+use progress_logger::{set_global_enabled, MemorySink, ProgressLogger, SharedSink};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+const FREQUENCY: Duration = Duration::from_millis(10);
+
+fn exclusive() -> MutexGuard<'static, ()> {
+    static SWITCH: Mutex<()> = Mutex::new(());
+    SWITCH.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The number of reports before, while and after the switch is off, with
+/// ten updates each a bit more than the frequency apart in each phase,
+/// and the completion statements
+fn reports_around_the_switch(ignore_global_switch: bool) -> ([usize; 3], usize) {
+    let lines = MemorySink::new();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(FREQUENCY)
+        .with_min_report_spacing(Duration::from_secs(0))
+        .with_memory_reporting(false)
+        .with_ignore_global_switch(ignore_global_switch)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    let mut phase = || {
+        let before = lines.lines().len();
+        for _ in 0..10 {
+            advance(FREQUENCY + Duration::from_millis(1));
+            pl.update(1u32);
+        }
+        lines.lines().len() - before
+    };
+    let before = phase();
+    set_global_enabled(false);
+    let during = phase();
+    set_global_enabled(true);
+    let after = phase();
+    assert_eq!(pl.stop().total_items, 30);
+    let done = lines
+        .lines()
+        .iter()
+        .filter(|line| line.starts_with("Done"))
+        .count();
+    ([before, during, after], done)
+}
+
+#[test]
+fn reports_stop_while_the_switch_is_off() {
+    let _exclusive = exclusive();
+    assert_eq!(reports_around_the_switch(false), ([10, 0, 10], 1));
+}
+
+#[test]
+fn loggers_can_ignore_the_switch() {
+    let _exclusive = exclusive();
+    assert_eq!(reports_around_the_switch(true), ([10, 10, 10], 1));
+}
+
+#[test]
+fn the_completion_statement_follows_the_switch() {
+    let _exclusive = exclusive();
+    let lines = MemorySink::new();
+    let mut pl = ProgressLogger::builder()
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    pl.update(1u32);
+    set_global_enabled(false);
+    pl.stop();
+    set_global_enabled(true);
+    assert!(lines.lines().is_empty(), "{:?}", lines.lines());
+}
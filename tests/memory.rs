@@ -0,0 +1,94 @@
+#![cfg(feature = "std")]
+//! Memory figures beyond 32 bits, which must render the same on 32-bit
+//! targets, e.g. with `cargo test --target i686-unknown-linux-gnu --test memory`.
+//! The memory probe is global, hence a test binary of its own.
+
+use progress_logger::{DigitGrouping, MemorySink, ProgressLogger, SharedSink};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The used memory and swap returned by the probe, in kB
+static USED_KB: AtomicU64 = AtomicU64::new(0);
+static SWAP_KB: AtomicU64 = AtomicU64::new(0);
+
+fn set_memory(used_kb: u64, swap_kb: u64) {
+    USED_KB.store(used_kb, Ordering::Relaxed);
+    SWAP_KB.store(swap_kb, Ordering::Relaxed);
+}
+
+#[test]
+fn memory_beyond_32_bits() {
+    progress_logger::set_memory_probe(Some(|| {
+        (
+            USED_KB.load(Ordering::Relaxed),
+            SWAP_KB.load(Ordering::Relaxed),
+        )
+    }));
+    let run = |compact: bool, phases: &[(&str, u64)]| {
+        let lines = MemorySink::new();
+        let mut pl = ProgressLogger::builder()
+            .with_digit_grouping(DigitGrouping::Comma)
+            .with_compact_numbers(compact)
+            .with_sink(SharedSink::new(lines.clone()))
+            .start();
+        for (name, used_kb) in phases {
+            set_memory(*used_kb, u64::MAX);
+            pl.start_phase(*name);
+        }
+        pl.end_phase();
+        pl.update(1u32);
+        pl.checkpoint();
+        let deltas = pl.phase_memory_deltas();
+        pl.stop();
+        (lines.lines(), deltas)
+    };
+
+    // Around 4 Gi kB, the largest count of kB in 32 bits
+    let (lines, deltas) = run(
+        false,
+        &[
+            ("below", (1 << 32) - 1),
+            ("above", (1 << 32) + 1),
+            ("far", 6 << 30),
+        ],
+    );
+    assert!(
+        lines[0].starts_with("[mem: 6,442,450,944 kB, swap: 18,446,744,073,709,551,615 kB"),
+        "{}",
+        lines[0]
+    );
+    assert_eq!(
+        deltas,
+        vec![
+            ("below".to_owned(), 2),
+            ("above".to_owned(), (2 << 30) - 1),
+            ("far".to_owned(), 0),
+        ]
+    );
+    assert!(
+        lines[1].ends_with("memory by phase: below +2.0 KiB, above +2.0 TiB, far +0.0 KiB"),
+        "{}",
+        lines[1]
+    );
+
+    // The whole u64 range
+    let (lines, deltas) = run(true, &[("grow", 0), ("shrink", u64::MAX), ("end", 0)]);
+    assert!(
+        lines[0].starts_with("[mem: 0 kB, swap: 18.4E kB"),
+        "{}",
+        lines[0]
+    );
+    assert_eq!(
+        deltas,
+        vec![
+            ("grow".to_owned(), i64::MAX),
+            ("shrink".to_owned(), i64::MIN),
+            ("end".to_owned(), 0),
+        ]
+    );
+    assert!(
+        lines[1].ends_with("memory by phase: grow +8.0 ZiB, shrink -8.0 ZiB, end +0.0 KiB"),
+        "{}",
+        lines[1]
+    );
+    progress_logger::set_memory_probe(None);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{
+    DigitGrouping, DisplayOrdering, MemorySink, MultiLoggerDisplay, ProgressLogger, SharedSink,
+};
+use std::sync::{Arc, Mutex};
+
+const NAMES: [&str; 4] = ["plums", "apples", "pears", "figs"];
+
+/// The reports of a display refreshed by the threads updating its
+/// loggers, each report as its status lines and its total
+fn reports(ordering: DisplayOrdering) -> Vec<(Vec<String>, u64)> {
+    let lines = MemorySink::new();
+    let mut display = MultiLoggerDisplay::new()
+        .with_ordering(ordering)
+        .with_sink(SharedSink::new(lines.clone()));
+    for name in NAMES.iter() {
+        display.add(
+            ProgressLogger::builder()
+                .with_items_name(*name)
+                .with_digit_grouping(DigitGrouping::Plain)
+                .with_memory_reporting(false)
+                .start(),
+        );
+    }
+    let display = Arc::new(Mutex::new(display));
+    let workers: Vec<_> = (0..NAMES.len())
+        .map(|slot| {
+            let display = Arc::clone(&display);
+            std::thread::spawn(move || {
+                for i in 0..20_000u32 {
+                    let mut display = display.lock().unwrap();
+                    display.logger(slot).update(slot as u32 + 1);
+                    if i % 1000 == 0 {
+                        display.refresh();
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let mut reports = Vec::new();
+    let mut statuses = Vec::new();
+    for line in lines.lines() {
+        match line.strip_prefix("total: ") {
+            Some(total) => {
+                let total = total.split(' ').next().unwrap().parse().unwrap();
+                reports.push((std::mem::take(&mut statuses), total));
+            }
+            None => statuses.push(line),
+        }
+    }
+    assert!(statuses.is_empty(), "{:?}", statuses);
+    reports
+}
+
+/// The name and count of the logger on a status line
+fn name_and_count(status: &str) -> (&'static str, u64) {
+    NAMES
+        .iter()
+        .filter_map(|&name| {
+            let at = status.find(&format!(" {}", name))?;
+            let count = status[..at].rsplit(' ').next().unwrap();
+            Some((name, count.parse().unwrap()))
+        })
+        .next()
+        .unwrap_or_else(|| panic!("no count in {:?}", status))
+}
+
+#[test]
+fn each_report_totals_the_counts_it_lists() {
+    let reports = reports(DisplayOrdering::Registration);
+    assert_eq!(reports.len(), NAMES.len() * 20);
+    for (statuses, total) in reports.iter() {
+        let counts: Vec<_> = statuses.iter().map(|s| name_and_count(s)).collect();
+        let names: Vec<_> = counts.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, NAMES);
+        assert_eq!(counts.iter().map(|&(_, count)| count).sum::<u64>(), *total);
+    }
+}
+
+#[test]
+fn reports_list_the_loggers_in_the_order_of_their_names() {
+    let mut sorted = NAMES;
+    sorted.sort_unstable();
+    for (statuses, total) in reports(DisplayOrdering::Name).iter() {
+        let counts: Vec<_> = statuses.iter().map(|s| name_and_count(s)).collect();
+        let names: Vec<_> = counts.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, sorted);
+        assert_eq!(counts.iter().map(|&(_, count)| count).sum::<u64>(), *total);
+    }
+}
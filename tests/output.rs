@@ -0,0 +1,346 @@
+//! The text of the messages, as received by a `log` backend, in
+//! representative scenarios. Timings vary from run to run: patterns match
+//! them with `*`, see `matches`.
+#![cfg(all(feature = "std", not(feature = "tracing")))]
+
+use log::Level;
+use progress_logger::test_util::{self, advance};
+use progress_logger::{Progress, ProgressEvent, ProgressLogger, SinkErrorPolicy};
+use std::time::Duration;
+
+/// Runs `f`, returning the levels and messages it logged on this thread
+fn capture<F: FnOnce()>(f: F) -> Vec<(Level, String)> {
+    test_util::capture(f)
+        .into_iter()
+        .map(|record| (record.level, record.message))
+        .collect()
+}
+
+/// Whether `s` matches `pattern`, in which `*` stands for any sequence of
+/// characters
+fn matches(pattern: &str, s: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == s,
+        Some((prefix, rest)) => {
+            s.starts_with(prefix)
+                && (prefix.len()..=s.len())
+                    .filter(|&i| s.is_char_boundary(i))
+                    .any(|i| matches(rest, &s[i..]))
+        }
+    }
+}
+
+fn assert_messages(records: &[(Level, String)], patterns: &[(Level, &str)]) {
+    assert_eq!(records.len(), patterns.len(), "{:#?}", records);
+    for ((level, message), (expected_level, pattern)) in records.iter().zip(patterns) {
+        assert!(
+            *level == *expected_level && matches(pattern, message),
+            "{:?} {:?} does not match {:?} {:?}",
+            level,
+            message,
+            expected_level,
+            pattern
+        );
+    }
+}
+
+/// A logger reporting only when asked to
+fn builder() -> progress_logger::ProgressLoggerBuilder {
+    ProgressLogger::builder()
+        .with_frequency(Duration::from_secs(3600))
+        .with_min_report_spacing(Duration::from_millis(0))
+        .with_memory_reporting(false)
+}
+
+#[test]
+fn without_expectation() {
+    let records = capture(|| {
+        let mut pl = builder().start();
+        pl.update(10u32);
+        pl.report_now();
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Info, "*s 10 updates (* updates/s)"),
+            (Level::Info, "Done in *s. 10 updates (* updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_expectation() {
+    let records = capture(|| {
+        let mut pl = builder().with_expected_updates(100u32).start();
+        pl.update(25u32);
+        pl.report_now();
+        pl.update(75u32);
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Info, "*s 25 updates, * s left (* updates/s)"),
+            (Level::Info, "Done in *s. 100 updates (* updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_overrun() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_items_name("edges")
+            .with_expected_updates(5u32)
+            .start();
+        pl.update(8u32);
+        pl.report_now();
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (
+                Level::Info,
+                "*s 8 edges, 3 edges over the expected 5 (* edges/s)",
+            ),
+            (Level::Info, "Done in *s. 8 edges (* edges/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_zero_items() {
+    let records = capture(|| {
+        builder().with_expected_updates(0u32).start().stop();
+        builder().start().stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Info, "Done in *s, nothing to do."),
+            (Level::Info, "Done in *s. 0 updates (0.00 updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_huge_counts() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_digit_grouping(progress_logger::DigitGrouping::Comma)
+            .start();
+        pl.update(u64::MAX / 2);
+        pl.stop();
+        let mut pl = builder().with_compact_numbers(true).start();
+        pl.update(123_456_789u32);
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (
+                Level::Info,
+                "Done in *s. 9,223,372,036,854,775,807 updates (* updates/s)",
+            ),
+            (Level::Info, "Done in *s. 123M updates (*T updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_memory() {
+    let records = capture(|| {
+        let mut pl = builder().with_memory_reporting(true).start();
+        pl.update(10u32);
+        pl.report_now();
+        pl.finish_silently();
+    });
+    let pattern = if cfg!(feature = "memory") {
+        "[mem: * kB, swap: * kB*] *s 10 updates (* updates/s)"
+    } else {
+        "*s 10 updates (* updates/s)"
+    };
+    assert_messages(&records, &[(Level::Info, pattern)]);
+}
+
+#[test]
+fn with_levels() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_log_level(Level::Debug)
+            .with_completion_level(Level::Info)
+            .start();
+        pl.update(10u32);
+        pl.report_now();
+        pl.note("halfway");
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Debug, "*s 10 updates (* updates/s)"),
+            (Level::Debug, "halfway"),
+            (Level::Info, "Done in *s. 10 updates (* updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_a_short_frequency() {
+    // Advancing the clock past the frequency makes every update report
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_items_name("files")
+            .with_frequency(Duration::from_millis(1))
+            .start();
+        for _ in 0..3 {
+            advance(Duration::from_millis(5));
+            pl.update(1u32);
+        }
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Info, "*s 1 files (* files/s)"),
+            (Level::Info, "*s 2 files (* files/s)"),
+            (Level::Info, "*s 3 files (* files/s)"),
+            (Level::Info, "Done in *s. 3 files (* files/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_a_panicking_hook() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_update_hook(|n| assert!(n < 5, "too large"))
+            .start();
+        pl.update(1u32);
+        pl.update(7u32);
+        pl.update(9u32);
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (
+                Level::Warn,
+                "The update hook of updates panicked, and is no longer called",
+            ),
+            (Level::Info, "Done in *s. 17 updates (* updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_a_panicking_on_log_callback() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_frequency(Duration::from_millis(1))
+            .with_on_log(|_, count, _| assert!(count < 2, "too large"))
+            .start();
+        for _ in 0..3 {
+            advance(Duration::from_millis(5));
+            pl.update(1u32);
+        }
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (Level::Info, "*s 1 updates (* updates/s)"),
+            (
+                Level::Warn,
+                "The on_log hook of updates panicked, and is no longer called",
+            ),
+            (Level::Info, "*s 2 updates (* updates/s)"),
+            (Level::Info, "*s 3 updates (* updates/s)"),
+            (Level::Info, "Done in *s. 3 updates (* updates/s)"),
+        ],
+    );
+}
+
+/// A reporter panicking at the first periodic report
+fn faulty_reporter(event: &ProgressEvent) {
+    assert!(!matches!(event, ProgressEvent::Report { .. }), "unreachable");
+}
+
+#[test]
+fn with_a_panicking_reporter() {
+    let records = capture(|| {
+        let mut pl = builder()
+            .with_frequency(Duration::from_millis(1))
+            .with_reporter(faulty_reporter)
+            .start();
+        for _ in 0..2 {
+            advance(Duration::from_millis(5));
+            pl.update(1u32);
+        }
+        pl.stop();
+    });
+    assert_messages(
+        &records,
+        &[
+            (
+                Level::Warn,
+                "A reporter of updates panicked, and no longer receives events",
+            ),
+            (Level::Info, "*s 1 updates (* updates/s)"),
+            (Level::Info, "*s 2 updates (* updates/s)"),
+            (Level::Info, "Done in *s. 2 updates (* updates/s)"),
+        ],
+    );
+}
+
+#[test]
+fn with_a_panicking_reporter_failing() {
+    let mut pl = builder()
+        .with_frequency(Duration::from_millis(1))
+        .with_reporter(faulty_reporter)
+        .with_sink_error_policy(SinkErrorPolicy::Fail)
+        .start();
+    let failed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        capture(|| {
+            advance(Duration::from_millis(5));
+            pl.update(1u32);
+        })
+    }));
+    assert!(failed.is_err());
+    pl.finish_silently();
+}
+
+#[test]
+fn with_work_units_that_are_not_numbers() {
+    for &compact in &[false, true] {
+        let records = capture(|| {
+            let mut pl = builder().with_compact_numbers(compact).start();
+            // Infinite work units, then infinity minus infinity in the
+            // second interval
+            pl.update_weighted(1, f64::INFINITY);
+            pl.report_now();
+            pl.update_weighted(1, 1.0);
+            pl.report_now();
+            pl.stop();
+        });
+        assert_messages(
+            &records,
+            &[
+                (
+                    Level::Info,
+                    "*s 1 updates (n/a work units, avg n/a per item, last interval n/a per item) (* updates/s, n/a work units/s)",
+                ),
+                (
+                    Level::Info,
+                    "*s 2 updates (n/a work units, avg n/a per item, last interval n/a per item) (* updates/s, n/a work units/s)",
+                ),
+                (
+                    Level::Info,
+                    "Done in *s. 2 updates (n/a work units) (* updates/s, n/a work units/s)",
+                ),
+            ],
+        );
+    }
+}
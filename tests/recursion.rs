@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{Progress, ProgressHandle, ProgressLogger};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Visits a complete binary tree of the given depth, returning its number
+/// of nodes, and calling `at_leaf` on the leaves
+fn visit(depth: u32, pl: &ProgressHandle, at_leaf: &mut dyn FnMut(&ProgressHandle)) -> u64 {
+    pl.update(1u32);
+    if depth == 0 {
+        at_leaf(pl);
+        return 1;
+    }
+    1 + visit(depth - 1, pl, at_leaf) + visit(depth - 1, pl, at_leaf)
+}
+
+#[test]
+fn counts_every_node() {
+    let mut pl = ProgressLogger::builder().start();
+    let nodes = visit(10, &pl.handle(), &mut |_| {});
+    assert_eq!(nodes, 2047);
+    assert_eq!(pl.stop().total_items, 2047);
+}
+
+#[test]
+fn applies_updates_from_within_with_logger() {
+    let mut pl = ProgressLogger::builder().start();
+    let handle = pl.handle();
+    // A recursion below `with_logger` keeps updating through the handle
+    handle.with_logger(|inner| {
+        inner.update(1000u32);
+        assert_eq!(visit(3, &handle, &mut |pl| pl.checkpoint()), 15);
+        assert_eq!(inner.count(), 1000);
+        assert_eq!(handle.count(), 15);
+    });
+    assert_eq!(handle.count(), 1015);
+    // And `with_logger` can be called from the leaves of a recursion
+    let mut seen = Vec::new();
+    visit(3, &handle, &mut |pl| {
+        pl.with_logger(|inner| seen.push(inner.count()))
+    });
+    assert_eq!(seen, [1019, 1020, 1022, 1023, 1026, 1027, 1029, 1030]);
+    drop(handle);
+    assert_eq!(pl.count(), 1030);
+}
+
+#[test]
+fn keeps_updates_when_with_logger_panics() {
+    let mut pl = ProgressLogger::builder().start();
+    {
+        let handle = pl.handle();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            handle.with_logger(|_| {
+                handle.update(7u32);
+                panic!("interrupted");
+            })
+        }));
+        assert!(result.is_err());
+    }
+    assert_eq!(pl.count(), 7);
+}
+
+#[test]
+#[should_panic(expected = "called from its own closure")]
+fn rejects_nested_with_logger() {
+    let mut pl = ProgressLogger::builder().start();
+    let handle = pl.handle();
+    handle.with_logger(|_| handle.with_logger(|_| ()));
+}
+
+#[test]
+fn implements_progress() {
+    fn count_to(n: u64, mut pl: impl Progress) {
+        for _ in 0..n {
+            pl.update_light(1);
+        }
+    }
+
+    let mut pl = ProgressLogger::builder().start();
+    let handle = pl.handle();
+    count_to(100, &handle);
+    handle.with_logger(|_| count_to(10, &handle));
+    drop(handle);
+    assert_eq!(pl.count(), 110);
+}
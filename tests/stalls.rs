@@ -0,0 +1,139 @@
+//! The accounting of stalls, scripted with the test clock.
+#![cfg(all(feature = "std", not(feature = "tracing")))]
+
+use progress_logger::test_util::{advance, capture};
+use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Held by each test, as the test clock is shared by the whole process and
+/// a test advancing it would insert stalls in the others
+fn exclusive_clock() -> MutexGuard<'static, ()> {
+    static CLOCK: Mutex<()> = Mutex::new(());
+    CLOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Updates `pl` every 10ms, with a gap of `stall` in the middle
+fn run_with_stall(stall: Duration, pl: &mut ProgressLogger) {
+    for _ in 0..10 {
+        advance(Duration::from_millis(10));
+        pl.update(1u32);
+    }
+    advance(stall);
+    for _ in 0..10 {
+        pl.update(1u32);
+        advance(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn stalls_between_updates_are_counted() {
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .start();
+    capture(|| run_with_stall(Duration::from_millis(500), &mut pl));
+    assert_eq!(pl.stall_episodes(), 1);
+    let stalled = pl.stalled_duration();
+    assert!(stalled >= Duration::from_millis(500), "{:?}", stalled);
+    assert!(stalled < Duration::from_millis(550), "{:?}", stalled);
+    let report = pl.finish_silently();
+    assert_eq!(report.stall_episodes, 1);
+    assert_eq!(report.stalled_duration, stalled);
+}
+
+#[test]
+fn gaps_shorter_than_the_frequency_are_not_stalls() {
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .start();
+    capture(|| run_with_stall(Duration::from_millis(30), &mut pl));
+    assert_eq!(pl.stall_episodes(), 0);
+    assert_eq!(pl.stalled_duration(), Duration::from_secs(0));
+    pl.finish_silently();
+}
+
+#[test]
+fn pauses_are_not_stalls() {
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .start();
+    capture(|| {
+        pl.update(1u32);
+        pl.pause();
+        advance(Duration::from_millis(500));
+        pl.resume();
+        pl.update(1u32);
+    });
+    assert_eq!(pl.stall_episodes(), 0);
+    pl.finish_silently();
+}
+
+#[test]
+fn stalls_until_the_end_are_counted_at_finish() {
+    let lines = MemorySink::new();
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .with_stall_reports(true)
+        .with_stall_warning_fraction(0.5)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    pl.update(1u32);
+    advance(Duration::from_millis(200));
+    let report = pl.stop();
+    let output = lines.contents();
+    assert!(output.contains("stalled for"), "{}", output);
+    assert_eq!(report.stall_episodes, 1);
+    assert!(report.stalled_duration >= Duration::from_millis(200));
+    let completion = output.lines().last().unwrap();
+    assert!(
+        completion.ends_with("% of wall time across 1 episodes"),
+        "{}",
+        completion
+    );
+}
+
+#[test]
+fn stalls_show_in_the_heartbeat_string() {
+    let _clock = exclusive_clock();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .with_heartbeat_string(true)
+        .start();
+    capture(|| run_with_stall(Duration::from_millis(500), &mut pl));
+    let heartbeat = pl.heartbeat().unwrap();
+    assert_eq!(heartbeat.matches('s').count(), 1, "{}", heartbeat);
+    assert!(heartbeat.starts_with('.'), "{}", heartbeat);
+    pl.finish_silently();
+}
+
+#[test]
+fn stall_reports_show_in_the_heartbeat_string() {
+    let _clock = exclusive_clock();
+    let lines = MemorySink::new();
+    let mut pl = ProgressLogger::builder()
+        .with_frequency(Duration::from_millis(50))
+        .with_memory_reporting(false)
+        .with_heartbeat_string(true)
+        .with_stall_reports(true)
+        .with_sink(SharedSink::new(lines.clone()))
+        .start();
+    pl.update(1u32);
+    for _ in 0..4 {
+        advance(Duration::from_millis(50));
+    }
+    pl.finish();
+    let output = lines.contents();
+    let stall_reports = output.matches("stalled for").count();
+    assert!(stall_reports >= 3, "{}", output);
+    // The reports of the thread, then the interval cut short by `finish`
+    assert_eq!(pl.heartbeat().unwrap(), "s".repeat(stall_reports + 1), "{}", output);
+}
@@ -0,0 +1,119 @@
+//! The events received by a `tracing` subscriber, with the `tracing`
+//! feature.
+#![cfg(feature = "tracing")]
+
+use progress_logger::test_util::advance;
+use progress_logger::{ProgressLogger, LOG_TARGET};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// The fields of a span or event, strings as they are and other values
+/// rendered with `Debug`
+#[derive(Default)]
+struct Fields(Vec<(String, String)>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_owned(), value.to_owned()));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.push((field.name().to_owned(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_owned(), format!("{:?}", value)));
+    }
+}
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The fields of the events received
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Fields>>>);
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == LOG_TARGET
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn reports_carry_their_figures() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut pl = ProgressLogger::builder()
+            .with_items_name("rows")
+            .with_expected_updates(100u32)
+            .with_frequency(Duration::from_millis(0))
+            .with_min_report_spacing(Duration::from_millis(0))
+            .with_memory_reporting(false)
+            .start();
+        for _ in 0..3 {
+            advance(Duration::from_millis(1));
+            pl.update(10u32);
+        }
+        pl.stop();
+    });
+    let events = recorder.0.lock().unwrap();
+    assert!(events.len() >= 2, "{} events", events.len());
+    for event in events.iter() {
+        assert_eq!(event.get("items_name"), Some("rows"));
+        assert_eq!(event.get("log_target"), Some(LOG_TARGET));
+        let count = event.get("count").unwrap();
+        let message = event.get("message").unwrap();
+        assert!(message.contains(count), "{} for {}", message, count);
+        let throughput: f64 = event.get("throughput_per_sec").unwrap().parse().unwrap();
+        assert!(throughput > 0.0);
+    }
+    assert!(events[0].get("ettc_secs").is_some());
+    let completion = events.last().unwrap();
+    assert!(completion.get("message").unwrap().starts_with("Done in"));
+    assert_eq!(completion.get("count"), Some("30"));
+    assert_eq!(completion.get("ettc_secs"), None);
+}
+
+#[test]
+fn the_configured_target_is_a_field() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let pl = ProgressLogger::builder()
+            .with_log_target("progress")
+            .with_memory_reporting(false)
+            .start();
+        pl.stop();
+    });
+    let events = recorder.0.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].get("log_target"), Some("progress"));
+}
@@ -0,0 +1,5 @@
+use progress_logger::ProgressLogger;
+
+fn main() {
+    let _pl = ProgressLogger::builder().with_milestones(&[0.5]).start();
+}
@@ -0,0 +1,43 @@
+//! Two semver-incompatible versions of the crate can be linked in the same
+//! program. Their outputs must tell which version they come from: this
+//! simulates the second version by logging with the target it would use.
+#![cfg(all(feature = "std", not(feature = "tracing")))]
+
+use progress_logger::test_util::capture;
+use progress_logger::{Progress, ProgressLogger, LOG_TARGET, VERSION};
+use std::time::Duration;
+
+#[test]
+fn outputs_identify_the_version() {
+    let mut outputs = None;
+    let records = capture(|| {
+        let mut pl = ProgressLogger::builder()
+            .with_items_name("rows")
+            .with_frequency(Duration::from_millis(0))
+            .with_min_report_spacing(Duration::from_millis(0))
+            .start();
+        let config = pl.config().to_json();
+        pl.update(10u32);
+        pl.report_now();
+        outputs = Some((config, pl.snapshot()));
+        pl.stop();
+        // The same line from the other version
+        log::info!(target: "progress_logger::v0.2", "Done in 1s (10 rows/s)");
+    });
+    let (config, snapshot) = outputs.unwrap();
+
+    let ours: Vec<_> = records.iter().filter(|r| r.target == LOG_TARGET).collect();
+    let theirs: Vec<_> = records.iter().filter(|r| r.target != LOG_TARGET).collect();
+    assert!(ours.len() >= 2, "{:?}", records);
+    assert!(ours.iter().any(|r| r.message.starts_with("Done in")));
+    assert_eq!(theirs.len(), 1);
+    // Both still match a filter on the crate name
+    assert!(records
+        .iter()
+        .all(|r| r.target.starts_with("progress_logger::")));
+
+    let version = format!("\"version\":\"{}\"", VERSION);
+    assert_eq!(snapshot.version, VERSION);
+    assert!(snapshot.to_json().starts_with(&format!("{{{}", version)));
+    assert!(config.starts_with(&format!("{{{}", version)));
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+
+use progress_logger::{MemorySink, ProgressLogger, SharedSink};
+use std::path::{Path, PathBuf};
+
+/// A tree of `dirs` directories of `files` files each, nested `depth` deep
+fn generate_tree(name: &str, dirs: usize, files: usize, depth: usize) -> PathBuf {
+    fn fill(dir: &Path, dirs: usize, files: usize, depth: usize) {
+        std::fs::create_dir_all(dir).unwrap();
+        for i in 0..files {
+            std::fs::write(dir.join(format!("file{}", i)), i.to_string()).unwrap();
+        }
+        if depth > 0 {
+            for i in 0..dirs {
+                fill(&dir.join(format!("dir{}", i)), dirs, files, depth - 1);
+            }
+        }
+    }
+    let root =
+        std::env::temp_dir().join(format!("progress-logger-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    fill(&root, dirs, files, depth);
+    root
+}
+
+fn logger(lines: &MemorySink) -> ProgressLogger {
+    ProgressLogger::builder()
+        .with_items_name("files")
+        .with_sink(SharedSink::new(lines.clone()))
+        .start()
+}
+
+#[test]
+fn discovered_files_set_the_expectation() {
+    // 1 + 3 + 9 directories of 5 files
+    let root = generate_tree("walk", 3, 5, 2);
+    let lines = MemorySink::new();
+    let mut pl = logger(&lines);
+    let mut files: Vec<PathBuf> = pl.walk_dir(&root).collect();
+    assert_eq!(pl.expected_updates(), Some(65));
+    assert_eq!(pl.count(), 65);
+    files.sort();
+    files.dedup();
+    assert_eq!(files.len(), 65);
+    assert!(files.iter().all(|path| path.is_file()));
+    pl.stop();
+    let lines = lines.lines();
+    assert!(
+        lines[0].starts_with(&format!("Discovered 65 files under {} in ", root.display())),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].starts_with("Done"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn the_expectation_adds_to_the_count() {
+    let root = generate_tree("walk-count", 2, 2, 1);
+    let lines = MemorySink::new();
+    let mut pl = logger(&lines);
+    pl.update(10u32);
+    let walk = pl.walk_dir(&root);
+    assert_eq!(walk.discovered(), 6);
+    assert_eq!(walk.count(), 6);
+    assert_eq!(pl.expected_updates(), Some(16));
+    assert_eq!(pl.count(), 16);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn unreadable_entries_are_counted() {
+    let root = std::env::temp_dir().join("progress-logger-walk-missing");
+    let lines = MemorySink::new();
+    let mut pl = logger(&lines);
+    let walk = pl.walk_dir(&root);
+    assert_eq!((walk.discovered(), walk.errors()), (0, 1));
+    assert_eq!(walk.count(), 0);
+    let lines = lines.lines();
+    assert!(
+        lines[0].contains(", 1 entries could not be read (first: "),
+        "{}",
+        lines[0]
+    );
+}